@@ -0,0 +1,106 @@
+// Draws the simulation in a nannou window, one generation per frame, by uploading the grid's
+// colors to a texture. The texture is ordinary wgpu, so it can just as well be fed to a shader.
+//
+// Click to start over with a new seed.
+
+use nannou::{
+    image::{DynamicImage, Rgba, RgbaImage},
+    prelude::*,
+};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use spreading_colors_ca::{
+    config::SimulationConfig,
+    grid::Grid,
+    simulation::{starting_grid, yx_coordinate_pairs},
+};
+
+// Size of the grid in cells, and how many pixels each cell takes up on screen
+const WIDTH: usize = 256;
+const HEIGHT: usize = 256;
+const CELL_PIXELS: u32 = 3;
+
+struct Model {
+    config: SimulationConfig,
+    grid: Grid,
+    rng: StdRng,
+    yx_coordinate_pairs: Vec<[usize; 2]>,
+    texture: wgpu::Texture,
+    // Whether every cell is alive, so there's nothing left to simulate
+    finished: bool,
+}
+
+fn main() {
+    nannou::app(model).update(update).run();
+}
+
+// A fresh simulation with a random seed
+fn restart(config: &SimulationConfig) -> (Grid, StdRng) {
+    let seed = thread_rng().gen();
+    println!("Using seed {seed}");
+    let mut rng = StdRng::seed_from_u64(seed);
+    (starting_grid(config, &mut rng), rng)
+}
+
+// The grid's colors as an image, with the top-left cell at (0, 0)
+fn grid_image(grid: &Grid) -> DynamicImage {
+    let image = RgbaImage::from_fn(grid.width as u32, grid.height as u32, |x, y| {
+        let [red, green, blue] = grid.get_color(y as usize, x as usize).as_slice();
+        Rgba([red, green, blue, u8::MAX])
+    });
+    DynamicImage::ImageRgba8(image)
+}
+
+fn model(app: &App) -> Model {
+    app.new_window()
+        .size(WIDTH as u32 * CELL_PIXELS, HEIGHT as u32 * CELL_PIXELS)
+        .title("spreading colors")
+        .view(view)
+        .mouse_pressed(mouse_pressed)
+        .build()
+        .unwrap();
+
+    let mut config = SimulationConfig {
+        width: WIDTH,
+        height: HEIGHT,
+        starting_live_cells: 4,
+        colorshift: 6,
+        ..SimulationConfig::default()
+    };
+    config.resolve_auto();
+    let (grid, rng) = restart(&config);
+    let texture = wgpu::Texture::from_image(app, &grid_image(&grid));
+    Model {
+        yx_coordinate_pairs: yx_coordinate_pairs(WIDTH, HEIGHT, config.boundary),
+        config,
+        grid,
+        rng,
+        texture,
+        finished: false,
+    }
+}
+
+fn update(app: &App, model: &mut Model, _update: Update) {
+    // Once the grid is full it stays on screen until it's clicked
+    if model.finished {
+        return;
+    }
+    model.finished = !model.grid.step(&model.yx_coordinate_pairs, &mut model.rng);
+    model.texture = wgpu::Texture::from_image(app, &grid_image(&model.grid));
+}
+
+fn mouse_pressed(_app: &App, model: &mut Model, _button: MouseButton) {
+    (model.grid, model.rng) = restart(&model.config);
+    model.finished = false;
+}
+
+fn view(app: &App, model: &Model, frame: Frame) {
+    // Nearest-neighbor sampling keeps every cell a crisp square instead of a blur
+    let sampler = wgpu::SamplerBuilder::new()
+        .min_filter(wgpu::FilterMode::Nearest)
+        .mag_filter(wgpu::FilterMode::Nearest)
+        .into_descriptor();
+    let draw = app.draw().sampler(sampler);
+    let window = app.window_rect();
+    draw.texture(&model.texture).w_h(window.w(), window.h());
+    draw.to_frame(app, &frame).unwrap();
+}
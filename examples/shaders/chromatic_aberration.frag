@@ -0,0 +1,13 @@
+// Chromatic aberration, like a cheap lens: the red and blue channels drift apart towards the edges.
+// Use it with `--shader examples/shaders/chromatic_aberration.frag`.
+
+const float STRENGTH = 0.01;
+
+void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+    vec2 uv = fragCoord / iResolution.xy;
+    vec2 offset = (uv - 0.5) * STRENGTH;
+    float red = texture(iChannel0, uv + offset).r;
+    float green = texture(iChannel0, uv).g;
+    float blue = texture(iChannel0, uv - offset).b;
+    fragColor = vec4(red, green, blue, 1.0);
+}
@@ -0,0 +1,20 @@
+// An old CRT screen: the image bulges out towards the corners, with dark scanlines and a vignette.
+// Use it with `--shader examples/shaders/crt.wgsl`.
+
+const CURVATURE: f32 = 0.12;
+const SCANLINES: f32 = 0.25;
+
+fn mainImage(fragCoord: vec2<f32>) -> vec4<f32> {
+    // Bend the coordinates outwards from the center, more so towards the edges
+    var uv = fragCoord / iResolution.xy * 2.0 - 1.0;
+    uv = uv * (1.0 + CURVATURE * dot(uv, uv));
+    if (abs(uv.x) > 1.0 || abs(uv.y) > 1.0) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    uv = uv * 0.5 + 0.5;
+
+    var color = textureSample(iChannel0, iChannel0Sampler, uv).rgb;
+    color = color * (1.0 - SCANLINES * (0.5 + 0.5 * sin(fragCoord.y * 3.14159)));
+    color = color * (1.0 - 0.5 * pow(length(uv - 0.5) * 1.2, 3.0));
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,181 @@
+// Colors for terminals without 24-bit color: each cell is drawn in the closest color of the
+// 256-color palette most terminals have, or of the 16 basic colors every color terminal has,
+// instead of in escape codes those terminals would show wrong or not at all.
+
+use crate::grid::RgbColor;
+
+// The levels each channel of the 6x6x6 color cube (colors 16 to 231) can have
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+// The first of the 24 grays after the cube (colors 232 to 255), going from 8 up to 238 in steps of 10
+const FIRST_GRAY: u8 = 232;
+const GRAY_LEVELS: u8 = 24;
+
+// The 16 basic colors as xterm shows them by default. Terminals theme these,
+// so they're only a guide to which is closest.
+const BASIC_COLORS: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [205, 0, 0],
+    [0, 205, 0],
+    [205, 205, 0],
+    [0, 0, 238],
+    [205, 0, 205],
+    [0, 205, 205],
+    [229, 229, 229],
+    [127, 127, 127],
+    [255, 0, 0],
+    [0, 255, 0],
+    [255, 255, 0],
+    [92, 92, 255],
+    [255, 0, 255],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+
+/// A color as the terminal is told to show it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnsiColor {
+    /// An exact 24-bit color
+    Rgb(u8, u8, u8),
+    /// A color of the 256-color palette, from 16 up (the first 16 being the basic colors)
+    Indexed(u8),
+    /// One of the 16 basic colors, the last 8 being their bright versions
+    Basic(u8),
+}
+
+impl AnsiColor {
+    /// The color closest to `color` in the 256-color palette. The basic colors at its start are
+    /// left out, since terminal themes change them.
+    pub fn nearest_256(color: RgbColor) -> Self {
+        let rgb = color.as_slice();
+        let cube = rgb.map(|channel| {
+            (0..CUBE_LEVELS.len())
+                .min_by_key(|&i| CUBE_LEVELS[i].abs_diff(channel))
+                .unwrap() as u8
+        });
+        let cube_color = cube.map(|i| CUBE_LEVELS[usize::from(i)]);
+        let cube_index = 16 + 36 * cube[0] + 6 * cube[1] + cube[2];
+
+        let average = rgb.iter().map(|&c| u32::from(c)).sum::<u32>() / 3;
+        let gray = (average.saturating_sub(3) / 10).min(u32::from(GRAY_LEVELS) - 1) as u8;
+        let gray_level = 8 + 10 * gray;
+
+        if distance(rgb, [gray_level; 3]) < distance(rgb, cube_color) {
+            Self::Indexed(FIRST_GRAY + gray)
+        } else {
+            Self::Indexed(cube_index)
+        }
+    }
+
+    /// The basic color closest to `color`
+    pub fn nearest_16(color: RgbColor) -> Self {
+        let rgb = color.as_slice();
+        let index = (0..BASIC_COLORS.len())
+            .min_by_key(|&i| distance(rgb, BASIC_COLORS[i]))
+            .unwrap();
+        Self::Basic(index as u8)
+    }
+
+    /// `text` in this color, as escape codes
+    pub fn paint(self, text: &str) -> String {
+        let code = match self {
+            Self::Rgb(red, green, blue) => format!("38;2;{red};{green};{blue}"),
+            Self::Indexed(index) => format!("38;5;{index}"),
+            Self::Basic(index @ 0..8) => (30 + index).to_string(),
+            Self::Basic(index) => (90 + index - 8).to_string(),
+        };
+        format!("\x1B[{code}m{text}\x1B[0m")
+    }
+
+    /// `text` on a background of this color, as escape codes
+    pub fn paint_background(self, text: &str) -> String {
+        let code = match self {
+            Self::Rgb(red, green, blue) => format!("48;2;{red};{green};{blue}"),
+            Self::Indexed(index) => format!("48;5;{index}"),
+            Self::Basic(index @ 0..8) => (40 + index).to_string(),
+            Self::Basic(index) => (100 + index - 8).to_string(),
+        };
+        format!("\x1B[{code}m{text}\x1B[0m")
+    }
+}
+
+// How far apart two colors are, as the squared distance between them
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(b)
+        .map(|(&a, b)| u32::from(a.abs_diff(b)).pow(2))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb([red, green, blue]: [u8; 3]) -> RgbColor {
+        RgbColor { red, green, blue }
+    }
+
+    #[test]
+    fn nearest_256_picks_cube_colors_and_grays() {
+        for (color, index) in [
+            ([0, 0, 0], 16),
+            ([255, 255, 255], 231),
+            ([255, 0, 0], 196),
+            ([0, 95, 135], 24),
+            // The boundary between the cube's first two levels is halfway from 0 to 95
+            ([47, 0, 255], 21),
+            ([48, 0, 255], 57),
+            // Ties go to the lower level
+            ([255, 115, 0], 202),
+            ([255, 116, 0], 208),
+            // Grays between the cube's levels are closer to one of the 24 grays
+            ([8, 8, 8], 232),
+            ([128, 128, 128], 244),
+            ([238, 238, 238], 255),
+            ([135, 135, 135], 102),
+        ] {
+            assert_eq!(
+                AnsiColor::nearest_256(rgb(color)),
+                AnsiColor::Indexed(index),
+                "{color:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn nearest_16_picks_basic_colors() {
+        for (color, index) in [
+            ([0, 0, 0], 0),
+            ([205, 0, 0], 1),
+            ([128, 128, 128], 8),
+            ([255, 0, 0], 9),
+            ([92, 92, 255], 12),
+            ([250, 250, 250], 15),
+            ([20, 200, 30], 2),
+        ] {
+            assert_eq!(
+                AnsiColor::nearest_16(rgb(color)),
+                AnsiColor::Basic(index),
+                "{color:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn colors_paint_with_their_escape_codes() {
+        assert_eq!(
+            AnsiColor::Rgb(1, 2, 3).paint("x"),
+            "\x1B[38;2;1;2;3mx\x1B[0m"
+        );
+        assert_eq!(AnsiColor::Indexed(196).paint("x"), "\x1B[38;5;196mx\x1B[0m");
+        assert_eq!(AnsiColor::Basic(3).paint("x"), "\x1B[33mx\x1B[0m");
+        assert_eq!(AnsiColor::Basic(9).paint("x"), "\x1B[91mx\x1B[0m");
+        assert_eq!(
+            AnsiColor::Basic(3).paint_background(" "),
+            "\x1B[43m \x1B[0m"
+        );
+        assert_eq!(
+            AnsiColor::Basic(9).paint_background(" "),
+            "\x1B[101m \x1B[0m"
+        );
+    }
+}
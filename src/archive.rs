@@ -0,0 +1,269 @@
+// Frame archives: every captured frame of a run in a single file, each compressed with zstd on its
+// own, followed by an index of where each one is. Frames of a cellular automaton change little
+// from one to the next, so they compress far better than a directory of PNGs, and the index lets
+// any frame be read without decompressing the ones before it.
+//
+//   "SCFRAMES" and a format version byte
+//   width and height of the frames (u32 each)
+//   the frames, each a zstd frame of its RGB bytes row by row
+//   the index: the number of frames (u64), then for each frame its generation, the offset of its
+//   data from the start of the file and the data's length (u64 each)
+//   the offset of the index (u64)
+//
+// Numbers are little-endian. The index is only written once the run is over, so an archive of a
+// run that was killed can't be read.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use image::RgbImage;
+
+const MAGIC: &[u8] = b"SCFRAMES";
+const FORMAT_VERSION: u8 = 1;
+
+// Bytes before the first frame
+const HEADER_BYTES: u64 = MAGIC.len() as u64 + 1 + 4 + 4;
+
+// How hard frames are compressed, from 1 (fastest) to 22 (smallest). The default level is quick
+// enough to keep up with runs, and the frames compress well anyway.
+const COMPRESSION_LEVEL: i32 = zstd::DEFAULT_COMPRESSION_LEVEL;
+
+/// Where a frame is in an archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// The generation the frame was captured on
+    pub generation: u64,
+    /// Where the frame's compressed data starts, from the start of the file
+    pub offset: u64,
+    /// How long the frame's compressed data is
+    pub length: u64,
+}
+
+/// An archive frames are being written to
+pub struct ArchiveWriter {
+    file: BufWriter<File>,
+    width: u32,
+    height: u32,
+    index: Vec<IndexEntry>,
+    // Where the next frame goes
+    offset: u64,
+}
+
+impl ArchiveWriter {
+    /// Starts an archive of frames of (width, height) pixels at `path`, creating any missing
+    /// directories on the way
+    pub fn create(path: &Path, (width, height): (u32, u32)) -> io::Result<Self> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&[FORMAT_VERSION])?;
+        file.write_all(&width.to_le_bytes())?;
+        file.write_all(&height.to_le_bytes())?;
+        Ok(Self {
+            file,
+            width,
+            height,
+            index: Vec::new(),
+            offset: HEADER_BYTES,
+        })
+    }
+
+    /// Compresses `frame`, captured on `generation`, into the archive
+    pub fn write_frame(&mut self, frame: &RgbImage, generation: u64) -> io::Result<()> {
+        if frame.dimensions() != (self.width, self.height) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "frames of an archive all have to be the same size",
+            ));
+        }
+        let data = zstd::bulk::compress(frame.as_raw(), COMPRESSION_LEVEL)?;
+        self.file.write_all(&data)?;
+        self.index.push(IndexEntry {
+            generation,
+            offset: self.offset,
+            length: data.len() as u64,
+        });
+        self.offset += data.len() as u64;
+        Ok(())
+    }
+
+    /// Writes the index, which the archive can't be read without
+    pub fn finish(mut self) -> io::Result<()> {
+        self.file
+            .write_all(&(self.index.len() as u64).to_le_bytes())?;
+        for entry in &self.index {
+            for number in [entry.generation, entry.offset, entry.length] {
+                self.file.write_all(&number.to_le_bytes())?;
+            }
+        }
+        self.file.write_all(&self.offset.to_le_bytes())?;
+        self.file.flush()
+    }
+}
+
+/// An archive read back, whose frames are decompressed as they're asked for
+pub struct ArchiveReader {
+    file: BufReader<File>,
+    /// Width of the frames
+    pub width: u32,
+    /// Height of the frames
+    pub height: u32,
+    /// Where each frame is, in the order they were captured
+    pub index: Vec<IndexEntry>,
+}
+
+impl ArchiveReader {
+    /// Opens the archive at `path` and reads its index
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+        let mut file = BufReader::new(File::open(path)?);
+
+        let mut header = [0; HEADER_BYTES as usize];
+        file.read_exact(&mut header)?;
+        let rest = header
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| invalid("it isn't a frame archive"))?;
+        if rest[0] != FORMAT_VERSION {
+            return Err(invalid(
+                "it was written in a format version that can't be read",
+            ));
+        }
+        let width = u32::from_le_bytes([rest[1], rest[2], rest[3], rest[4]]);
+        let height = u32::from_le_bytes([rest[5], rest[6], rest[7], rest[8]]);
+
+        // Check the index against the file's size before trusting any number in it, so a damaged
+        // archive can't ask for a huge allocation
+        let file_bytes = file.get_ref().metadata()?.len();
+        file.seek(SeekFrom::End(-8))?;
+        let index_offset = read_u64(&mut file)?;
+        if index_offset < HEADER_BYTES || index_offset > file_bytes - 8 {
+            return Err(invalid("its index is damaged"));
+        }
+        file.seek(SeekFrom::Start(index_offset))?;
+        let count = read_u64(&mut file)?;
+        let index_bytes = count
+            .checked_mul(3 * 8)
+            .and_then(|bytes| bytes.checked_add(2 * 8));
+        if index_bytes != Some(file_bytes - index_offset) {
+            return Err(invalid("its index is damaged"));
+        }
+        let index: Vec<IndexEntry> = (0..count)
+            .map(|_| {
+                Ok(IndexEntry {
+                    generation: read_u64(&mut file)?,
+                    offset: read_u64(&mut file)?,
+                    length: read_u64(&mut file)?,
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        if index.iter().any(|entry| {
+            entry.offset < HEADER_BYTES
+                || entry
+                    .offset
+                    .checked_add(entry.length)
+                    .is_none_or(|end| end > index_offset)
+        }) {
+            return Err(invalid("its index points outside the frames"));
+        }
+        Ok(Self {
+            file,
+            width,
+            height,
+            index,
+        })
+    }
+
+    /// Decompresses the `n`th frame, counting from 0
+    pub fn read_frame(&mut self, n: usize) -> io::Result<RgbImage> {
+        let entry = self.index.get(n).copied().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "the archive has no such frame")
+        })?;
+        self.file.seek(SeekFrom::Start(entry.offset))?;
+        let mut data = vec![0; entry.length as usize];
+        self.file.read_exact(&mut data)?;
+        let pixels = self.width as usize * self.height as usize * 3;
+        let raw = zstd::bulk::decompress(&data, pixels)?;
+        RgbImage::from_raw(self.width, self.height, raw)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "a frame is the wrong size"))
+    }
+}
+
+// Reads a little-endian u64
+fn read_u64(file: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    file.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_path;
+
+    // Frames whose pixels depend on their number, so they all differ
+    fn frame(n: u8) -> RgbImage {
+        RgbImage::from_fn(5, 3, |x, y| image::Rgb([n, x as u8, y as u8]))
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let path = temp_path("archive_round_trip.scframes");
+        let mut writer = ArchiveWriter::create(&path, (5, 3)).unwrap();
+        for n in 0..4 {
+            writer.write_frame(&frame(n), u64::from(n) * 10).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut reader = ArchiveReader::open(&path).unwrap();
+        assert_eq!((reader.width, reader.height), (5, 3));
+        let generations: Vec<u64> = reader.index.iter().map(|entry| entry.generation).collect();
+        assert_eq!(generations, [0, 10, 20, 30]);
+        // Frames can be read in any order
+        for n in [3, 0, 2, 1] {
+            assert_eq!(reader.read_frame(n as usize).unwrap(), frame(n));
+        }
+        assert!(reader.read_frame(4).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn frames_of_another_size_are_refused() {
+        let path = temp_path("archive_wrong_size.scframes");
+        let mut writer = ArchiveWriter::create(&path, (4, 4)).unwrap();
+        assert!(writer.write_frame(&frame(0), 0).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn damaged_indexes_are_refused() {
+        let path = temp_path("archive_damaged.scframes");
+        let mut writer = ArchiveWriter::create(&path, (5, 3)).unwrap();
+        writer.write_frame(&frame(1), 0).unwrap();
+        writer.finish().unwrap();
+        let archive = fs::read(&path).unwrap();
+        let index_offset = archive.len() - 8 - 3 * 8 - 8;
+
+        // A frame count far past what the file holds
+        let mut huge_count = archive.clone();
+        huge_count[index_offset..index_offset + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        // A frame whose length runs past the end of the file
+        let mut huge_length = archive.clone();
+        let length = index_offset + 8 + 2 * 8;
+        huge_length[length..length + 8].copy_from_slice(&(u64::MAX / 2).to_le_bytes());
+        // An index offset past the end of the file
+        let mut bad_offset = archive.clone();
+        let end = bad_offset.len();
+        bad_offset[end - 8..].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        for damaged in [huge_count, huge_length, bad_offset, archive[..20].to_vec()] {
+            fs::write(&path, damaged).unwrap();
+            assert!(ArchiveReader::open(&path).is_err());
+        }
+        fs::remove_file(path).unwrap();
+    }
+}
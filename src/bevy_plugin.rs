@@ -0,0 +1,137 @@
+//! A Bevy plugin that runs the simulation as a living texture, behind the `bevy` feature.
+//! Every frame the grid advances one generation and its colors are written into an `Image`
+//! asset, which can be put on any material or sprite:
+//!
+//! ```ignore
+//! App::new()
+//!     .add_plugins((DefaultPlugins, SpreadingColorsPlugin::new(config)))
+//!     .add_systems(Startup, |mut commands: Commands, colors: Res<SpreadingColors>| {
+//!         commands.spawn(Sprite::from_image(colors.image.clone()));
+//!     })
+//!     .run();
+//! ```
+
+use bevy::{
+    asset::RenderAssetUsages,
+    image::ImageSampler,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+use crate::{
+    config::SimulationConfig,
+    grid::Grid,
+    simulation::{starting_grid, yx_coordinate_pairs},
+};
+
+/// Runs a simulation from `config`, available as the `SpreadingColors` resource from `Startup` on
+pub struct SpreadingColorsPlugin {
+    config: SimulationConfig,
+}
+
+impl SpreadingColorsPlugin {
+    /// A plugin simulating `config`, with a random seed unless it has one
+    pub fn new(config: SimulationConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl Plugin for SpreadingColorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings(self.config.clone()))
+            .add_systems(PreStartup, setup)
+            .add_systems(Update, step);
+    }
+}
+
+/// The running simulation and the image it's drawn into
+#[derive(Resource)]
+pub struct SpreadingColors {
+    /// The grid's colors as an sRGB image, one pixel per cell, updated every frame
+    pub image: Handle<Image>,
+    /// The seed the simulation was started with, to reproduce it later
+    pub seed: u64,
+    grid: Grid,
+    rng: StdRng,
+    yx_coordinate_pairs: Vec<[usize; 2]>,
+    finished: bool,
+}
+
+impl SpreadingColors {
+    /// Number of generations simulated so far
+    pub fn generation(&self) -> u64 {
+        self.grid.generation
+    }
+
+    /// Whether every cell is alive, so the image won't change anymore
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+}
+
+// The settings the plugin was added with, until `setup` starts the simulation
+#[derive(Resource)]
+struct Settings(SimulationConfig);
+
+// Writes the grid's colors into `pixels` as RGBA, row by row from the top-left corner
+fn write_pixels(grid: &Grid, pixels: &mut [u8]) {
+    for (pixel, (_, color, _)) in pixels.chunks_exact_mut(4).zip(grid.cells()) {
+        let [red, green, blue] = color.as_slice();
+        pixel.copy_from_slice(&[red, green, blue, u8::MAX]);
+    }
+}
+
+// Places the starting cells and creates the image, before any of the app's own startup systems
+fn setup(mut commands: Commands, settings: Res<Settings>, mut images: ResMut<Assets<Image>>) {
+    let mut config = settings.0.clone();
+    config.resolve_auto();
+    let seed = *config.seed.get_or_insert_with(|| thread_rng().gen());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let grid = starting_grid(&config, &mut rng);
+
+    let size = Extent3d {
+        width: config.width as u32,
+        height: config.height as u32,
+        depth_or_array_layers: 1,
+    };
+    let mut image = Image::new_fill(
+        size,
+        TextureDimension::D2,
+        &[0, 0, 0, u8::MAX],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    // Every cell stays a crisp square when the texture is scaled up
+    image.sampler = ImageSampler::nearest();
+    if let Some(pixels) = image.data.as_mut() {
+        write_pixels(&grid, pixels);
+    }
+
+    commands.remove_resource::<Settings>();
+    commands.insert_resource(SpreadingColors {
+        image: images.add(image),
+        seed,
+        grid,
+        rng,
+        yx_coordinate_pairs: yx_coordinate_pairs(config.width, config.height, config.boundary),
+        finished: false,
+    });
+}
+
+// Advances the simulation by a generation and redraws the image
+fn step(mut colors: ResMut<SpreadingColors>, mut images: ResMut<Assets<Image>>) {
+    if colors.finished {
+        return;
+    }
+    let colors = &mut *colors;
+    colors.finished = !colors
+        .grid
+        .step(&colors.yx_coordinate_pairs, &mut colors.rng);
+    if let Some(pixels) = images
+        .get_mut(&colors.image)
+        .and_then(|image| image.data.as_mut())
+    {
+        write_pixels(&colors.grid, pixels);
+    }
+}
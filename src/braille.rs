@@ -0,0 +1,60 @@
+// Braille rendering: each character packs a block of 2x4 cells as Braille dots, raised for the live
+// ones, so grids many times bigger than the terminal can still be watched in it. A character only
+// has one color, which is taken from the colony with the most live cells in its block.
+
+use crate::grid::{Grid, RgbColor};
+
+/// How many cells each Braille character stands for, across and down
+pub const BRAILLE_CELLS: (usize, usize) = (2, 4);
+
+// The dot standing for each cell of a block, by row and then column, as Unicode numbers them
+const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+// The Braille character without any dots raised. The other patterns follow it.
+const BLANK: u32 = 0x2800;
+
+/// The Braille character for the block of cells whose top-left cell is (y, x), and its color.
+/// Blocks without live cells are drawn as a space, in the color of their top-left cell.
+pub fn block(grid: &Grid, y: usize, x: usize) -> (char, RgbColor) {
+    let mut dots = 0;
+    // Each colony in the block, with how many of its cells are in it and their colors summed up
+    let mut colonies: Vec<(u32, usize, [u32; 3])> = Vec::new();
+    let border = grid.boundary().border();
+    for (dy, row) in DOTS.iter().enumerate() {
+        for (dx, dot) in row.iter().enumerate() {
+            let (y, x) = (y + dy, x + dx);
+            if y >= grid.height - border || x >= grid.width - border || !grid.alive_states[[y, x]] {
+                continue;
+            }
+            dots |= dot;
+
+            let colony = grid.get_colony(y, x);
+            let index = match colonies.iter().position(|(c, ..)| *c == colony) {
+                Some(index) => index,
+                None => {
+                    colonies.push((colony, 0, [0; 3]));
+                    colonies.len() - 1
+                }
+            };
+            let (_, count, sum) = &mut colonies[index];
+            *count += 1;
+            for (sum, channel) in sum.iter_mut().zip(grid.get_color(y, x).as_slice()) {
+                *sum += u32::from(channel);
+            }
+        }
+    }
+
+    // Ties go to the colony found first, reading the block row by row
+    let dominant = colonies.iter().rev().max_by_key(|(_, count, _)| *count);
+    match dominant {
+        Some(&(_, count, [red, green, blue])) => {
+            let count = count as u32;
+            let color = RgbColor {
+                red: (red / count) as u8,
+                green: (green / count) as u8,
+                blue: (blue / count) as u8,
+            };
+            (char::from_u32(BLANK + dots).unwrap(), color)
+        }
+        None => (' ', grid.get_color(y, x)),
+    }
+}
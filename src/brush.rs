@@ -0,0 +1,95 @@
+use std::fmt::Display;
+
+use crate::{
+    grid::{Grid, RgbColor},
+    i18n::t,
+    terminal::renderer,
+};
+
+const MAX_BRUSH_RADIUS: usize = 32;
+
+/// What a brush stroke does to the cells it touches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrushTool {
+    /// Kills cells, leaving room for the surrounding colonies to grow back in
+    Erase,
+    /// Brings cells to life in the brush color, seeding new growth from them
+    Paint,
+}
+
+/// A round brush for editing the grid while the simulation runs
+#[derive(Debug, Clone, Copy)]
+pub struct Brush {
+    pub tool: BrushTool,
+    pub radius: usize,
+    pub color: RgbColor,
+}
+
+impl Default for Brush {
+    fn default() -> Self {
+        Self {
+            tool: BrushTool::Erase,
+            radius: 2,
+            color: RgbColor::from([255, 255, 255]),
+        }
+    }
+}
+
+impl Brush {
+    pub fn toggle_tool(&mut self) {
+        self.tool = match self.tool {
+            BrushTool::Erase => BrushTool::Paint,
+            BrushTool::Paint => BrushTool::Erase,
+        };
+    }
+
+    pub fn grow(&mut self) {
+        self.radius = (self.radius + 1).min(MAX_BRUSH_RADIUS);
+    }
+
+    pub fn shrink(&mut self) {
+        self.radius = self.radius.saturating_sub(1);
+    }
+
+    /// Applies the brush to every simulated cell within `radius` of (`y`, `x`)
+    pub fn apply(&self, grid: &mut Grid, y: usize, x: usize) {
+        let radius = self.radius as isize;
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                if dy * dy + dx * dx > radius * radius {
+                    continue;
+                }
+                let (Some(cell_y), Some(cell_x)) =
+                    (y.checked_add_signed(dy), x.checked_add_signed(dx))
+                else {
+                    continue;
+                };
+                // The border, if there is one, isn't part of the simulation
+                let border = grid.boundary().border();
+                if !(border..grid.height - border).contains(&cell_y)
+                    || !(border..grid.width - border).contains(&cell_x)
+                {
+                    continue;
+                }
+
+                match self.tool {
+                    BrushTool::Erase => grid.kill_cell(cell_y, cell_x),
+                    BrushTool::Paint => grid.paint_cell(cell_y, cell_x, self.color),
+                }
+            }
+        }
+    }
+}
+
+impl Display for Brush {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.tool {
+            BrushTool::Erase => write!(f, "{}", t!("animation.brush_erase", size = self.radius)),
+            BrushTool::Paint => {
+                let swatch = renderer().cell(self.color).repeat(2);
+                let paint = t!("animation.brush_paint", swatch = swatch, size = self.radius);
+                write!(f, "{paint}")
+            }
+        }
+    }
+}
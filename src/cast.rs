@@ -0,0 +1,52 @@
+// Recording of animations as asciinema casts (https://docs.asciinema.org/manual/asciicast/v2/),
+// so they can be shared and replayed without running the simulation again. A cast is a line of
+// JSON describing the terminal, followed by a line for each chunk of output with its time.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::Instant,
+};
+
+use chrono::Utc;
+use serde_json::json;
+
+/// An asciinema cast being written, which frames are recorded to as they're drawn
+pub struct Cast {
+    file: BufWriter<File>,
+    started: Instant,
+}
+
+impl Cast {
+    /// Starts a cast at `path` for a terminal of (columns, rows) characters
+    pub fn create(path: &Path, (columns, rows): (usize, usize)) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = BufWriter::new(File::create(path)?);
+        let header = json!({
+            "version": 2,
+            "width": columns,
+            "height": rows,
+            "timestamp": Utc::now().timestamp(),
+            "env": { "TERM": "xterm-256color" },
+        });
+        writeln!(file, "{header}")?;
+        Ok(Self {
+            file,
+            started: Instant::now(),
+        })
+    }
+
+    /// Records `output` as printed to the terminal now
+    pub fn record(&mut self, output: &str) -> io::Result<()> {
+        let time = self.started.elapsed().as_secs_f64();
+        writeln!(self.file, "{}", json!([time, "o", output]))
+    }
+
+    /// Writes out whatever of the cast is still buffered
+    pub fn finish(mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
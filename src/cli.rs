@@ -0,0 +1,839 @@
+use std::path::PathBuf;
+
+use clap::{ArgAction, Args, Parser, Subcommand};
+use clap_complete::Shell;
+
+use crate::{
+    color_space::ColorSpace,
+    compare::Setting,
+    config::{Boundary, Neighborhood, SimulationConfig, MAX_SPREAD_RADIUS},
+    entropy::EntropySource,
+    estimate::{parse_byte_size, MAX_EXPORT_SIZE_DEFAULT},
+    export::{HashNames, Layer, Orientation, Overwrite},
+    format::FormatOptions,
+    glyphs::{parse_glyph, Palette},
+    history::HISTORY_SIZE_DEFAULT,
+    metadata::Metadata,
+    monitors::MonitorLayout,
+    palettes::{ColorPalette, Theme},
+    resolution::GridSize,
+    simulation::{DrawEvery, PreviewEvery},
+    swatches::{parse_palette_path, PALETTE_COLORS_DEFAULT},
+    sweep::SweepRange,
+    terminal::{Capabilities, CellStyle, Renderer},
+    timelapse::{SamplingMode, TIMELAPSE_FRAMES_DEFAULT},
+    video::parse_fps,
+    vision::ColorBlindness,
+    volume::DEPTH_DEFAULT,
+};
+
+/// A cellular automaton that produces pretty images from spreading colors
+///
+/// Every flag can also be set through its `SPREADING_COLORS_*` environment variable.
+/// Flags take precedence over environment variables.
+#[derive(Debug, Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Print nothing but errors (and prompts, if there are any)
+    #[arg(
+        short,
+        long,
+        global = true,
+        conflicts_with = "verbose",
+        env = "SPREADING_COLORS_QUIET"
+    )]
+    pub quiet: bool,
+
+    /// Print more details: -v shows every orphan that's spawned, -vv every cell that spreads too
+    #[arg(short, long, global = true, action = ArgAction::Count, env = "SPREADING_COLORS_VERBOSE")]
+    pub verbose: u8,
+
+    // Running without a subcommand is the same as `run`
+    #[command(flatten)]
+    pub run: RunArgs,
+}
+
+impl Cli {
+    /// The command to execute, defaulting to `run` with the top-level flags
+    pub fn into_command(self) -> Command {
+        self.command.unwrap_or(Command::Run(self.run))
+    }
+}
+
+// Only one command is ever parsed, so the size of the biggest one doesn't matter
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run a simulation (the default when no subcommand is given)
+    Run(RunArgs),
+    /// Render jobs from a shared directory until it's empty, or forever with --watch
+    Worker(WorkerArgs),
+    /// Render one image for every combination of the given setting ranges
+    Sweep(SweepArgs),
+    /// Run two simulations that differ by a few settings, or load two saved runs,
+    /// and draw them side by side in the terminal
+    Compare(CompareArgs),
+    /// List or inspect the growth rules, including the ones from plugins
+    Rules(RulesArgs),
+    /// Print a completion script for your shell, e.g. `spreading_colors_ca completions bash`
+    ///
+    /// The names of your presets are completed too, as they were when the script was made.
+    Completions(CompletionsArgs),
+    /// Check the terminal, output directory, memory and build, and run a short simulation,
+    /// summing up whether everything is ready. Worth including in bug reports
+    Doctor(DoctorArgs),
+    /// Grow the colors through a 3D block of cells, each spreading to the 26 around it, and save
+    /// it slice by slice or as an animation sweeping through it
+    Volume(VolumeArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct VolumeArgs {
+    /// JSON or TOML file with the settings to grow the volume with (`-` reads it from stdin).
+    /// The neighborhood and spread radius carry over into three dimensions, and a wrapping
+    /// boundary wraps every face
+    #[arg(env = "SPREADING_COLORS_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Profile from the config file to use, e.g. `--profile wallpaper` for its
+    /// `[profile.wallpaper]` section. Its settings replace the ones at the top of the file
+    #[arg(long, requires = "config", env = "SPREADING_COLORS_PROFILE")]
+    pub profile: Option<String>,
+
+    /// Width of the volume in cells
+    #[arg(long, env = "SPREADING_COLORS_WIDTH")]
+    pub width: Option<usize>,
+
+    /// Height of the volume in cells
+    #[arg(long, env = "SPREADING_COLORS_HEIGHT")]
+    pub height: Option<usize>,
+
+    /// How many cells deep the volume is, which is how many slices it's saved as
+    #[arg(
+        long,
+        default_value_t = DEPTH_DEFAULT,
+        value_parser = clap::value_parser!(u32).range(1..),
+        env = "SPREADING_COLORS_DEPTH"
+    )]
+    pub depth: u32,
+
+    /// Seed for the random number generator, to grow the same volume again
+    #[arg(long, env = "SPREADING_COLORS_SEED")]
+    pub seed: Option<u64>,
+
+    /// Save each slice of the volume from front to back in this directory, as slice_0001.png,
+    /// slice_0002.png, ..., for stacking in 3D tools
+    #[arg(
+        long,
+        value_name = "DIR",
+        required_unless_present = "sweep",
+        env = "SPREADING_COLORS_SLICES"
+    )]
+    pub slices: Option<PathBuf>,
+
+    /// Save an animation sweeping through the volume from front to back, a slice per frame: a
+    /// GIF, an APNG when it's named .png or .apng, or a web page when it's named .html
+    #[arg(long, value_name = "PATH", env = "SPREADING_COLORS_SWEEP")]
+    pub sweep: Option<PathBuf>,
+
+    /// Slices per second the --sweep plays at
+    #[arg(
+        long,
+        default_value_t = 12.0,
+        value_name = "FPS",
+        value_parser = parse_fps,
+        requires = "sweep",
+        env = "SPREADING_COLORS_SWEEP_FPS"
+    )]
+    pub sweep_fps: f64,
+
+    /// Scale each cell of the slices up to a square of this many pixels
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_name = "PIXELS",
+        value_parser = clap::value_parser!(u32).range(1..),
+        env = "SPREADING_COLORS_SCALE"
+    )]
+    pub scale: u32,
+
+    /// Don't ask before volumes estimated to exceed --max-export-size, skip them instead
+    #[arg(short, long, env = "SPREADING_COLORS_YES")]
+    pub yes: bool,
+
+    /// Volumes whose slices are estimated to be bigger than this (e.g. 500MB, 2GB) need
+    /// confirming
+    #[arg(long, default_value = MAX_EXPORT_SIZE_DEFAULT, value_parser = parse_byte_size, env = "SPREADING_COLORS_MAX_EXPORT_SIZE")]
+    pub max_export_size: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct RulesArgs {
+    #[command(subcommand)]
+    pub command: RulesCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RulesCommand {
+    /// List every rule, built-in ones first and then the ones from plugins
+    List,
+}
+
+#[derive(Debug, Args)]
+pub struct DoctorArgs {
+    /// Directory images would be saved in, which is checked for being writable
+    #[arg(
+        long,
+        default_value = "output_images",
+        env = "SPREADING_COLORS_OUT_DIR"
+    )]
+    pub out_dir: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct CompletionsArgs {
+    /// Shell to generate completions for
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+#[derive(Debug, Args)]
+pub struct SweepArgs {
+    /// JSON or TOML file with the settings that stay the same throughout the sweep
+    /// (`-` reads it from stdin)
+    #[arg(env = "SPREADING_COLORS_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Profile from the config file to use, e.g. `--profile wallpaper` for its
+    /// `[profile.wallpaper]` section. Its settings replace the ones at the top of the file
+    #[arg(long, requires = "config", env = "SPREADING_COLORS_PROFILE")]
+    pub profile: Option<String>,
+
+    /// A setting and the range of values to sweep it through, both ends included,
+    /// e.g. `--vary colorshift=1..16 --vary "spread_chance=0.1..0.9 step 0.1"`.
+    /// Sweepable settings: width, height, starting_live_cells, colorshift, spread_chance, temperature
+    #[arg(long, required = true, value_name = "SETTING=START..END[ step STEP]")]
+    pub vary: Vec<SweepRange>,
+
+    /// Seed shared by every image, so they only differ by their settings
+    #[arg(long, env = "SPREADING_COLORS_SEED")]
+    pub seed: Option<u64>,
+
+    /// Don't ask before sweeps estimated to exceed --max-export-size, skip them instead
+    #[arg(short, long, env = "SPREADING_COLORS_YES")]
+    pub yes: bool,
+
+    /// Sweeps estimated to be bigger than this (e.g. 500MB, 2GB) need confirming
+    #[arg(long, default_value = MAX_EXPORT_SIZE_DEFAULT, value_parser = parse_byte_size, env = "SPREADING_COLORS_MAX_EXPORT_SIZE")]
+    pub max_export_size: u64,
+
+    /// Base filename of the images, which get each combination's settings appended,
+    /// e.g. image_colorshift4_spread_chance0.3.png
+    #[arg(short, long, env = "SPREADING_COLORS_OUTPUT")]
+    pub output: Option<String>,
+
+    /// What to do when an image would be saved over an existing file
+    #[arg(long, value_enum, default_value_t = Overwrite::Increment, env = "SPREADING_COLORS_OVERWRITE")]
+    pub overwrite: Overwrite,
+
+    /// Name images after a hash of their pixels or settings, as with `run --hash-names`.
+    /// With `settings`, combinations that were already saved aren't rendered again
+    #[arg(long, value_enum, env = "SPREADING_COLORS_HASH_NAMES")]
+    pub hash_names: Option<HashNames>,
+
+    /// Directory images are saved in. It's created if it doesn't exist yet.
+    #[arg(
+        long,
+        default_value = "output_images",
+        env = "SPREADING_COLORS_OUT_DIR"
+    )]
+    pub out_dir: PathBuf,
+
+    /// Fragment shader to draw every image through, as with `run --shader`
+    #[arg(long, env = "SPREADING_COLORS_SHADER")]
+    pub shader: Option<PathBuf>,
+
+    /// Color space saved PNG and JPEG images are tagged with, as with `run --color-space`
+    #[arg(long, value_enum, default_value_t = ColorSpace::Srgb, env = "SPREADING_COLORS_COLOR_SPACE")]
+    pub color_space: ColorSpace,
+
+    /// Scale each cell of the images up to a square of this many pixels, as with `run --scale`
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_name = "PIXELS",
+        value_parser = clap::value_parser!(u32).range(1..),
+        env = "SPREADING_COLORS_SCALE"
+    )]
+    pub scale: u32,
+
+    #[command(flatten)]
+    pub orientation: Orientation,
+
+    /// Extra images to save next to each image, as with `run --layers`
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        env = "SPREADING_COLORS_LAYERS"
+    )]
+    pub layers: Vec<Layer>,
+
+    #[command(flatten)]
+    pub format: FormatOptions,
+
+    #[command(flatten)]
+    pub metadata: Metadata,
+}
+
+#[derive(Debug, Args)]
+pub struct CompareArgs {
+    /// JSON or TOML file with the settings both runs share (`-` reads it from stdin)
+    #[arg(env = "SPREADING_COLORS_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Profile from the config file to use, e.g. `--profile wallpaper` for its
+    /// `[profile.wallpaper]` section. Its settings replace the ones at the top of the file
+    #[arg(long, requires = "config", env = "SPREADING_COLORS_PROFILE")]
+    pub profile: Option<String>,
+
+    /// A setting the left run changes, e.g. `--left colorshift=2`. Can be given more than once.
+    /// Comparable settings: width, height, starting_live_cells, colorshift, spread_chance, temperature
+    #[arg(long, value_name = "SETTING=VALUE", required_unless_present = "load")]
+    pub left: Vec<Setting>,
+
+    /// A setting the right run changes, e.g. `--right colorshift=12`. Can be given more than once
+    #[arg(long, value_name = "SETTING=VALUE", required_unless_present = "load")]
+    pub right: Vec<Setting>,
+
+    /// Compare two runs saved with `run --save-state` instead of running new ones
+    #[arg(long, num_args = 2, value_names = ["LEFT", "RIGHT"], conflicts_with_all = ["config", "left", "right", "seed"])]
+    pub load: Vec<PathBuf>,
+
+    /// Seed both runs share, so they only differ by their settings
+    #[arg(long, env = "SPREADING_COLORS_SEED")]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Args)]
+pub struct WorkerArgs {
+    /// Directory to take job files (JSON or TOML simulation configs) from
+    #[arg(long, env = "SPREADING_COLORS_JOBS_DIR")]
+    pub jobs: PathBuf,
+
+    /// Directory rendered images and their manifests are written to
+    #[arg(long, env = "SPREADING_COLORS_RESULTS_DIR")]
+    pub results: PathBuf,
+
+    /// Keep waiting for new jobs instead of exiting once the jobs directory is empty
+    #[arg(long, env = "SPREADING_COLORS_WATCH")]
+    pub watch: bool,
+
+    /// Seconds between checks for new jobs while watching
+    #[arg(
+        long,
+        default_value_t = 5,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        env = "SPREADING_COLORS_POLL_INTERVAL"
+    )]
+    pub poll_interval: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+    /// JSON or TOML file to read the settings from, instead of prompting for them.
+    /// Use `-` to read them from stdin
+    #[arg(env = "SPREADING_COLORS_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Profile from the config file to use, e.g. `--profile wallpaper` for its
+    /// `[profile.wallpaper]` section. Its settings replace the ones at the top of the file
+    #[arg(long, requires = "config", env = "SPREADING_COLORS_PROFILE")]
+    pub profile: Option<String>,
+
+    /// Saved preset to take the settings from, instead of prompting for them
+    #[arg(long, conflicts_with = "config", env = "SPREADING_COLORS_PRESET")]
+    pub preset: Option<String>,
+
+    /// Continue a run saved with --save-state, with its saved settings.
+    /// Settings given as flags still apply, except for the grid's size.
+    /// Runs can be continued from a grid saved as .scgrid too, with the default settings
+    #[arg(long, conflicts_with_all = ["config", "preset", "count"], env = "SPREADING_COLORS_RESUME")]
+    pub resume: Option<PathBuf>,
+
+    /// Save the run's state here when it ends, so it can be continued with --resume.
+    /// Background runs also stop and save on Ctrl+C, so huge runs can be paused
+    #[arg(long, env = "SPREADING_COLORS_SAVE_STATE")]
+    pub save_state: Option<PathBuf>,
+
+    /// Save the colors the run ended up with here as a palette for other art, a GIMP palette
+    /// (.gpl) or Adobe Swatch Exchange file (.ase)
+    #[arg(long, value_name = "FILE", value_parser = parse_palette_path, env = "SPREADING_COLORS_EXPORT_PALETTE")]
+    pub export_palette: Option<PathBuf>,
+
+    /// How many colors an --export-palette boils the run's colors down to
+    #[arg(
+        long,
+        default_value_t = PALETTE_COLORS_DEFAULT,
+        value_name = "COUNT",
+        value_parser = clap::value_parser!(u16).range(1..),
+        env = "SPREADING_COLORS_PALETTE_COLORS"
+    )]
+    pub palette_colors: u16,
+
+    /// Never prompt: use the defaults plus any settings given as flags, and always save the image
+    #[arg(short, long, env = "SPREADING_COLORS_YES")]
+    pub yes: bool,
+
+    /// Width of the grid in pixels
+    #[arg(long, env = "SPREADING_COLORS_WIDTH")]
+    pub width: Option<usize>,
+
+    /// Height of the grid in pixels
+    #[arg(long, env = "SPREADING_COLORS_HEIGHT")]
+    pub height: Option<usize>,
+
+    /// Width and height of the grid together, e.g. `1920x1080`, a name like `1080p`, `1440p`, `4k`
+    /// or `8k`, a common desktop size (`wxga`, `hd+`, `wuxga`, `wqxga`, or `uwfhd` and `uwqhd` for
+    /// ultrawide screens), or `terminal` to fill the terminal with the animation
+    #[arg(long, conflicts_with_all = ["width", "height"], env = "SPREADING_COLORS_SIZE")]
+    pub size: Option<GridSize>,
+
+    /// Number of randomly placed cells the simulation starts with
+    #[arg(long, env = "SPREADING_COLORS_STARTING_LIVE_CELLS")]
+    pub starting_live_cells: Option<u32>,
+
+    /// Framerate of the terminal animation
+    #[arg(long, env = "SPREADING_COLORS_FRAMERATE")]
+    pub framerate: Option<usize>,
+
+    /// Animate the simulation in the terminal while it runs
+    #[arg(long, env = "SPREADING_COLORS_ANIMATE")]
+    pub animate: bool,
+
+    /// How far a child's color may drift from its parent's on each channel
+    #[arg(long, env = "SPREADING_COLORS_COLORSHIFT")]
+    pub colorshift: Option<u8>,
+
+    /// Chance (0.0 -> 1.0) that a live cell spreads on each generation
+    #[arg(long, env = "SPREADING_COLORS_SPREAD_CHANCE")]
+    pub spread_chance: Option<f64>,
+
+    /// One dial for all the randomness: scales the colorshift and how far orphans' colors stray
+    /// from their palette together. 1 is as set, towards 0 gives calm gradients, above 1 noise
+    #[arg(long, env = "SPREADING_COLORS_TEMPERATURE")]
+    pub temperature: Option<f64>,
+
+    /// What happens at the edges of the grid: `exclude` keeps a border that's never simulated,
+    /// `clamp` simulates every cell and stops growth at the edges, and `wrap` lets it flow off one
+    /// edge and continue on the opposite side, so saved images tile seamlessly. The preview of a
+    /// wrapping grid shows the image tiled 2x2 to check the seams
+    #[arg(long, value_enum, env = "SPREADING_COLORS_BOUNDARY")]
+    pub boundary: Option<Boundary>,
+
+    /// Short for --boundary wrap
+    #[arg(long, conflicts_with = "boundary", env = "SPREADING_COLORS_WRAP")]
+    pub wrap: bool,
+
+    /// Which cells a live cell can spread to: all eight around it (`moore`), only the four
+    /// above, below and beside it (`von-neumann`), which grows diamond-shaped colonies, or those
+    /// within a circle around it (`circular`), which grows round colonies past a --spread-radius
+    /// of 1 (at 1 it's the same as `von-neumann`)
+    #[arg(long, value_enum, env = "SPREADING_COLORS_NEIGHBORHOOD")]
+    pub neighborhood: Option<Neighborhood>,
+
+    /// How many cells away a live cell can spread, within its --neighborhood. Beyond 1 cells skip
+    /// over their neighbors, for looser, more organic textures
+    #[arg(
+        long,
+        value_name = "CELLS",
+        value_parser = clap::value_parser!(u32).range(1..=i64::from(MAX_SPREAD_RADIUS)),
+        env = "SPREADING_COLORS_SPREAD_RADIUS"
+    )]
+    pub spread_radius: Option<u32>,
+
+    /// Seed for the random number generator, to reproduce an earlier run
+    #[arg(long, env = "SPREADING_COLORS_SEED")]
+    pub seed: Option<u64>,
+
+    /// Colors the starting cells are picked from. `auto` picks a palette by the time of day,
+    /// following the config's `palette_schedule` (or a built-in one)
+    #[arg(long, value_enum, env = "SPREADING_COLORS_PALETTE")]
+    pub palette: Option<ColorPalette>,
+
+    /// Dark or light background for the terminal and images, also picking the palette when none
+    /// is given. Follows the system's dark or light mode by default
+    #[arg(long, value_enum, env = "SPREADING_COLORS_THEME")]
+    pub theme: Option<Theme>,
+
+    /// Pick the colorshift, spread chance, number of starting cells and palette at random, from
+    /// ranges that tend to look good. The picks are printed and recorded in a manifest beside the image
+    #[arg(
+        long,
+        conflicts_with_all = ["colorshift", "spread_chance", "starting_live_cells", "palette", "resume"],
+        env = "SPREADING_COLORS_RANDOMIZE"
+    )]
+    pub randomize: bool,
+
+    /// Derive the seed (and with it the starting cells and colors) from the physical world.
+    /// The seed is still printed, so the run can be reproduced with --seed
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with = "seed",
+        env = "SPREADING_COLORS_ENTROPY"
+    )]
+    pub entropy: Option<EntropySource>,
+
+    /// Generations to pause at, e.g. `--break-at 100,500,1000`.
+    /// Animated runs wait for Enter, background runs save a snapshot image instead
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "GENERATIONS",
+        env = "SPREADING_COLORS_BREAK_AT"
+    )]
+    pub break_at: Vec<u64>,
+
+    /// Draw a frame of the animation every this many generations, or `auto` to skip as many as
+    /// it takes for the terminal to keep up. Skipped generations are still simulated at the
+    /// framerate, so drawing doesn't slow the simulation down
+    #[arg(
+        long,
+        default_value = "1",
+        value_name = "GENERATIONS",
+        env = "SPREADING_COLORS_DRAW_EVERY"
+    )]
+    pub draw_every: DrawEvery,
+
+    /// Print a preview of the grid, shrunk to fit the terminal, every this many generations or
+    /// seconds (e.g. `500` or `30s`) while running in the background, to keep an eye on long runs
+    #[arg(
+        long,
+        value_name = "GENERATIONS|SECONDS",
+        conflicts_with_all = ["animate", "accessible"],
+        env = "SPREADING_COLORS_PREVIEW_EVERY"
+    )]
+    pub preview_every: Option<PreviewEvery>,
+
+    /// Record the animation to this file as an asciinema cast, so it can be shared and replayed
+    /// (e.g. with `asciinema play`) without running the simulation again
+    #[arg(long, value_name = "PATH", env = "SPREADING_COLORS_CAST")]
+    pub cast: Option<PathBuf>,
+
+    /// Save a timelapse of the whole run here as an animated GIF, or as an APNG when it's named
+    /// .png or .apng, which keeps every color where GIFs only have 256. Named .html, it's saved as
+    /// a web page with a player that can pause, step and seek, which plays in any browser. So the
+    /// growth itself can be shared. Frames are captured every --timelapse-every generations and
+    /// play at the framerate
+    #[arg(long, value_name = "PATH", env = "SPREADING_COLORS_TIMELAPSE")]
+    pub timelapse: Option<PathBuf>,
+
+    /// Stream the run into a video here (e.g. out.mp4 or out.webm) through ffmpeg, which has to
+    /// be installed. Frames are captured like a --timelapse's, without keeping them in memory
+    #[arg(long, value_name = "PATH", env = "SPREADING_COLORS_EXPORT_VIDEO")]
+    pub export_video: Option<PathBuf>,
+
+    /// Frames per second the --export-video plays at. Defaults to the framerate
+    #[arg(
+        long,
+        value_name = "FPS",
+        value_parser = parse_fps,
+        requires = "export_video",
+        env = "SPREADING_COLORS_VIDEO_FPS"
+    )]
+    pub video_fps: Option<f64>,
+
+    /// Scale each cell of the --export-video up to a square of this many pixels, keeping them
+    /// crisp, since players blur small videos when they scale them up themselves
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_name = "PIXELS",
+        requires = "export_video",
+        value_parser = clap::value_parser!(u32).range(1..),
+        env = "SPREADING_COLORS_VIDEO_SCALE"
+    )]
+    pub video_scale: u32,
+
+    /// Save a frame of the run every --timelapse-every generations in this directory, as
+    /// frame_000001.png, frame_000002.png, ..., to make animations from with other tools
+    #[arg(long, value_name = "DIR", env = "SPREADING_COLORS_FRAMES_DIR")]
+    pub frames_dir: Option<PathBuf>,
+
+    /// Write a frame of the run to stdout every --timelapse-every generations, as a stream of
+    /// binary PPM images to pipe into other tools, e.g. `| ffmpeg -f image2pipe -i - out.mp4`.
+    /// The run happens in the background and status messages are silenced, so nothing else gets
+    /// into the stream
+    #[arg(long, env = "SPREADING_COLORS_FRAMES_TO_STDOUT")]
+    pub frames_to_stdout: bool,
+
+    /// Compress a frame of the run every --timelapse-every generations into this file, a single
+    /// zstd archive with an index of the frames. Far smaller than --frames-dir for big grids
+    #[arg(long, value_name = "FILE", env = "SPREADING_COLORS_FRAMES_ARCHIVE")]
+    pub frames_archive: Option<PathBuf>,
+
+    /// How many generations apart the frames of a --timelapse, --export-video, --frames-dir,
+    /// --frames-to-stdout or --frames-archive are captured
+    #[arg(
+        long,
+        default_value_t = 10,
+        value_name = "GENERATIONS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        env = "SPREADING_COLORS_TIMELAPSE_EVERY"
+    )]
+    pub timelapse_every: u64,
+
+    /// How the generations frames are captured on are picked: every --timelapse-every
+    /// generations, logarithmically (dense early while growth is fast, sparse late as it slows),
+    /// or evenly for a total of --timelapse-frames. Those last two go by how long the run is
+    /// expected to take, so the count is only about right
+    #[arg(long, value_enum, default_value_t = SamplingMode::Every, value_name = "MODE", env = "SPREADING_COLORS_TIMELAPSE_SAMPLING")]
+    pub timelapse_sampling: SamplingMode,
+
+    /// How many frames --timelapse-sampling log or frames aims for
+    #[arg(
+        long,
+        default_value_t = TIMELAPSE_FRAMES_DEFAULT,
+        value_name = "FRAMES",
+        value_parser = clap::value_parser!(u64).range(2..),
+        env = "SPREADING_COLORS_TIMELAPSE_FRAMES"
+    )]
+    pub timelapse_frames: u64,
+
+    /// How many recent generations an animated run keeps around for rewinding
+    #[arg(long, default_value_t = HISTORY_SIZE_DEFAULT, env = "SPREADING_COLORS_HISTORY_SIZE")]
+    pub history_size: usize,
+
+    /// Filename of the saved image, relative to the output directory (or an absolute path).
+    /// Defaults to a name made from the date and settings, e.g. 2024-05-12_1830_w512h256_cs6_sc0.45_seed12345.png.
+    /// Name it .txt to save the grid as text, or .ans to save it as ANSI art for printing with `cat`.
+    /// .json and .csv files get whether each cell is alive and its color, for loading into other
+    /// tools (a y,x,r,g,b,alive line per cell in CSVs). .scgrid files get them as a compact binary
+    /// dump, which other renderers can read and --resume can continue from.
+    /// .ppm and .pam files are saved uncompressed, which is much faster for huge grids that are
+    /// only going to be read by other tools (ffmpeg, ImageMagick, ...)
+    #[arg(short, long, env = "SPREADING_COLORS_OUTPUT")]
+    pub output: Option<String>,
+
+    /// Size the image to fit the monitors: one image spanning all of them,
+    /// or one image per monitor with consecutive seeds
+    #[arg(long, value_enum, conflicts_with_all = ["width", "height", "size", "count", "resume"], env = "SPREADING_COLORS_MONITORS")]
+    pub monitors: Option<MonitorLayout>,
+
+    /// Set the saved image as the desktop background. With `--monitors each`, the primary
+    /// monitor's image is set
+    #[arg(long, conflicts_with = "count", env = "SPREADING_COLORS_SET_WALLPAPER")]
+    pub set_wallpaper: bool,
+
+    /// Don't save a contact sheet with batches of images (from --count or `--monitors each`).
+    /// It shows every image of the batch as a thumbnail labeled with its settings
+    #[arg(long, env = "SPREADING_COLORS_NO_CONTACT_SHEET")]
+    pub no_contact_sheet: bool,
+
+    /// Render this many images in one go, each with a new seed, saved without any prompts
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..), env = "SPREADING_COLORS_COUNT")]
+    pub count: u32,
+
+    /// Recordings and exports estimated to be bigger than this (e.g. 500MB, 2GB) need confirming,
+    /// and are skipped with --yes
+    #[arg(long, default_value = MAX_EXPORT_SIZE_DEFAULT, value_parser = parse_byte_size, env = "SPREADING_COLORS_MAX_EXPORT_SIZE")]
+    pub max_export_size: u64,
+
+    /// How cells are drawn in the terminal. Images saved as .txt use the colony and Braille styles too
+    #[arg(long, visible_alias = "render-backend", value_enum, default_value_t = CellStyle::Block, env = "SPREADING_COLORS_CELL_STYLE")]
+    pub cell_style: CellStyle,
+
+    /// Draw a frame around the grid in the terminal, showing where the canvas ends
+    #[arg(long, env = "SPREADING_COLORS_BORDER")]
+    pub border: bool,
+
+    /// Show the grid in the terminal as it looks with this kind of color blindness, to check how
+    /// the palette reads for color-blind viewers. Saved images keep their real colors
+    #[arg(long, env = "SPREADING_COLORS_COLOR_BLINDNESS")]
+    pub color_blindness: Option<ColorBlindness>,
+
+    /// Character live cells are drawn with in the block style, in their color, instead of a block
+    /// ('#' where unicode isn't available), e.g. `--cell-char ●`
+    #[arg(long, value_parser = parse_glyph, env = "SPREADING_COLORS_CELL_CHAR")]
+    pub cell_char: Option<String>,
+
+    /// Character cells born in the latest generation are drawn with in the block style, so the
+    /// growing edges of colonies stand out while animating, e.g. `--new-cell-char ○`
+    #[arg(long, value_parser = parse_glyph, env = "SPREADING_COLORS_NEW_CELL_CHAR")]
+    pub new_cell_char: Option<String>,
+
+    /// Draw cells with your own glyphs, each standing for an equal slice of hues starting at red,
+    /// e.g. `--glyphs 🟥🟧🟨🟩🟦🟪`. Separate glyphs longer than one character with spaces.
+    /// Images saved as .txt use these glyphs too
+    #[arg(long, env = "SPREADING_COLORS_GLYPHS")]
+    pub glyphs: Option<Palette>,
+
+    /// Plain text output for screen readers and log files: instead of drawing the grid,
+    /// print a status line with the generation and fill percentage as the grid fills up
+    #[arg(long, env = "SPREADING_COLORS_ACCESSIBLE")]
+    pub accessible: bool,
+
+    /// Print the settings the run would use, with estimates of its length and memory use, then stop
+    #[arg(long, env = "SPREADING_COLORS_DRY_RUN")]
+    pub dry_run: bool,
+
+    /// Show a desktop notification when the run finishes or fails
+    #[arg(long, env = "SPREADING_COLORS_NOTIFY")]
+    pub notify: bool,
+
+    /// What to do when an image would be saved over an existing file
+    #[arg(long, value_enum, default_value_t = Overwrite::Increment, env = "SPREADING_COLORS_OVERWRITE")]
+    pub overwrite: Overwrite,
+
+    /// Name saved images after a hash of their pixels (content) or of the settings and seed they're
+    /// made from (settings), keeping the directory and extension of --output. Images already saved
+    /// under their hash aren't saved again, and batches skip rendering them with `settings`
+    #[arg(long, value_enum, env = "SPREADING_COLORS_HASH_NAMES")]
+    pub hash_names: Option<HashNames>,
+
+    /// Directory images are saved in. It's created if it doesn't exist yet.
+    #[arg(
+        long,
+        default_value = "output_images",
+        env = "SPREADING_COLORS_OUT_DIR"
+    )]
+    pub out_dir: PathBuf,
+
+    /// Fragment shader (.wgsl, or .frag/.glsl for GLSL) to draw saved images through, for effects
+    /// like chromatic aberration or CRT curvature. Written like on Shadertoy, with a `mainImage`
+    /// reading the image from `iChannel0`. Needs the `shaders` feature
+    #[arg(long, env = "SPREADING_COLORS_SHADER")]
+    pub shader: Option<PathBuf>,
+
+    /// Color space saved PNG and JPEG images are tagged with, so they look the same in every
+    /// color-managed app. Colors are converted into Display P3 from sRGB when it's chosen
+    #[arg(long, value_enum, default_value_t = ColorSpace::Srgb, env = "SPREADING_COLORS_COLOR_SPACE")]
+    pub color_space: ColorSpace,
+
+    /// Scale each cell of saved images up to a square of this many pixels, so small grids
+    /// come out big enough to see, with crisp edges
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_name = "PIXELS",
+        value_parser = clap::value_parser!(u32).range(1..),
+        env = "SPREADING_COLORS_SCALE"
+    )]
+    pub scale: u32,
+
+    #[command(flatten)]
+    pub orientation: Orientation,
+
+    /// Extra images to save next to the image, named after it, e.g. `--layers mask` saves which
+    /// cells are alive as image_mask.png. Separate several with commas, e.g. `--layers mask,age`.
+    /// `directions` is drawn for laying over the image in an editor
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        env = "SPREADING_COLORS_LAYERS"
+    )]
+    pub layers: Vec<Layer>,
+
+    #[command(flatten)]
+    pub format: FormatOptions,
+
+    #[command(flatten)]
+    pub metadata: Metadata,
+}
+
+impl RunArgs {
+    /// Whether any simulation setting was given on the command line
+    pub fn has_setting_overrides(&self) -> bool {
+        self.width.is_some()
+            || self.height.is_some()
+            || self.size.is_some()
+            || self.starting_live_cells.is_some()
+            || self.framerate.is_some()
+            || self.animate
+            || self.colorshift.is_some()
+            || self.spread_chance.is_some()
+            || self.temperature.is_some()
+            || self.boundary.is_some()
+            || self.wrap
+            || self.neighborhood.is_some()
+            || self.spread_radius.is_some()
+            || self.seed.is_some()
+            || self.palette.is_some()
+            || self.theme.is_some()
+            || self.randomize
+    }
+
+    /// The renderer drawing cells the way the flags ask, on a terminal with `capabilities`
+    pub fn renderer(&self, capabilities: Capabilities) -> Renderer {
+        if self.accessible {
+            return Renderer::accessible(capabilities);
+        }
+        let renderer = Renderer::new(capabilities)
+            .with_style(self.cell_style)
+            .with_cell_chars(self.cell_char.clone(), self.new_cell_char.clone())
+            .with_frame(self.border)
+            .with_color_blindness(self.color_blindness);
+        match &self.glyphs {
+            Some(palette) => renderer.with_palette(palette.clone()),
+            None => renderer,
+        }
+    }
+
+    /// Replaces the settings in `config` with any that were given on the command line
+    pub fn apply_overrides(&self, config: &mut SimulationConfig) {
+        if let Some(width) = self.width {
+            config.width = width;
+        }
+        if let Some(height) = self.height {
+            config.height = height;
+        }
+        if let Some(size) = self.size {
+            (config.width, config.height) = size.dimensions(&self.renderer(Capabilities::detect()));
+        }
+        if let Some(starting_live_cells) = self.starting_live_cells {
+            config.starting_live_cells = starting_live_cells;
+        }
+        if let Some(framerate) = self.framerate {
+            config.framerate = framerate;
+        }
+        if self.animate {
+            config.show_while_running = true;
+        }
+        if let Some(colorshift) = self.colorshift {
+            config.colorshift = colorshift;
+        }
+        if let Some(spread_chance) = self.spread_chance {
+            config.spread_chance = spread_chance;
+        }
+        if let Some(temperature) = self.temperature {
+            config.temperature = temperature;
+        }
+        if let Some(boundary) = self.boundary {
+            config.boundary = boundary;
+        }
+        if self.wrap {
+            config.boundary = Boundary::Wrap;
+        }
+        if let Some(neighborhood) = self.neighborhood {
+            config.neighborhood = neighborhood;
+        }
+        if let Some(spread_radius) = self.spread_radius {
+            config.spread_radius = spread_radius;
+        }
+        if let Some(seed) = self.seed {
+            config.seed = Some(seed);
+        }
+        if let Some(palette) = self.palette {
+            config.palette = Some(palette);
+        }
+        if let Some(theme) = self.theme {
+            config.theme = theme;
+        }
+    }
+}
@@ -0,0 +1,399 @@
+// The color space exported images are tagged with, as an embedded ICC profile, so color-managed
+// browsers and photo apps show them the same everywhere instead of guessing.
+//
+// The color pipeline: cells hold plain 8-bit RGB values, which don't belong to any color space by
+// themselves. Palettes, colorshift and the terminal preview all treat them as sRGB, as viewers do
+// with untagged images. On export (after a `--shader`, which works on the sRGB values too) they're
+// converted from sRGB into the chosen color space and the image is tagged with its profile, so a
+// Display P3 image looks the same as an sRGB one in color-managed apps, its values just written
+// in the wider gamut. sRGB images are written out unchanged.
+//
+// PNG and JPEG exports are tagged, and carry the authorship metadata from `metadata.rs` too.
+// Other formats are saved without a profile, meaning sRGB. 16-bit PNGs and TIFFs hold the same
+// 8-bit colors widened to the 16-bit range, which adds no precision, and OpenEXR files hold them
+// decoded into linear light, as compositing tools expect, with sRGB's primaries. PNGs always carry
+// the settings they were made with as well, a text chunk per setting, so they can be made again
+// by whoever has them.
+
+use std::{
+    borrow::Cow,
+    fs::{self, File},
+    io::BufWriter,
+    path::Path,
+};
+
+use clap::ValueEnum;
+use image::{
+    codecs::jpeg::JpegEncoder,
+    error::{EncodingError, ImageFormatHint},
+    DynamicImage, ImageError, ImageFormat, ImageResult, Rgb, Rgb32FImage, RgbImage,
+};
+use moxcms::{ColorProfile, Layout, TransformOptions};
+use png::{
+    chunk::ChunkType,
+    text_metadata::{ITXtChunk, TEXtChunk},
+};
+
+use crate::{
+    format::{BitDepth, FormatOptions},
+    metadata::Metadata,
+    vision::to_linear,
+};
+
+// Where an ICC profile's header says when it was made, as year, month, day, hours, minutes and
+// seconds, and the date embedded profiles are given there
+const ICC_DATE: std::ops::Range<usize> = 24..36;
+const PROFILE_DATE: [u16; 6] = [2024, 1, 1, 0, 0, 0];
+
+// How JPEG files mark segments holding an ICC profile, and how much of one fits in each segment
+// (64KB, less the segment's length, this marker and the chunk's number and count)
+const JPEG_ICC_MARKER: &[u8] = b"ICC_PROFILE\0";
+const JPEG_ICC_CHUNK_SIZE: usize = u16::MAX as usize - 2 - JPEG_ICC_MARKER.len() - 2;
+// How JPEG files mark the APP1 segments holding EXIF and XMP
+const JPEG_EXIF_MARKER: &[u8] = b"Exif\0\0";
+const JPEG_XMP_MARKER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+// The PNG keyword XMP packets are stored under
+const PNG_XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+
+/// A color space exported images can be tagged with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ColorSpace {
+    /// The standard for the web and most screens, matching the terminal preview
+    #[default]
+    Srgb,
+    /// The wider gamut of recent phones and monitors. Colors are converted into it from sRGB
+    DisplayP3,
+}
+
+impl ColorSpace {
+    // The color space's profile, as moxcms works with it
+    fn profile(self) -> ColorProfile {
+        match self {
+            ColorSpace::Srgb => ColorProfile::new_srgb(),
+            ColorSpace::DisplayP3 => ColorProfile::new_display_p3(),
+        }
+    }
+
+    /// The ICC profile describing the color space
+    pub fn icc_profile(self) -> Vec<u8> {
+        let mut icc = self
+            .profile()
+            .encode()
+            .expect("the built-in profiles can always be encoded");
+        // Encoding stamps profiles with the current time, which would make saving the same image
+        // twice give different files
+        let date: Vec<u8> = PROFILE_DATE.iter().flat_map(|v| v.to_be_bytes()).collect();
+        icc[ICC_DATE].copy_from_slice(&date);
+        icc
+    }
+
+    /// `image`, whose values are sRGB like every cell's, with them converted into the color space
+    pub fn convert(self, image: &RgbImage) -> Cow<'_, RgbImage> {
+        if self == ColorSpace::Srgb {
+            return Cow::Borrowed(image);
+        }
+        let transform = ColorSpace::Srgb
+            .profile()
+            .create_transform_8bit(
+                Layout::Rgb,
+                &self.profile(),
+                Layout::Rgb,
+                TransformOptions::default(),
+            )
+            .expect("the built-in profiles can always be converted between");
+        let mut converted = RgbImage::new(image.width(), image.height());
+        transform
+            .transform(image.as_raw(), &mut converted)
+            .expect("the images are the same size");
+        Cow::Owned(converted)
+    }
+}
+
+/// Saves `image` at `path`, in the format its extension asks for, compressed and at the bit depth
+/// `format` says.
+/// PNG and JPEG files are tagged as being in `color_space` and carry `metadata`,
+/// along with the image's `settings` if they're embedded. PNG files carry the `recipe` the image
+/// was made from too (see `Grid::recipe`).
+pub fn save_tagged(
+    image: &RgbImage,
+    path: &Path,
+    color_space: ColorSpace,
+    format: &FormatOptions,
+    metadata: &Metadata,
+    settings: Option<&str>,
+    recipe: &[(&str, String)],
+) -> ImageResult<()> {
+    let profile = color_space.icc_profile();
+    match ImageFormat::from_path(path)? {
+        ImageFormat::Png => {
+            let image = color_space.convert(image);
+            write_png(&image, path, profile, format, metadata, settings, recipe)
+        }
+        ImageFormat::Jpeg => {
+            let image = color_space.convert(image);
+            write_jpeg(&image, path, &profile, format, metadata, settings)
+        }
+        ImageFormat::Tiff if format.bit_depth == BitDepth::Sixteen => {
+            DynamicImage::ImageRgb8(image.clone())
+                .into_rgb16()
+                .save(path)
+        }
+        ImageFormat::OpenExr => linear_image(image).save(path),
+        _ => image.save(path),
+    }
+}
+
+// Writes a PNG with the profile in its iCCP chunk, the metadata as text chunks (XMP being one of
+// them), the recipe as plain tEXt chunks and EXIF in an eXIf chunk. The `image` crate can't embed
+// any of these, so the `png` crate it uses is called directly, with the same filtering it would use.
+fn write_png(
+    image: &RgbImage,
+    path: &Path,
+    profile: Vec<u8>,
+    format: &FormatOptions,
+    metadata: &Metadata,
+    settings: Option<&str>,
+    recipe: &[(&str, String)],
+) -> ImageResult<()> {
+    let png_error = |e| {
+        ImageError::Encoding(EncodingError::new(
+            ImageFormatHint::Exact(ImageFormat::Png),
+            e,
+        ))
+    };
+    let mut info = png::Info::with_size(image.width(), image.height());
+    info.color_type = png::ColorType::Rgb;
+    info.bit_depth = format.bit_depth.into();
+    info.icc_profile = Some(profile.into());
+    for (keyword, text) in metadata.png_text(settings) {
+        info.utf8_text.push(ITXtChunk::new(keyword, text));
+    }
+    if let Some(xmp) = metadata.xmp(settings) {
+        info.utf8_text.push(ITXtChunk::new(PNG_XMP_KEYWORD, xmp));
+    }
+    // tEXt chunks, since they're the ones every tool reads (settings are plain ASCII anyway)
+    for (keyword, value) in recipe {
+        info.uncompressed_latin1_text
+            .push(TEXtChunk::new(*keyword, value.clone()));
+    }
+
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::with_info(file, info).map_err(png_error)?;
+    encoder.set_compression(format.png_compression.into());
+    encoder.set_adaptive_filter(png::AdaptiveFilterType::Adaptive);
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    if let Some(exif) = metadata.exif() {
+        writer
+            .write_chunk(ChunkType(*b"eXIf"), &exif)
+            .map_err(png_error)?;
+    }
+    let data = match format.bit_depth {
+        BitDepth::Eight => Cow::Borrowed(image.as_raw()),
+        // Each value is stretched over the whole 16-bit range, big-endian as PNGs store them
+        BitDepth::Sixteen => Cow::Owned(
+            image
+                .as_raw()
+                .iter()
+                .flat_map(|&value| (u16::from(value) * 257).to_be_bytes())
+                .collect(),
+        ),
+    };
+    writer.write_image_data(&data).map_err(png_error)?;
+    writer.finish().map_err(png_error)
+}
+
+// `image` as floating point values in linear light, as OpenEXR files hold them
+fn linear_image(image: &RgbImage) -> Rgb32FImage {
+    Rgb32FImage::from_fn(image.width(), image.height(), |x, y| {
+        Rgb(image
+            .get_pixel(x, y)
+            .0
+            .map(|channel| to_linear(channel) as f32))
+    })
+}
+
+// Writes a JPEG with the metadata in APP1 segments (EXIF, then XMP) and the profile in APP2 segments
+// right after the JFIF header, where readers look for them. Profiles too big for one segment are
+// split across several, numbered from 1. Metadata too big for one is left out.
+fn write_jpeg(
+    image: &RgbImage,
+    path: &Path,
+    profile: &[u8],
+    format: &FormatOptions,
+    metadata: &Metadata,
+    settings: Option<&str>,
+) -> ImageResult<()> {
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, format.jpeg_quality).encode_image(image)?;
+
+    // The file starts with its start-of-image marker, usually followed by the JFIF header (APP0)
+    let mut header_end = 2;
+    if jpeg[2..4] == [0xFF, 0xE0] {
+        header_end += 2 + usize::from(u16::from_be_bytes([jpeg[4], jpeg[5]]));
+    }
+    let mut segments = Vec::new();
+    let app1 = [
+        (JPEG_EXIF_MARKER, metadata.exif()),
+        (
+            JPEG_XMP_MARKER,
+            metadata.xmp(settings).map(String::into_bytes),
+        ),
+    ];
+    for (marker, payload) in app1 {
+        let Some(payload) = payload else { continue };
+        let Ok(length) = u16::try_from(2 + marker.len() + payload.len()) else {
+            continue;
+        };
+        segments.extend([0xFF, 0xE1]);
+        segments.extend(length.to_be_bytes());
+        segments.extend(marker);
+        segments.extend(payload);
+    }
+    let chunks: Vec<&[u8]> = profile.chunks(JPEG_ICC_CHUNK_SIZE).collect();
+    for (number, chunk) in (1..).zip(&chunks) {
+        let length = (2 + JPEG_ICC_MARKER.len() + 2 + chunk.len()) as u16;
+        segments.extend([0xFF, 0xE2]);
+        segments.extend(length.to_be_bytes());
+        segments.extend(JPEG_ICC_MARKER);
+        segments.extend([number, chunks.len() as u8]);
+        segments.extend(*chunk);
+    }
+    jpeg.splice(header_end..header_end, segments);
+    fs::write(path, jpeg).map_err(ImageError::IoError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::temp_path;
+
+    // A small image with a wide spread of colors, saturated ones included
+    fn sample_image() -> RgbImage {
+        RgbImage::from_fn(16, 16, |x, y| {
+            Rgb([(x * 17) as u8, (y * 17) as u8, ((x + y) * 8) as u8])
+        })
+    }
+
+    fn save(color_space: ColorSpace, name: &str) -> Vec<u8> {
+        let path = temp_path(name);
+        let format = FormatOptions::default();
+        save_tagged(
+            &sample_image(),
+            &path,
+            color_space,
+            &format,
+            &Metadata::default(),
+            None,
+            &[],
+        )
+        .unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn png_iccp_holds_each_profile() {
+        for &color_space in ColorSpace::value_variants() {
+            let png = save(color_space, "tagged.png");
+            let reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+            let icc = reader.info().icc_profile.as_deref().unwrap();
+            assert_eq!(icc, color_space.icc_profile(), "{color_space:?}");
+        }
+    }
+
+    #[test]
+    fn jpeg_app2_holds_each_profile() {
+        for &color_space in ColorSpace::value_variants() {
+            let jpeg = save(color_space, "tagged.jpg");
+            let start = jpeg
+                .windows(JPEG_ICC_MARKER.len())
+                .position(|window| window == JPEG_ICC_MARKER)
+                .unwrap();
+            // The segment's marker and length come right before, its number and count right after
+            assert_eq!(jpeg[start - 4..start - 2], [0xFF, 0xE2]);
+            let length = usize::from(u16::from_be_bytes([jpeg[start - 2], jpeg[start - 1]]));
+            let chunk = start + JPEG_ICC_MARKER.len();
+            assert_eq!(jpeg[chunk..chunk + 2], [1, 1]);
+            let icc = &jpeg[chunk + 2..start - 2 + length];
+            assert_eq!(icc, color_space.icc_profile(), "{color_space:?}");
+        }
+    }
+
+    #[test]
+    fn srgb_pixels_are_saved_unchanged() {
+        let png = save(ColorSpace::Srgb, "srgb.png");
+        let saved = image::load_from_memory(&png).unwrap().into_rgb8();
+        assert_eq!(saved, sample_image());
+    }
+
+    #[test]
+    fn display_p3_pixels_convert_back_to_srgb() {
+        let png = save(ColorSpace::DisplayP3, "p3.png");
+        let saved = image::load_from_memory(&png).unwrap().into_rgb8();
+        assert_ne!(saved, sample_image());
+
+        let back = ColorSpace::DisplayP3
+            .profile()
+            .create_transform_8bit(
+                Layout::Rgb,
+                &ColorSpace::Srgb.profile(),
+                Layout::Rgb,
+                TransformOptions::default(),
+            )
+            .unwrap();
+        let mut srgb = RgbImage::new(saved.width(), saved.height());
+        back.transform(saved.as_raw(), &mut srgb).unwrap();
+        // Rounding to 8 bits in P3 loses a little, most at the edge of sRGB's gamut, where a step
+        // of a P3 value moves the sRGB one furthest
+        for (converted, original) in srgb.as_raw().iter().zip(sample_image().as_raw()) {
+            assert!(
+                converted.abs_diff(*original) <= 8,
+                "{converted} vs {original}"
+            );
+        }
+    }
+
+    #[test]
+    fn saving_twice_gives_the_same_bytes() {
+        for &color_space in ColorSpace::value_variants() {
+            for name in ["same.png", "same.jpg"] {
+                assert_eq!(save(color_space, name), save(color_space, name), "{name}");
+            }
+            let icc = color_space.icc_profile();
+            let date: Vec<u8> = PROFILE_DATE.iter().flat_map(|v| v.to_be_bytes()).collect();
+            assert_eq!(icc[ICC_DATE], date);
+        }
+    }
+
+    #[test]
+    fn sixteen_bit_values_are_the_eight_bit_ones_widened() {
+        let format = FormatOptions {
+            bit_depth: BitDepth::Sixteen,
+            ..FormatOptions::default()
+        };
+        for name in ["wide.png", "wide.tiff"] {
+            let path = temp_path(name);
+            save_tagged(
+                &sample_image(),
+                &path,
+                ColorSpace::Srgb,
+                &format,
+                &Metadata::default(),
+                None,
+                &[],
+            )
+            .unwrap();
+            let saved = image::open(&path).unwrap();
+            fs::remove_file(path).unwrap();
+            let DynamicImage::ImageRgb16(saved) = saved else {
+                panic!("{name} wasn't saved with 16 bits per channel");
+            };
+            assert_eq!(saved.get_pixel(0, 0).0, [0, 0, 0], "{name}");
+            assert_eq!(saved.get_pixel(15, 15).0, [65535, 65535, 61680], "{name}");
+            for (wide, narrow) in saved.as_raw().iter().zip(sample_image().as_raw()) {
+                assert_eq!(*wide, u16::from(*narrow) * 257, "{name}");
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+// Side-by-side comparisons: two runs that differ by a few settings (or two saved runs), drawn next
+// to each other in the terminal so the effect of the settings can be seen at a glance.
+// Both runs use the same seed, so they only differ by the settings that were changed.
+
+use std::{path::Path, process, str::FromStr};
+
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+use crate::{
+    cli::CompareArgs,
+    config::SimulationConfig,
+    grid::Grid,
+    i18n::t,
+    simulation::render,
+    state,
+    sweep::{set_setting, SWEEPABLE_SETTINGS},
+    terminal::{renderer, screen_size, Renderer},
+    verbosity::status,
+    viewport::shrink_to_fit,
+};
+
+// Columns left blank between the two grids
+const GAP: usize = 4;
+// Lines left free around the grids: a label for each run, and the line the cursor ends up on
+const LABEL_LINES: usize = 3;
+
+/// A setting one side of a comparison is run with, parsed from e.g. "colorshift=12"
+#[derive(Debug, Clone)]
+pub struct Setting {
+    pub key: String,
+    pub value: f64,
+}
+
+impl FromStr for Setting {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or(format!("expected SETTING=VALUE, got '{s}'"))?;
+        let key = key.trim().to_string();
+        if !SWEEPABLE_SETTINGS.contains(&key.as_str()) {
+            return Err(format!(
+                "'{key}' can't be compared, expected one of {}",
+                SWEEPABLE_SETTINGS.join(", ")
+            ));
+        }
+        let value = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("'{}' isn't a number", value.trim()))?;
+        Ok(Self { key, value })
+    }
+}
+
+// `base` with `settings` changed, exiting if they don't make a valid run
+fn with_settings(base: &SimulationConfig, settings: &[Setting]) -> SimulationConfig {
+    let mut config = base.clone();
+    for setting in settings {
+        if let Err(message) = set_setting(&mut config, &setting.key, setting.value) {
+            eprintln!("{}", t!("compare.invalid", error = message));
+            process::exit(1);
+        }
+    }
+    if let Err(e) = config.validate() {
+        eprintln!("{}", t!("compare.invalid", error = e));
+        process::exit(1);
+    }
+    config
+}
+
+// The settings a side was changed to, e.g. "colorshift=12, spread_chance=0.3"
+fn describe(settings: &[Setting]) -> String {
+    settings
+        .iter()
+        .map(|setting| format!("{}={}", setting.key, setting.value))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// The grid of a run saved with --save-state, exiting if it can't be loaded
+fn load(path: &Path) -> Grid {
+    let loaded = state::load(path).and_then(|saved| {
+        let config = saved.config.clone();
+        saved.into_grid(&config)
+    });
+    loaded.unwrap_or_else(|e| {
+        let path = path.display();
+        eprintln!("{}", t!("run.resume_failed", path = path, error = e));
+        process::exit(1);
+    })
+}
+
+pub fn run(args: &CompareArgs) {
+    let (left, right) = match args.load.as_slice() {
+        [left, right] => {
+            let labels = (left.display().to_string(), right.display().to_string());
+            ((load(left), labels.0), (load(right), labels.1))
+        }
+        _ => {
+            let mut base = match &args.config {
+                Some(path) => SimulationConfig::from_file(path, args.profile.as_deref())
+                    .unwrap_or_else(|e| {
+                        eprintln!("{}", t!("run.invalid_settings", error = e));
+                        process::exit(1);
+                    }),
+                None => SimulationConfig::default(),
+            };
+            // Both runs should have the same theme and palette, even if the system's changes midway
+            base.resolve_auto();
+            let seed = args
+                .seed
+                .or(base.seed)
+                .unwrap_or_else(|| thread_rng().gen());
+            base.seed = Some(seed);
+            status!("{}", t!("compare.start", seed = seed));
+
+            let run_side = |settings: &[Setting]| {
+                let config = with_settings(&base, settings);
+                let mut rng = StdRng::seed_from_u64(seed);
+                (render(&config, &mut rng, false), describe(settings))
+            };
+            (run_side(&args.left), run_side(&args.right))
+        }
+    };
+    print_side_by_side(&left, &right);
+}
+
+// Prints the two grids next to each other under their labels, each shrunk to fit half of the
+// terminal by averaging blocks of cells when it's bigger
+fn print_side_by_side((left, left_label): &(Grid, String), (right, right_label): &(Grid, String)) {
+    // Images can't be put next to each other, so they're compared in blocks
+    let renderer = if renderer().draws_image() {
+        Renderer::new(renderer().capabilities())
+    } else {
+        renderer().clone()
+    };
+    let area = screen_size().map(|(columns, rows)| {
+        (
+            columns.saturating_sub(GAP) / 2,
+            rows.saturating_sub(LABEL_LINES),
+        )
+    });
+    let (left_summary, right_summary) = (left.settings_summary(), right.settings_summary());
+    println!(
+        "{}",
+        t!(
+            "compare.left",
+            settings = left_label,
+            summary = left_summary
+        )
+    );
+    println!(
+        "{}",
+        t!(
+            "compare.right",
+            settings = right_label,
+            summary = right_summary
+        )
+    );
+
+    let shrunk = |grid: &Grid| area.and_then(|area| shrink_to_fit(grid, &renderer, area));
+    let (shrunk_left, shrunk_right) = (shrunk(left), shrunk(right));
+    let left = shrunk_left.as_ref().unwrap_or(left);
+    let right = shrunk_right.as_ref().unwrap_or(right);
+    let left_width =
+        left.drawn_size(&renderer).0 * renderer.cell_width() + 2 * renderer.frame_width();
+    let left_frame = left.frame_with(&renderer);
+    let right_frame = right.frame_with(&renderer);
+    let mut left_lines = left_frame.lines();
+    let mut right_lines = right_frame.lines();
+    loop {
+        let (left_line, right_line) = (left_lines.next(), right_lines.next());
+        if left_line.is_none() && right_line.is_none() {
+            break;
+        }
+        // Shorter grids are padded with blank lines, so the other one stays in its column
+        let left_line = left_line.unwrap_or_default();
+        let padding = if left_line.is_empty() { left_width } else { 0 };
+        println!(
+            "{left_line}{}{}",
+            " ".repeat(padding + GAP),
+            right_line.unwrap_or_default()
+        );
+    }
+}
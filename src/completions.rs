@@ -0,0 +1,30 @@
+// Shell completion scripts, generated from the command line definition itself
+// so they never fall out of date with the flags.
+
+use std::io;
+
+use clap::{builder::PossibleValuesParser, CommandFactory};
+use clap_complete::generate;
+
+use crate::{
+    cli::{Cli, CompletionsArgs},
+    presets::list_presets,
+};
+
+pub fn run(args: &CompletionsArgs) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+
+    // Completion scripts can't run the program to ask for presets, so the current presets are
+    // baked in as the values `--preset` accepts. Regenerate the script after adding presets.
+    let presets = list_presets();
+    if !presets.is_empty() {
+        let with_presets =
+            |arg: clap::Arg| arg.value_parser(PossibleValuesParser::new(presets.clone()));
+        command = command
+            .mut_arg("preset", with_presets)
+            .mut_subcommand("run", |run| run.mut_arg("preset", with_presets));
+    }
+
+    generate(args.shell, &mut command, name, &mut io::stdout());
+}
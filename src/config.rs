@@ -0,0 +1,642 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::palettes::{parse_time, ColorPalette, ScheduleEntry, Theme};
+
+// DEFAULTS (if user skips prompts these defaults are used in place of user input)
+pub const WIDTH_DEFAULT: usize = 32;
+pub const HEIGHT_DEFAULT: usize = 16;
+pub const FRAMERATE_DEFAULT: usize = 32;
+pub const SHOW_WHILE_RUNNING_DEFAULT: bool = false;
+pub const COLORSHIFT_DEFAULT: u8 = 4;
+pub const STARTING_LIVE_CELLS_DEFAULT: u32 = 1;
+pub const SPREAD_CHANCE_DEFAULT: f64 = 0.5;
+pub const TEMPERATURE_DEFAULT: f64 = 1.0;
+pub const SPREAD_RADIUS_DEFAULT: u32 = 1;
+
+// Furthest cells can spread, which keeps the list of cells each one can spread to short
+pub const MAX_SPREAD_RADIUS: u32 = 8;
+pub const OUTPUT_FILENAME_DEFAULT: &str = "image.png";
+
+// Smallest width/height that still leaves at least one simulated (non-border) cell
+pub const MIN_GRID_SIZE: usize = 3;
+
+/// Every setting the user can choose before a simulation starts.
+/// Missing fields in a preset or config file fall back to their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimulationConfig {
+    pub width: usize,
+    pub height: usize,
+    pub starting_live_cells: u32,
+    pub framerate: usize,
+    pub show_while_running: bool,
+    pub colorshift: u8,
+    pub spread_chance: f64,
+    // Scales every random magnitude at once: the colorshift and how far orphans' colors stray from
+    // their palette. 1 leaves them be, lower calms them towards flat gradients, higher heats them
+    // up into noise.
+    pub temperature: f64,
+    // What happens at the edges of the grid
+    pub boundary: Boundary,
+    // Which cells around a live cell it can spread to, and how far away they can be
+    pub neighborhood: Neighborhood,
+    pub spread_radius: u32,
+    // Seed for the random number generator. A random seed is picked when there isn't one.
+    #[serde(skip_serializing_if = "Option::is_none", with = "seed_format")]
+    pub seed: Option<u64>,
+    // Background and default palette, dark or light
+    pub theme: Theme,
+    // Colors the starting cells are picked from. The theme picks them when this isn't set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette: Option<ColorPalette>,
+    // When the `auto` palette switches to which palette, e.g.
+    // `[[palette_schedule]] from = "06:00" palette = "sunrise"`. A built-in schedule is used without one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette_schedule: Option<Vec<ScheduleEntry>>,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            width: WIDTH_DEFAULT,
+            height: HEIGHT_DEFAULT,
+            starting_live_cells: STARTING_LIVE_CELLS_DEFAULT,
+            framerate: FRAMERATE_DEFAULT,
+            show_while_running: SHOW_WHILE_RUNNING_DEFAULT,
+            colorshift: COLORSHIFT_DEFAULT,
+            spread_chance: SPREAD_CHANCE_DEFAULT,
+            temperature: TEMPERATURE_DEFAULT,
+            boundary: Boundary::default(),
+            neighborhood: Neighborhood::default(),
+            spread_radius: SPREAD_RADIUS_DEFAULT,
+            seed: None,
+            theme: Theme::Auto,
+            palette: None,
+            palette_schedule: None,
+        }
+    }
+}
+
+/// What happens to growth at the edges of the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Boundary {
+    /// The outermost rows and columns are a border that's never simulated, the default
+    #[default]
+    Exclude,
+    /// Every cell is simulated, and growth stops at the edges
+    Clamp,
+    /// Growth flows off one edge and continues on the opposite side, as if the grid were a torus,
+    /// so images tile seamlessly. Every cell is simulated.
+    Wrap,
+}
+
+impl Boundary {
+    /// How many rows or columns thick the unsimulated border on each side of the grid is
+    pub fn border(self) -> usize {
+        usize::from(self == Boundary::Exclude)
+    }
+
+    /// The cell `offset` away from `cell`, in a grid or volume `size` cells long in each dimension.
+    /// Wrapping boundaries reach around to the opposite edge, the others end at theirs.
+    pub fn neighbor<const N: usize>(
+        self,
+        cell: [usize; N],
+        offset: [isize; N],
+        size: [usize; N],
+    ) -> Option<[usize; N]> {
+        let mut neighbor = [0; N];
+        for (((neighbor, position), offset), size) in
+            neighbor.iter_mut().zip(cell).zip(offset).zip(size)
+        {
+            let (position, size) = (position as isize + offset, size as isize);
+            *neighbor = match self {
+                Boundary::Wrap => position.rem_euclid(size) as usize,
+                _ if (0..size).contains(&position) => position as usize,
+                _ => return None,
+            };
+        }
+        Some(neighbor)
+    }
+}
+
+impl Display for Boundary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = self
+            .to_possible_value()
+            .expect("boundaries are never skipped");
+        write!(f, "{}", name.get_name())
+    }
+}
+
+/// Which cells around a live cell it can spread to, within the spread radius
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Neighborhood {
+    /// Every cell in the square around it, diagonals included (Moore): the eight cells around
+    /// it at radius 1. The default
+    #[default]
+    Moore,
+    /// The cells that many steps up, down and sideways from it (von Neumann): the four above,
+    /// below and beside it at radius 1. Grows diamond-shaped colonies
+    VonNeumann,
+    /// The cells in the circle around it, as far as a straight line reaches
+    Circular,
+}
+
+impl Neighborhood {
+    /// Where the cells a cell can spread to are from it when they can be up to `radius` away, in
+    /// `N` dimensions: as [rows down, columns right] for grids, and [layers back, rows down,
+    /// columns right] for volumes. Row by row (and layer by layer), from the top left.
+    pub fn offsets<const N: usize>(self, radius: u32) -> Vec<[isize; N]> {
+        let radius = radius as isize;
+        let side = 2 * radius + 1;
+        (0..side.pow(N as u32))
+            .map(|index| {
+                // The index written in base `side`, one digit per dimension, the last one lowest
+                let mut offset = [0; N];
+                let mut rest = index;
+                for distance in offset.iter_mut().rev() {
+                    *distance = rest % side - radius;
+                    rest /= side;
+                }
+                offset
+            })
+            .filter(|offset| offset.iter().any(|&distance| distance != 0))
+            .filter(|offset| match self {
+                Neighborhood::Moore => true,
+                Neighborhood::VonNeumann => offset.iter().map(|d| d.abs()).sum::<isize>() <= radius,
+                Neighborhood::Circular => {
+                    offset.iter().map(|d| d * d).sum::<isize>() <= radius * radius
+                }
+            })
+            .collect()
+    }
+}
+
+impl Display for Neighborhood {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = self
+            .to_possible_value()
+            .expect("neighborhoods are never skipped");
+        write!(f, "{}", name.get_name())
+    }
+}
+
+/// Why a config couldn't be turned into a usable `SimulationConfig`
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file couldn't be read
+    Io(PathBuf, io::Error),
+    /// The config file isn't valid JSON/TOML, or a value has the wrong type
+    Parse(String),
+    /// A setting has a value the simulation can't run with
+    Invalid { key: &'static str, message: String },
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "couldn't read {}: {e}", path.display()),
+            ConfigError::Parse(message) => write!(f, "{message}"),
+            ConfigError::Invalid { key, message } => write!(f, "{key} {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl SimulationConfig {
+    /// Reads a config file, picking the format from its extension (`.json`, otherwise TOML),
+    /// and validates it. A path of `-` reads the config from stdin instead.
+    /// `profile` picks one of the file's named profiles, see `from_json`.
+    pub fn from_file(path: &Path, profile: Option<&str>) -> Result<Self, ConfigError> {
+        if path == Path::new("-") {
+            return Self::from_stdin(profile);
+        }
+        let contents =
+            fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+        let config = if path.extension().is_some_and(|ext| ext == "json") {
+            Self::from_json(&contents, profile)?
+        } else {
+            Self::from_toml(&contents, profile)?
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reads a config piped into stdin and validates it.
+    /// There's no extension to go by, so anything that looks like a JSON object is parsed as JSON.
+    pub fn from_stdin(profile: Option<&str>) -> Result<Self, ConfigError> {
+        let mut contents = String::new();
+        io::stdin()
+            .read_to_string(&mut contents)
+            .map_err(|e| ConfigError::Io(PathBuf::from("<stdin>"), e))?;
+        let config = if contents.trim_start().starts_with('{') {
+            Self::from_json(&contents, profile)?
+        } else {
+            Self::from_toml(&contents, profile)?
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parses a JSON config. Errors name the key that couldn't be parsed.
+    ///
+    /// Configs can hold several named profiles under `profile`, e.g.
+    /// `{"width": 64, "profile": {"wallpaper": {"width": 1920, "height": 1080}}}`.
+    /// A profile's settings replace the ones at the top level, which are shared by every profile.
+    /// Without a `profile` name only the top-level settings are used.
+    pub fn from_json(json: &str, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let root: Value = serde_json::from_str(json)
+            .map_err(|e| ConfigError::Parse(format!("invalid JSON config: {e}")))?;
+        Self::from_value(root, profile)
+    }
+
+    /// Parses a TOML config, which can hold named profiles in `[profile.NAME]` sections
+    /// (see `from_json`). Syntax errors quote the offending line.
+    pub fn from_toml(toml: &str, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let root: toml::Table = toml::from_str(toml)
+            .map_err(|e| ConfigError::Parse(format!("invalid TOML config: {e}")))?;
+        // Going through JSON values lets both formats share the profile handling and error messages
+        let root = serde_json::to_value(root)
+            .map_err(|e| ConfigError::Parse(format!("invalid TOML config: {e}")))?;
+        Self::from_value(root, profile)
+    }
+
+    // Applies `profile` on top of the top-level settings and parses the result
+    fn from_value(root: Value, profile: Option<&str>) -> Result<Self, ConfigError> {
+        let Value::Object(mut settings) = root else {
+            return Err(ConfigError::Parse(
+                "the config must be a table of settings".to_string(),
+            ));
+        };
+        let profiles = match settings.remove("profile") {
+            Some(Value::Object(profiles)) => profiles,
+            Some(_) => {
+                return Err(ConfigError::Parse(
+                    "profile must be a table of named profiles".to_string(),
+                ))
+            }
+            None => Map::new(),
+        };
+
+        if let Some(name) = profile {
+            match profiles.get(name) {
+                Some(Value::Object(overrides)) => settings.extend(overrides.clone()),
+                Some(_) => {
+                    return Err(ConfigError::Parse(format!(
+                        "profile {name} must be a table of settings"
+                    )))
+                }
+                None if profiles.is_empty() => {
+                    return Err(ConfigError::Parse(format!(
+                        "there's no profile named '{name}', the config doesn't have any profiles"
+                    )))
+                }
+                None => {
+                    let names: Vec<&str> = profiles.keys().map(String::as_str).collect();
+                    return Err(ConfigError::Parse(format!(
+                        "there's no profile named '{name}', expected one of {}",
+                        names.join(", ")
+                    )));
+                }
+            }
+        }
+
+        serde_path_to_error::deserialize(Value::Object(settings)).map_err(|e| {
+            let key = e.path().to_string();
+            ConfigError::Parse(format!("invalid value for {key}: {}", e.into_inner()))
+        })
+    }
+
+    /// Checks that every setting has a value the simulation can run with
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        // The outermost rows and columns are never simulated, so anything smaller has no cells
+        if self.width < MIN_GRID_SIZE {
+            return Err(invalid(
+                "width",
+                format!("must be at least {MIN_GRID_SIZE}, got {}", self.width),
+            ));
+        }
+        if self.height < MIN_GRID_SIZE {
+            return Err(invalid(
+                "height",
+                format!("must be at least {MIN_GRID_SIZE}, got {}", self.height),
+            ));
+        }
+        if self.starting_live_cells == 0 {
+            return Err(invalid(
+                "starting_live_cells",
+                "must be at least 1, got 0".to_string(),
+            ));
+        }
+        if self.framerate == 0 {
+            return Err(invalid(
+                "framerate",
+                "must be at least 1, got 0".to_string(),
+            ));
+        }
+        if self.colorshift == 0 {
+            return Err(invalid(
+                "colorshift",
+                "must be at least 1, got 0".to_string(),
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.spread_chance) {
+            return Err(invalid(
+                "spread_chance",
+                format!("must be between 0.0 and 1.0, got {}", self.spread_chance),
+            ));
+        }
+        if !(1..=MAX_SPREAD_RADIUS).contains(&self.spread_radius) {
+            return Err(invalid(
+                "spread_radius",
+                format!(
+                    "must be between 1 and {MAX_SPREAD_RADIUS}, got {}",
+                    self.spread_radius
+                ),
+            ));
+        }
+        if !(self.temperature.is_finite() && self.temperature >= 0.0) {
+            return Err(invalid(
+                "temperature",
+                format!("must be 0.0 or more, got {}", self.temperature),
+            ));
+        }
+        for entry in self.palette_schedule.iter().flatten() {
+            if parse_time(&entry.from).is_none() {
+                return Err(invalid(
+                    "palette_schedule",
+                    format!("expected a time like 06:30, got '{}'", entry.from),
+                ));
+            }
+            if entry.palette == ColorPalette::Auto {
+                return Err(invalid(
+                    "palette_schedule",
+                    "can't switch to the auto palette, since that's what follows the schedule"
+                        .to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The palette the starting cells are picked from right now, see `ColorPalette::resolve`
+    pub fn current_palette(&self) -> ColorPalette {
+        self.palette
+            .unwrap_or_else(|| self.theme.default_palette())
+            .resolve(self.palette_schedule.as_deref())
+    }
+
+    /// Settles the settings that depend on the system or the time of day (the `auto` theme and
+    /// palette), so saved settings say what was really used
+    pub fn resolve_auto(&mut self) {
+        self.theme = self.theme.resolve();
+        self.palette = Some(self.current_palette());
+    }
+}
+
+fn invalid(key: &'static str, message: String) -> ConfigError {
+    ConfigError::Invalid { key, message }
+}
+
+// Seeds are any u64, but TOML integers stop at i64::MAX, so seeds past it are written as strings
+// (e.g. `seed = "18446744073709551615"`) to keep saved configs loadable. Either form is read.
+mod seed_format {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seed {
+        Number(u64),
+        Text(String),
+    }
+
+    pub fn serialize<S: Serializer>(seed: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match seed {
+            Some(seed) if i64::try_from(*seed).is_err() => {
+                serializer.serialize_some(&seed.to_string())
+            }
+            seed => seed.serialize(serializer),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u64>, D::Error> {
+        match Option::<Seed>::deserialize(deserializer) {
+            Ok(None) => Ok(None),
+            Ok(Some(Seed::Number(seed))) => Ok(Some(seed)),
+            Ok(Some(Seed::Text(seed))) => seed.trim().parse().map(Some).map_err(|_| {
+                D::Error::custom(format!("expected a whole number from 0 up, got '{seed}'"))
+            }),
+            Err(_) => Err(D::Error::custom("expected a whole number from 0 up")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_seed_survives_toml() {
+        for seed in [0, 42, i64::MAX as u64, i64::MAX as u64 + 1, u64::MAX] {
+            let config = SimulationConfig {
+                seed: Some(seed),
+                ..SimulationConfig::default()
+            };
+            let toml = toml::to_string(&config).unwrap();
+            let loaded = SimulationConfig::from_toml(&toml, None).unwrap();
+            assert_eq!(loaded.seed, Some(seed), "{toml}");
+        }
+    }
+
+    // The message `json` is refused with
+    fn json_error(json: &str) -> String {
+        SimulationConfig::from_json(json, None)
+            .and_then(|config| config.validate())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn wrong_types_name_their_key() {
+        for (json, key) in [
+            (r#"{"width": "wide"}"#, "invalid value for width:"),
+            (
+                r#"{"palette_schedule": [{"from": "06:00", "palette": "day"},
+                                         {"from": "20:00", "palette": 5}]}"#,
+                "invalid value for palette_schedule[1].palette:",
+            ),
+            (
+                r#"{"palette_schedule": [{"from": 6, "palette": "day"}]}"#,
+                "invalid value for palette_schedule[0].from:",
+            ),
+        ] {
+            let message = json_error(json);
+            assert!(message.starts_with(key), "{message}");
+        }
+    }
+
+    #[test]
+    fn out_of_range_values_name_their_key() {
+        for (json, key) in [
+            (r#"{"colorshift": 300}"#, "invalid value for colorshift:"),
+            (r#"{"width": -4}"#, "invalid value for width:"),
+            (r#"{"spread_chance": 1.5}"#, "spread_chance must be between"),
+            (r#"{"height": 2}"#, "height must be at least 3"),
+            (r#"{"spread_radius": 9}"#, "spread_radius must be between"),
+            (
+                r#"{"palette_schedule": [{"from": "25:00", "palette": "day"}]}"#,
+                "palette_schedule expected a time",
+            ),
+        ] {
+            let message = json_error(json);
+            assert!(message.starts_with(key), "{message}");
+        }
+    }
+
+    const PROFILES: &str = r#"
+        width = 64
+        height = 32
+        colorshift = 9
+
+        [profile.wallpaper]
+        width = 1920
+        height = 1080
+
+        [profile.tiny]
+        width = 8
+    "#;
+
+    #[test]
+    fn profiles_override_the_base_settings() {
+        let base = SimulationConfig::from_toml(PROFILES, None).unwrap();
+        assert_eq!((base.width, base.height, base.colorshift), (64, 32, 9));
+
+        let wallpaper = SimulationConfig::from_toml(PROFILES, Some("wallpaper")).unwrap();
+        assert_eq!((wallpaper.width, wallpaper.height), (1920, 1080));
+        // Settings the profile leaves out come from the top of the file
+        assert_eq!(wallpaper.colorshift, 9);
+
+        let tiny = SimulationConfig::from_toml(PROFILES, Some("tiny")).unwrap();
+        assert_eq!((tiny.width, tiny.height), (8, 32));
+    }
+
+    #[test]
+    fn json_profiles_are_picked_too() {
+        let json = r#"{"width": 64, "profile": {"wallpaper": {"width": 1920}}}"#;
+        let config = SimulationConfig::from_json(json, Some("wallpaper")).unwrap();
+        assert_eq!(config.width, 1920);
+    }
+
+    #[test]
+    fn missing_profiles_are_refused() {
+        let message = SimulationConfig::from_toml(PROFILES, Some("phone"))
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("no profile named 'phone'"), "{message}");
+        assert!(
+            message.contains("wallpaper") && message.contains("tiny"),
+            "{message}"
+        );
+
+        let message = SimulationConfig::from_toml("width = 64", Some("phone"))
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("doesn't have any profiles"), "{message}");
+    }
+
+    #[test]
+    fn profile_values_are_checked() {
+        let toml = "[profile.broken]\nwidth = \"wide\"";
+        assert!(SimulationConfig::from_toml(toml, None).is_ok());
+        let message = SimulationConfig::from_toml(toml, Some("broken"))
+            .unwrap_err()
+            .to_string();
+        assert!(message.starts_with("invalid value for width:"), "{message}");
+    }
+
+    #[test]
+    fn radius_1_neighborhoods_are_the_classic_ones() {
+        assert_eq!(
+            Neighborhood::Moore.offsets(1),
+            [
+                [-1, -1],
+                [-1, 0],
+                [-1, 1],
+                [0, -1],
+                [0, 1],
+                [1, -1],
+                [1, 0],
+                [1, 1]
+            ]
+        );
+        assert_eq!(
+            Neighborhood::VonNeumann.offsets(1),
+            [[-1, 0], [0, -1], [0, 1], [1, 0]]
+        );
+    }
+
+    #[test]
+    fn neighborhoods_grow_with_the_radius() {
+        for (radius, moore, von_neumann, circular) in [
+            (1, 8, 4, 4),
+            (2, 24, 12, 12),
+            (3, 48, 24, 28),
+            (MAX_SPREAD_RADIUS, 288, 144, 196),
+        ] {
+            let counts = [
+                Neighborhood::Moore,
+                Neighborhood::VonNeumann,
+                Neighborhood::Circular,
+            ]
+            .map(|neighborhood| neighborhood.offsets::<2>(radius).len());
+            assert_eq!(counts, [moore, von_neumann, circular], "radius {radius}");
+        }
+    }
+
+    #[test]
+    fn neighborhoods_extend_to_three_dimensions() {
+        for (radius, moore, von_neumann, circular) in [
+            (1, 26, 6, 6),
+            (2, 124, 24, 32),
+            (3, 342, 62, 122),
+            (MAX_SPREAD_RADIUS, 4912, 832, 2108),
+        ] {
+            let counts = [
+                Neighborhood::Moore,
+                Neighborhood::VonNeumann,
+                Neighborhood::Circular,
+            ]
+            .map(|neighborhood| neighborhood.offsets::<3>(radius).len());
+            assert_eq!(counts, [moore, von_neumann, circular], "radius {radius}");
+        }
+    }
+
+    #[test]
+    fn every_offset_fits_in_a_source() {
+        // Grids remember which offset each cell was spread from as its index + 1 in a u16
+        for neighborhood in Neighborhood::value_variants() {
+            let offsets = neighborhood.offsets::<2>(MAX_SPREAD_RADIUS);
+            assert!(offsets.len() < usize::from(u16::MAX), "{neighborhood}");
+        }
+    }
+
+    #[test]
+    fn unknown_keys_are_named() {
+        let message = json_error(r#"{"colour_shift": 3}"#);
+        assert!(message.contains("colour_shift"), "{message}");
+    }
+}
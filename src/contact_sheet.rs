@@ -0,0 +1,196 @@
+// Contact sheets: every image of a batch shrunk to a thumbnail and laid out in one image, each
+// labeled with the settings it was made with, so the best of a batch can be picked at a glance
+// instead of opening every image. Labels are drawn in a tiny built-in pixel font, since only
+// numbers, settings and palette names need to fit under a thumbnail.
+
+use std::{fs, path::Path};
+
+use image::{
+    imageops::{self, FilterType},
+    ImageBuffer, ImageResult, Rgb, RgbImage,
+};
+
+use crate::{config::SimulationConfig, grid::Grid};
+
+// Size of the square each thumbnail is fitted into, and the space around each one
+const THUMBNAIL_SIZE: u32 = 240;
+const PADDING: u32 = 12;
+// How many pixels each pixel of the font is drawn as, and the space between characters and lines
+const FONT_SCALE: u32 = 2;
+const CHARACTER_GAP: u32 = 1;
+const LINE_GAP: u32 = 4;
+// Lines of settings under each thumbnail
+const LABEL_LINES: u32 = 3;
+
+const BACKGROUND: Rgb<u8> = Rgb([32, 32, 32]);
+const TEXT: Rgb<u8> = Rgb([220, 220, 220]);
+
+// Each character of the font as five rows of three pixels, the leftmost pixel the highest bit
+const GLYPH_WIDTH: u32 = 3;
+const GLYPH_HEIGHT: u32 = 5;
+const UNKNOWN_GLYPH: [u8; 5] = [0b111, 0b001, 0b010, 0b000, 0b010];
+
+// The pixels of `c` in the font, lowercase letters standing in for uppercase ones
+fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_lowercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'a' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'b' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'c' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'd' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'e' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'f' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'g' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'h' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'i' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'j' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'k' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'l' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'm' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'n' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'o' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'p' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'r' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        's' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        't' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'u' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'v' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'w' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'x' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        ' ' => [0; 5],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '(' => [0b010, 0b100, 0b100, 0b100, 0b010],
+        ')' => [0b010, 0b001, 0b001, 0b001, 0b010],
+        _ => UNKNOWN_GLYPH,
+    }
+}
+
+/// Thumbnails of a batch's images with their settings, to be laid out in one image
+#[derive(Default)]
+pub struct ContactSheet {
+    thumbnails: Vec<(RgbImage, [String; LABEL_LINES as usize])>,
+}
+
+impl ContactSheet {
+    /// Adds a thumbnail of `grid`, the `index`th image of the batch, made with `config`
+    pub fn add(&mut self, grid: &Grid, index: u32, config: &SimulationConfig) {
+        let image = ImageBuffer::from_fn(grid.width as u32, grid.height as u32, |x, y| {
+            Rgb(grid.get_color(y as usize, x as usize).as_slice())
+        });
+        let seed = config
+            .seed
+            .map_or_else(|| "random".to_string(), |seed| seed.to_string());
+        let label = [
+            format!("#{index}  seed {seed}"),
+            format!(
+                "{}x{}  cs {}  sc {}",
+                config.width, config.height, config.colorshift, config.spread_chance
+            ),
+            format!("temp {}  {}", config.temperature, config.current_palette()),
+        ];
+        self.thumbnails.push((thumbnail(&image), label));
+    }
+
+    /// Whether no thumbnails were added
+    pub fn is_empty(&self) -> bool {
+        self.thumbnails.is_empty()
+    }
+
+    /// Lays the thumbnails out in rows, as close to a square as they fit, and saves them at
+    /// `path`, creating any missing directories on the way
+    pub fn save(&self, path: &Path) -> ImageResult<()> {
+        let count = self.thumbnails.len() as u32;
+        let columns = (f64::from(count).sqrt().ceil() as u32).max(1);
+        let rows = count.div_ceil(columns).max(1);
+        let line_height = GLYPH_HEIGHT * FONT_SCALE + LINE_GAP;
+        let cell_width = THUMBNAIL_SIZE + PADDING;
+        let cell_height = THUMBNAIL_SIZE + LINE_GAP + LABEL_LINES * line_height + PADDING;
+        let mut sheet = RgbImage::from_pixel(
+            columns * cell_width + PADDING,
+            rows * cell_height + PADDING,
+            BACKGROUND,
+        );
+        for (i, (thumbnail, label)) in (0..).zip(&self.thumbnails) {
+            let (left, top) = (
+                PADDING + i % columns * cell_width,
+                PADDING + i / columns * cell_height,
+            );
+            // Thumbnails that aren't square are centered in their square
+            let x = left + (THUMBNAIL_SIZE - thumbnail.width()) / 2;
+            let y = top + (THUMBNAIL_SIZE - thumbnail.height()) / 2;
+            imageops::replace(&mut sheet, thumbnail, x.into(), y.into());
+            for (line, text) in (0..).zip(label) {
+                let y = top + THUMBNAIL_SIZE + LINE_GAP + line * line_height;
+                draw_text(&mut sheet, text, left, y, THUMBNAIL_SIZE);
+            }
+        }
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        sheet.save(path)
+    }
+}
+
+// `image` scaled to fit a square of THUMBNAIL_SIZE. Small images are scaled up by whole pixels so
+// their cells stay crisp, big ones are scaled down smoothly.
+fn thumbnail(image: &RgbImage) -> RgbImage {
+    let (width, height) = image.dimensions();
+    let longest = width.max(height);
+    if longest <= THUMBNAIL_SIZE {
+        let factor = THUMBNAIL_SIZE / longest;
+        return imageops::resize(image, width * factor, height * factor, FilterType::Nearest);
+    }
+    let fit = |side: u32| (u64::from(side) * u64::from(THUMBNAIL_SIZE) / u64::from(longest)) as u32;
+    imageops::resize(
+        image,
+        fit(width).max(1),
+        fit(height).max(1),
+        FilterType::Triangle,
+    )
+}
+
+// Draws `text` with its top-left corner at (x, y), cutting it off at `max_width` pixels
+fn draw_text(image: &mut RgbImage, text: &str, x: u32, y: u32, max_width: u32) {
+    let advance = (GLYPH_WIDTH + CHARACTER_GAP) * FONT_SCALE;
+    for (i, c) in (0..).zip(text.chars()) {
+        let left = x + i * advance;
+        if (i + 1) * advance > max_width + CHARACTER_GAP * FONT_SCALE {
+            break;
+        }
+        for (row, bits) in (0..).zip(glyph(c)) {
+            for column in 0..GLYPH_WIDTH {
+                if bits >> (GLYPH_WIDTH - 1 - column) & 1 == 0 {
+                    continue;
+                }
+                for dy in 0..FONT_SCALE {
+                    for dx in 0..FONT_SCALE {
+                        let (px, py) = (left + column * FONT_SCALE + dx, y + row * FONT_SCALE + dy);
+                        if px < image.width() && py < image.height() {
+                            image.put_pixel(px, py, TEXT);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+// The `doctor` subcommand: checks everything a run depends on that can differ between machines
+// (the terminal, the output directory, memory, what the build includes) and runs a short
+// simulation, then sums up whether the program is ready to use. Its output is meant to be pasted
+// into bug reports.
+
+use std::{
+    fs,
+    io::{self, IsTerminal},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, SeedableRng};
+use sysinfo::System;
+
+use crate::{
+    cli::DoctorArgs,
+    config::SimulationConfig,
+    estimate::{format_bytes, memory_bytes},
+    history::HISTORY_SIZE_DEFAULT,
+    i18n::t,
+    simulation::{starting_grid, yx_coordinate_pairs},
+    terminal::{Capabilities, Renderer},
+};
+
+// How long the test simulation runs for, and the size of its grid
+const SIMULATION_TIME: Duration = Duration::from_secs(1);
+const SIMULATION_SIZE: usize = 64;
+// File written to the output directory to check it can be written to, and removed again
+const PROBE_FILE: &str = ".spreading_colors_doctor";
+
+// How a check went
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Ok,
+    // Works, but not as well as it could
+    Warning,
+    // Keeps the program from working
+    Problem,
+}
+
+/// Runs every check, printing each outcome and a summary
+pub fn run(args: &DoctorArgs) {
+    let outcomes = [
+        check_terminal(),
+        check_out_dir(&args.out_dir),
+        check_memory(),
+        check_features(),
+        check_simulation(),
+    ];
+
+    println!();
+    let count = |outcome| outcomes.iter().filter(|o| **o == outcome).count();
+    let (warnings, problems) = (count(Outcome::Warning), count(Outcome::Problem));
+    if problems > 0 {
+        println!("{}", t!("doctor.not_ready", problems = problems));
+    } else if warnings > 0 {
+        println!("{}", t!("doctor.ready_with_warnings", warnings = warnings));
+    } else {
+        println!("{}", t!("doctor.ready"));
+    }
+}
+
+// Prints a check's outcome and what it found, with any details indented below
+fn report(outcome: Outcome, message: &str, details: &[String]) -> Outcome {
+    let label = match outcome {
+        Outcome::Ok => t!("doctor.ok"),
+        Outcome::Warning => t!("doctor.warning"),
+        Outcome::Problem => t!("doctor.problem"),
+    };
+    println!("[{label}] {message}");
+    for detail in details {
+        println!("    {detail}");
+    }
+    outcome
+}
+
+// What the terminal supports, and how drawing is downgraded on it
+fn check_terminal() -> Outcome {
+    let capabilities = Capabilities::detect();
+    let message = t!("doctor.terminal", capabilities = capabilities);
+    // Output saved for a bug report goes to a file, which says nothing about the terminal
+    if !io::stdout().is_terminal() {
+        return report(Outcome::Ok, &message, &[t!("doctor.not_a_terminal")]);
+    }
+    let downgrades = Renderer::new(capabilities).downgrades();
+    let outcome = if downgrades.is_empty() {
+        Outcome::Ok
+    } else {
+        Outcome::Warning
+    };
+    report(outcome, &message, &downgrades)
+}
+
+// Whether images can be saved in `dir`, by creating it and writing a file there
+fn check_out_dir(dir: &Path) -> Outcome {
+    let probe = dir.join(PROBE_FILE);
+    let written = fs::create_dir_all(dir)
+        .and_then(|()| fs::write(&probe, b""))
+        .and_then(|()| fs::remove_file(&probe));
+    let dir = dir.display();
+    match written {
+        Ok(()) => report(Outcome::Ok, &t!("doctor.out_dir", dir = dir), &[]),
+        Err(error) => report(
+            Outcome::Problem,
+            &t!("doctor.out_dir_unwritable", dir = dir, error = error),
+            &[],
+        ),
+    }
+}
+
+// Whether there's enough free memory for a run with the default settings
+fn check_memory() -> Outcome {
+    let mut system = System::new();
+    system.refresh_memory();
+    let available = system.available_memory();
+    let config = SimulationConfig {
+        show_while_running: true,
+        ..SimulationConfig::default()
+    };
+    let needed = memory_bytes(&config, HISTORY_SIZE_DEFAULT);
+    let message = t!(
+        "doctor.memory",
+        available = format_bytes(available),
+        needed = format_bytes(needed)
+    );
+    // Some systems don't report their memory, which isn't worth warning about
+    let outcome = if available == 0 || available >= needed {
+        Outcome::Ok
+    } else {
+        Outcome::Problem
+    };
+    report(outcome, &message, &[])
+}
+
+// Which optional features the program was built with, and whether the GPU for shaders works
+fn check_features() -> Outcome {
+    let features = [
+        ("shaders", cfg!(feature = "shaders")),
+        ("plugins", cfg!(feature = "plugins")),
+        ("notify", cfg!(feature = "notify")),
+        ("microphone", cfg!(feature = "microphone")),
+        ("python", cfg!(feature = "python")),
+        ("bevy", cfg!(feature = "bevy")),
+    ];
+    let list = |included: bool| {
+        let names: Vec<&str> = features
+            .iter()
+            .filter(|(_, built)| *built == included)
+            .map(|(name, _)| *name)
+            .collect();
+        if names.is_empty() {
+            t!("doctor.none")
+        } else {
+            names.join(", ")
+        }
+    };
+    let mut details = vec![t!("doctor.features_missing", features = list(false))];
+    let outcome = gpu_outcome(&mut details);
+    report(
+        outcome,
+        &t!("doctor.features", features = list(true)),
+        &details,
+    )
+}
+
+// Whether shaders have a GPU to run on, added to `details`
+#[cfg(feature = "shaders")]
+fn gpu_outcome(details: &mut Vec<String>) -> Outcome {
+    match crate::shader::gpu_name() {
+        Ok(name) => {
+            details.push(t!("doctor.gpu", name = name));
+            Outcome::Ok
+        }
+        Err(error) => {
+            details.push(error);
+            Outcome::Warning
+        }
+    }
+}
+
+#[cfg(not(feature = "shaders"))]
+fn gpu_outcome(_details: &mut Vec<String>) -> Outcome {
+    Outcome::Ok
+}
+
+// Runs small simulations for a moment, starting over whenever one finishes, to check simulating
+// works and see how fast it goes
+fn check_simulation() -> Outcome {
+    let config = SimulationConfig {
+        width: SIMULATION_SIZE,
+        height: SIMULATION_SIZE,
+        ..SimulationConfig::default()
+    };
+    let mut rng = StdRng::seed_from_u64(0);
+    let yx_coordinate_pairs = yx_coordinate_pairs(config.width, config.height, config.boundary);
+    let mut grid = starting_grid(&config, &mut rng);
+    let mut generations = 0u64;
+    let start = Instant::now();
+    while start.elapsed() < SIMULATION_TIME {
+        if !grid.step(&yx_coordinate_pairs, &mut rng) {
+            grid = starting_grid(&config, &mut rng);
+        }
+        generations += 1;
+    }
+    let per_second = (generations as f64 / start.elapsed().as_secs_f64()).round();
+    report(
+        Outcome::Ok,
+        &t!(
+            "doctor.simulation",
+            size = SIMULATION_SIZE,
+            generations = per_second
+        ),
+        &[],
+    )
+}
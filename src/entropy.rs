@@ -0,0 +1,111 @@
+// Seeds from the physical world for fun: a burst of microphone noise or the readings of the
+// machine's sensors get hashed into the seed, which then decides the starting cells and colors.
+// The seed is printed like any other, so runs seeded this way can still be reproduced.
+
+use std::{
+    hash::{DefaultHasher, Hasher},
+    thread,
+};
+
+use clap::ValueEnum;
+use sysinfo::{Components, Networks, System, MINIMUM_CPU_UPDATE_INTERVAL};
+
+// Number of times the sensors are read. Readings drift between samples, which is the point.
+const SENSOR_SAMPLES: u32 = 5;
+
+// How long the microphone records for
+#[cfg(feature = "microphone")]
+const MICROPHONE_BURST: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Where a seed's randomness comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EntropySource {
+    /// Temperatures, CPU load, memory use and network traffic, read a few times over a second
+    Sensors,
+    /// Half a second of microphone noise (needs the `microphone` feature)
+    Microphone,
+}
+
+/// Derives a seed from `source`, or explains why the source couldn't be read
+pub fn gather_seed(source: EntropySource) -> Result<u64, String> {
+    let noise = match source {
+        EntropySource::Sensors => sensor_readings(),
+        EntropySource::Microphone => microphone_noise()?,
+    };
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&noise);
+    Ok(hasher.finish())
+}
+
+// The raw bytes of every sensor reading, sampled several times
+fn sensor_readings() -> Vec<u8> {
+    let mut readings = Vec::new();
+    let mut system = System::new();
+    let mut components = Components::new_with_refreshed_list();
+    let mut networks = Networks::new_with_refreshed_list();
+
+    for _ in 0..SENSOR_SAMPLES {
+        // CPU usage is measured between two refreshes, so they need to be this far apart
+        thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+        system.refresh_cpu_usage();
+        system.refresh_memory();
+        components.refresh(false);
+        networks.refresh(false);
+
+        for cpu in system.cpus() {
+            readings.extend(cpu.cpu_usage().to_bits().to_le_bytes());
+            readings.extend(cpu.frequency().to_le_bytes());
+        }
+        readings.extend(system.used_memory().to_le_bytes());
+        for component in &components {
+            let temperature = component.temperature().unwrap_or_default();
+            readings.extend(temperature.to_bits().to_le_bytes());
+        }
+        for (_, network) in &networks {
+            readings.extend(network.total_received().to_le_bytes());
+            readings.extend(network.total_transmitted().to_le_bytes());
+        }
+    }
+    readings
+}
+
+// The raw samples of a short recording from the default microphone
+#[cfg(feature = "microphone")]
+fn microphone_noise() -> Result<Vec<u8>, String> {
+    use std::sync::{Arc, Mutex};
+
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or_else(|| "no microphone was found".to_string())?;
+    let config = device.default_input_config().map_err(|e| e.to_string())?;
+
+    let noise = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&noise);
+    let stream = device
+        .build_input_stream_raw(
+            &config.config(),
+            config.sample_format(),
+            move |data: &cpal::Data, _: &cpal::InputCallbackInfo| {
+                recorded.lock().unwrap().extend_from_slice(data.bytes());
+            },
+            |e| eprintln!("{}", crate::i18n::t!("run.microphone_error", error = e)),
+            None,
+        )
+        .map_err(|e| e.to_string())?;
+    stream.play().map_err(|e| e.to_string())?;
+    thread::sleep(MICROPHONE_BURST);
+    drop(stream);
+
+    let noise = noise.lock().unwrap().clone();
+    if noise.is_empty() {
+        return Err("the microphone didn't record anything".to_string());
+    }
+    Ok(noise)
+}
+
+#[cfg(not(feature = "microphone"))]
+fn microphone_noise() -> Result<Vec<u8>, String> {
+    Err("this build can't record audio, rebuild it with `--features microphone`".to_string())
+}
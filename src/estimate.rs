@@ -0,0 +1,212 @@
+// Rough estimates of how long a run takes and how much it writes to disk,
+// used to warn before recordings or exports that would fill up the disk.
+
+use std::mem::size_of;
+
+use inquire::Confirm;
+
+use crate::{
+    config::{Neighborhood, SimulationConfig},
+    grid::RgbColor,
+    history::CellChange,
+    i18n::t,
+    verbosity::status,
+};
+
+// How many times as long runs spreading without diagonal neighbors take, measured like the rest
+const VON_NEUMANN_SLOWDOWN: f64 = 1.2;
+
+// Exports estimated to be bigger than this need confirming, unless the user picks another limit
+pub const MAX_EXPORT_SIZE_DEFAULT: &str = "1GB";
+
+/// Roughly how many generations a run takes to fill the grid, or `None` if it never will.
+/// Fitted against measured runs: colonies grow about `spread_chance` cells per generation,
+/// more starting cells means less distance for each of them to cover. Growth without diagonals
+/// takes a bit longer, and a bigger spread radius covers more ground each generation.
+pub fn expected_generations(config: &SimulationConfig) -> Option<u64> {
+    if config.spread_chance <= 0.0 {
+        return None;
+    }
+    let longest_side = config.width.max(config.height) as f64;
+    let starting_cells = f64::from(config.starting_live_cells.max(1));
+    let neighborhood = match config.neighborhood {
+        Neighborhood::Moore => 1.0,
+        Neighborhood::VonNeumann | Neighborhood::Circular => VON_NEUMANN_SLOWDOWN,
+    };
+    let radius = f64::from(config.spread_radius.max(1)).powf(0.8);
+    let generations = 0.55 * neighborhood * longest_side
+        / (radius * starting_cells.powf(0.25) * config.spread_chance.powf(0.9));
+    Some(generations.ceil() as u64)
+}
+
+// Estimates saturate at u64::MAX rather than overflowing, so a huge grid is never mistaken for a
+// small one by the size checks
+
+/// Size of one uncompressed 8-bit RGB frame of the grid
+pub fn frame_bytes(config: &SimulationConfig) -> u64 {
+    cell_count(config).saturating_mul(3)
+}
+
+// How many cells the grid has
+fn cell_count(config: &SimulationConfig) -> u64 {
+    (config.width as u64).saturating_mul(config.height as u64)
+}
+
+/// Size of one uncompressed saved image of the grid, each cell scaled up to `scale`x`scale` pixels
+pub fn image_bytes(config: &SimulationConfig, scale: u32) -> u64 {
+    frame_bytes(config).saturating_mul(u64::from(scale).pow(2))
+}
+
+/// Roughly how much memory a run needs at its peak: the grid itself, the list of cells to simulate,
+/// the image buffer when saving, and for animated runs the rewind history of up to `history_size`
+/// generations (every cell changes about once over a whole run).
+pub fn memory_bytes(config: &SimulationConfig, history_size: usize) -> u64 {
+    let cells = cell_count(config);
+    let grid = cells.saturating_mul((size_of::<bool>() + size_of::<RgbColor>()) as u64);
+    let coordinates = cells.saturating_mul(size_of::<[usize; 2]>() as u64);
+
+    let history = if config.show_while_running {
+        let generations = expected_generations(config).unwrap_or(u64::MAX).max(1);
+        let kept = (history_size as u64).min(generations) as f64 / generations as f64;
+        // Float to int casts saturate too
+        (kept * cells as f64 * size_of::<CellChange>() as f64) as u64
+    } else {
+        0
+    };
+    [grid, coordinates, frame_bytes(config), history]
+        .into_iter()
+        .fold(0, u64::saturating_add)
+}
+
+/// Formats a byte count for humans, e.g. 1536 -> "1.5 KB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// Parses sizes like "500MB", "1.5GB", "2g" or a plain number of bytes
+pub fn parse_byte_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{s}' doesn't start with a number"))?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" => 1 << 10,
+        "m" | "mb" => 1 << 20,
+        "g" | "gb" => 1 << 30,
+        "t" | "tb" => 1 << 40,
+        other => {
+            return Err(format!(
+                "unknown size unit '{other}', expected B, KB, MB, GB or TB"
+            ))
+        }
+    };
+    let bytes = number * multiplier as f64;
+    if bytes >= u64::MAX as f64 {
+        return Err(format!("'{s}' is too big a size"));
+    }
+    Ok(bytes as u64)
+}
+
+/// Checks an export's estimated size against `limit`, asking the user to confirm it if it's over.
+/// Without prompts (`interactive` is false) oversized exports are refused.
+/// Returns whether the export should go ahead.
+pub fn confirm_export_size(
+    what: &str,
+    estimated_bytes: u64,
+    limit: u64,
+    interactive: bool,
+) -> bool {
+    if estimated_bytes <= limit {
+        return true;
+    }
+
+    let warning = t!(
+        "export.too_big",
+        what = what,
+        size = format_bytes(estimated_bytes),
+        limit = format_bytes(limit)
+    );
+    if !interactive {
+        status!("{}", t!("export.skipped", warning = warning));
+        return false;
+    }
+    println!("{warning}");
+    Confirm::new(&t!("prompt.continue_anyway"))
+        .with_default(false)
+        .prompt()
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_parse_in_every_unit() {
+        for (size, bytes) in [
+            ("500MB", 500 << 20),
+            ("2GB", 2 << 30),
+            ("2g", 2 << 30),
+            ("1.5 kb", 1536),
+            ("1TB", 1 << 40),
+            ("4096", 4096),
+            ("12b", 12),
+            (" 64 KB ", 64 << 10),
+        ] {
+            assert_eq!(parse_byte_size(size), Ok(bytes), "{size}");
+        }
+    }
+
+    #[test]
+    fn junk_is_rejected() {
+        for size in ["", "MB", "lots", "5 parsecs", "1.2.3MB", "-5MB"] {
+            assert!(parse_byte_size(size).is_err(), "{size}");
+        }
+    }
+
+    #[test]
+    fn sizes_past_u64_are_rejected() {
+        assert!(parse_byte_size("99999999999TB").is_err());
+        assert!(parse_byte_size("18446744073709551616").is_err());
+    }
+
+    #[test]
+    fn estimates_saturate_instead_of_overflowing() {
+        let config = SimulationConfig {
+            width: usize::MAX,
+            height: usize::MAX,
+            show_while_running: true,
+            ..SimulationConfig::default()
+        };
+        assert_eq!(frame_bytes(&config), u64::MAX);
+        assert_eq!(image_bytes(&config, u32::MAX), u64::MAX);
+        assert_eq!(memory_bytes(&config, usize::MAX), u64::MAX);
+
+        // Big enough to overflow once scaled up, though the grid alone fits
+        let config = SimulationConfig {
+            width: 1 << 20,
+            height: 1 << 20,
+            ..SimulationConfig::default()
+        };
+        assert_eq!(frame_bytes(&config), 3 << 40);
+        assert_eq!(image_bytes(&config, 1 << 16), u64::MAX);
+        assert!(memory_bytes(&config, 0) > frame_bytes(&config));
+    }
+}
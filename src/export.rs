@@ -0,0 +1,802 @@
+use std::{
+    fmt::Display,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use chrono::Local;
+use clap::{Args, ValueEnum};
+use image::{
+    imageops::{self, FilterType},
+    GrayImage, ImageBuffer, ImageError, ImageResult, Luma, Pixel, Rgb, RgbImage, Rgba, RgbaImage,
+};
+use inquire::Confirm;
+use serde::{Serialize, Serializer};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    color_space::{save_tagged, ColorSpace},
+    config::SimulationConfig,
+    format::FormatOptions,
+    grid::Grid,
+    i18n::t,
+    metadata::Metadata,
+    scgrid,
+    shader::{PostShader, ShaderError},
+    terminal::{renderer, Renderer},
+    verbosity::status,
+};
+
+/// Names an image after when it was made and the settings that made it,
+/// e.g. "2024-05-12_1830_w512h256_cs6_sc0.45_seed12345.png",
+/// so outputs don't collide and their settings can be read back from the name
+pub fn descriptive_filename(
+    config: &SimulationConfig,
+    seed: impl Display,
+    extension: &str,
+) -> String {
+    format!(
+        "{}_w{}h{}_cs{}_sc{}_seed{seed}.{extension}",
+        Local::now().format("%Y-%m-%d_%H%M"),
+        config.width,
+        config.height,
+        config.colorshift,
+        config.spread_chance
+    )
+}
+
+/// Adds `suffix` to the end of a filename, before its extension,
+/// e.g. "image.png" with the suffix "_gen100" becomes "image_gen100.png"
+pub fn with_filename_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("png");
+    path.with_file_name(format!("{stem}{suffix}.{extension}"))
+}
+
+/// A quarter turn, half turn or three quarter turn saved images are given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Rotation {
+    /// A quarter turn clockwise
+    #[value(name = "90")]
+    Quarter,
+    /// Upside down
+    #[value(name = "180")]
+    Half,
+    /// A quarter turn counterclockwise
+    #[value(name = "270")]
+    ThreeQuarters,
+}
+
+/// How saved images are turned and mirrored from the way the grid is shown in the terminal
+#[derive(Debug, Clone, Copy, Default, Args)]
+pub struct Orientation {
+    /// Turn saved images clockwise by this many degrees, e.g. to make a wide run a phone
+    /// wallpaper. Done after any flips
+    #[arg(
+        long,
+        value_enum,
+        value_name = "DEGREES",
+        env = "SPREADING_COLORS_ROTATE"
+    )]
+    pub rotate: Option<Rotation>,
+
+    /// Mirror saved images left to right
+    #[arg(long, env = "SPREADING_COLORS_FLIP_HORIZONTAL")]
+    pub flip_horizontal: bool,
+
+    /// Mirror saved images top to bottom
+    #[arg(long, env = "SPREADING_COLORS_FLIP_VERTICAL")]
+    pub flip_vertical: bool,
+}
+
+// An image with pixels of any kind, e.g. RGB or grayscale
+type Image<P> = ImageBuffer<P, Vec<<P as Pixel>::Subpixel>>;
+
+impl Orientation {
+    /// `img` flipped, then turned
+    pub fn apply<P: Pixel + 'static>(&self, mut img: Image<P>) -> Image<P> {
+        if self.flip_horizontal {
+            imageops::flip_horizontal_in_place(&mut img);
+        }
+        if self.flip_vertical {
+            imageops::flip_vertical_in_place(&mut img);
+        }
+        match self.rotate {
+            Some(Rotation::Quarter) => imageops::rotate90(&img),
+            Some(Rotation::Half) => {
+                imageops::rotate180_in_place(&mut img);
+                img
+            }
+            Some(Rotation::ThreeQuarters) => imageops::rotate270(&img),
+            None => img,
+        }
+    }
+}
+
+/// An extra image saved next to each image, named after it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Layer {
+    /// Live cells in white and dead ones in black, for selecting or masking the image in an
+    /// image editor. Saved as e.g. image_mask.png
+    Mask,
+    /// Live cells colored by the generation they came to life in, from dark purple for the first
+    /// to yellow for the last, showing how the colors grew. Saved as e.g. image_age.png
+    Age,
+    /// A streak across each cell pointing the way it was spread into from its parent, colored by
+    /// that direction, on a transparent background for laying over the image. Saved as e.g.
+    /// image_directions.png. Streaks need a few pixels per cell to show, see --scale
+    Directions,
+}
+
+// Colors along the gradient the age layer is drawn in (viridis), evenly spaced from oldest to
+// newest. Viridis reads as a steady change in brightness, in color and in grayscale alike.
+const AGE_GRADIENT: [[u8; 3]; 6] = [
+    [68, 1, 84],
+    [65, 68, 135],
+    [42, 120, 142],
+    [34, 168, 132],
+    [122, 209, 81],
+    [253, 231, 37],
+];
+
+impl Layer {
+    // What's added to the image's name for the layer's
+    fn suffix(self) -> &'static str {
+        match self {
+            Layer::Mask => "_mask",
+            Layer::Age => "_age",
+            Layer::Directions => "_directions",
+        }
+    }
+}
+
+// How far the streaks of the directions layer start from transparent
+const STREAK_TAIL_ALPHA: f64 = 0.2;
+
+// Which cells of `grid` are alive, white if they are, a pixel per cell
+fn mask_image(grid: &Grid) -> GrayImage {
+    ImageBuffer::from_fn(grid.width as u32, grid.height as u32, |x, y| {
+        let alive = grid.alive_states[[y as usize, x as usize]];
+        Luma([if alive { u8::MAX } else { 0 }])
+    })
+}
+
+// When each live cell of `grid` came to life, along `AGE_GRADIENT` from the first generation to
+// the latest one any cell was born in, a pixel per cell. Dead cells are black.
+fn age_image(grid: &Grid) -> RgbImage {
+    let last = (0..grid.height)
+        .flat_map(|y| (0..grid.width).filter_map(move |x| grid.birth_generation(y, x)))
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    ImageBuffer::from_fn(grid.width as u32, grid.height as u32, |x, y| {
+        match grid.birth_generation(y as usize, x as usize) {
+            Some(born) => Rgb(gradient_color(born as f64 / last as f64)),
+            None => Rgb([0; 3]),
+        }
+    })
+}
+
+// Which way each live cell of `grid` was spread into, as a streak across its square of `scale` pixels
+// from the side its parent is on to the opposite one, fading in towards its head. Cells without a
+// parent, and dead ones, are left transparent.
+fn directions_image(grid: &Grid, scale: u32) -> RgbaImage {
+    let mut img = RgbaImage::new(grid.width as u32 * scale, grid.height as u32 * scale);
+    // The streak runs from corner to corner or edge to edge through the middle of the square
+    let reach = f64::from(scale - 1) / 2.0;
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            let Some((dy, dx)) = grid.spread_direction(y, x) else {
+                continue;
+            };
+            let [red, green, blue] = direction_color(dy.signum(), dx.signum());
+            let (top, left) = (y as f64 * f64::from(scale), x as f64 * f64::from(scale));
+            // Cells spread from further away point the same way, across the same square
+            let longest = dy.abs().max(dx.abs()) as f64;
+            let (down, right) = (dy as f64 / longest, dx as f64 / longest);
+            for step in 0..scale {
+                let along = if scale == 1 {
+                    1.0
+                } else {
+                    f64::from(step) / f64::from(scale - 1)
+                };
+                let offset = (2.0 * along - 1.0) * reach;
+                let px = (left + reach + right * offset).round() as u32;
+                let py = (top + reach + down * offset).round() as u32;
+                let alpha = STREAK_TAIL_ALPHA + (1.0 - STREAK_TAIL_ALPHA) * along;
+                img.put_pixel(px, py, Rgba([red, green, blue, (alpha * 255.0) as u8]));
+            }
+        }
+    }
+    img
+}
+
+// The color streaks going (dy, dx) are drawn in, around the color wheel from red for right
+fn direction_color(dy: isize, dx: isize) -> [u8; 3] {
+    match (dy, dx) {
+        (0, 1) => [255, 80, 80],
+        (-1, 1) => [255, 190, 70],
+        (-1, 0) => [200, 255, 80],
+        (-1, -1) => [80, 255, 140],
+        (0, -1) => [80, 230, 255],
+        (1, -1) => [90, 140, 255],
+        (1, 0) => [170, 100, 255],
+        _ => [255, 90, 210],
+    }
+}
+
+// The color `position` (from 0 to 1) of the way along `AGE_GRADIENT`
+fn gradient_color(position: f64) -> [u8; 3] {
+    let scaled = position.clamp(0.0, 1.0) * (AGE_GRADIENT.len() - 1) as f64;
+    let index = (scaled.floor() as usize).min(AGE_GRADIENT.len() - 2);
+    let fraction = scaled - index as f64;
+    let (from, to) = (AGE_GRADIENT[index], AGE_GRADIENT[index + 1]);
+    std::array::from_fn(|c| {
+        (f64::from(from[c]) + (f64::from(to[c]) - f64::from(from[c])) * fraction).round() as u8
+    })
+}
+
+/// What saved images are named after with `--hash-names`, instead of their settings or `--output`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HashNames {
+    /// The image's pixels, so images that come out the same are only saved once
+    Content,
+    /// The settings and seed the image is made from, so images that were already made
+    /// can be skipped without rendering them again
+    Settings,
+}
+
+/// `path` with its file name replaced by a hash of `bytes`, keeping its directory and extension,
+/// e.g. "out/art.jpg" becomes "out/3f9a04c2d1e5b7a8.jpg"
+pub fn hashed_path(path: &Path, bytes: &[u8]) -> PathBuf {
+    let hash = Sha256::digest(bytes);
+    // 16 hex digits are plenty to tell a directory's worth of images apart
+    let name: String = hash[..8].iter().map(|byte| format!("{byte:02x}")).collect();
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("png");
+    path.with_file_name(format!("{name}.{extension}"))
+}
+
+/// How exported images are drawn and named, beyond the grid's colors. Text exports ignore the
+/// drawing options.
+#[derive(Debug, Clone)]
+pub struct ImageOptions {
+    /// Fragment shader the image is drawn through before it's saved
+    pub shader: Option<PostShader>,
+    /// How many pixels wide and high each cell is drawn
+    pub scale: u32,
+    /// How the image is turned and mirrored
+    pub orientation: Orientation,
+    /// Extra images saved next to the image, drawn at the same scale and orientation
+    pub layers: Vec<Layer>,
+    /// Color space PNG and JPEG images are tagged with
+    pub color_space: ColorSpace,
+    /// Format images are saved in, and its settings
+    pub format: FormatOptions,
+    /// Authorship PNG and JPEG images carry
+    pub metadata: Metadata,
+    /// What images are named after, if they're named by a hash
+    pub hash_names: Option<HashNames>,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            shader: None,
+            scale: 1,
+            orientation: Orientation::default(),
+            layers: Vec::new(),
+            color_space: ColorSpace::default(),
+            format: FormatOptions::default(),
+            metadata: Metadata::default(),
+            hash_names: None,
+        }
+    }
+}
+
+impl ImageOptions {
+    /// Options drawing images with each cell `scale` pixels wide, turned as `orientation` says,
+    /// through the shader at `shader` (if one is given), tagging them with `color_space` and `metadata`, saving them as `format`
+    /// says and naming them by `hash_names`. The shader is read right away, so a missing one is
+    /// reported before anything is rendered.
+    pub fn new(
+        shader: Option<&Path>,
+        scale: u32,
+        orientation: Orientation,
+        color_space: ColorSpace,
+        format: &FormatOptions,
+        metadata: &Metadata,
+        hash_names: Option<HashNames>,
+    ) -> Result<Self, ShaderError> {
+        Ok(Self {
+            shader: shader.map(PostShader::load).transpose()?,
+            scale: scale.max(1),
+            orientation,
+            layers: Vec::new(),
+            color_space,
+            format: format.clone(),
+            metadata: metadata.clone(),
+            hash_names,
+        })
+    }
+
+    /// Saves `layers` next to each image too
+    pub fn with_layers(self, layers: &[Layer]) -> Self {
+        Self {
+            layers: layers.to_vec(),
+            ..self
+        }
+    }
+
+    /// Whether the grids images are saved from have to track when and where their cells were born
+    pub fn needs_births(&self) -> bool {
+        self.layers.contains(&Layer::Age) || self.layers.contains(&Layer::Directions)
+    }
+
+    // `img` turned and scaled up as the options say
+    fn arrange<P: Pixel + 'static>(&self, img: Image<P>) -> Image<P> {
+        let img = self.orientation.apply(img);
+        match self.scale {
+            1 => img,
+            scale => {
+                let (width, height) = (img.width() * scale, img.height() * scale);
+                imageops::resize(&img, width, height, FilterType::Nearest)
+            }
+        }
+    }
+
+    /// Where an image meant for `path` and made from `config` (which has to include the seed) is
+    /// saved: under a hash of its settings when images are named by them, otherwise at `path`.
+    /// The hash covers the options changing the pixels and this version too, since either
+    /// could make the same settings come out differently.
+    pub fn settings_path(&self, path: &Path, config: &SimulationConfig) -> PathBuf {
+        if self.hash_names != Some(HashNames::Settings) {
+            return path.to_path_buf();
+        }
+        let mut bytes = serde_json::to_vec(config).expect("settings can always be serialized");
+        bytes.extend(env!("CARGO_PKG_VERSION").as_bytes());
+        bytes.extend(self.pixel_options());
+        hashed_path(path, &bytes)
+    }
+
+    /// Whether an image named by its settings is already saved at `path` (from `settings_path`),
+    /// so it doesn't have to be rendered again. Says so when it is.
+    pub fn already_saved(&self, path: &Path) -> bool {
+        let saved = self.hash_names == Some(HashNames::Settings) && path.exists();
+        if saved {
+            status!("{}", t!("export.already_saved", path = path.display()));
+        }
+        saved
+    }
+
+    // The options that change an image's pixels, as bytes for hashing
+    fn pixel_options(&self) -> Vec<u8> {
+        let Self {
+            shader,
+            scale,
+            orientation,
+            color_space,
+            ..
+        } = self;
+        format!("{shader:?}{scale}{orientation:?}{color_space:?}").into_bytes()
+    }
+
+    // Where an image of `grid` meant for `path` is saved when images are named by their content:
+    // under a hash of its colors and the options changing its pixels
+    fn content_path(&self, grid: &Grid, path: &Path) -> PathBuf {
+        let mut bytes = Vec::with_capacity(16 + grid.width * grid.height * 3);
+        bytes.extend((grid.width as u64).to_le_bytes());
+        bytes.extend((grid.height as u64).to_le_bytes());
+        for (_, color, _) in grid.cells() {
+            bytes.extend(color.as_slice());
+        }
+        bytes.extend(self.pixel_options());
+        hashed_path(path, &bytes)
+    }
+}
+
+/// Writes the grid as an image at `path`, creating any missing directories on the way.
+/// The image format is picked from the file extension. `.txt` files get the grid drawn in glyphs,
+/// and `.ans` files get it drawn in colored blocks, as ANSI art. `.json` and `.csv` files get the
+/// state of every cell as data, and `.scgrid` files a compact binary dump of it (see `scgrid.rs`).
+/// `.ppm` and `.pam` files are written uncompressed, without any metadata.
+pub fn write_image(grid: &Grid, path: &Path, options: &ImageOptions) -> ImageResult<()> {
+    if path.extension().is_some_and(|ext| ext == "scgrid") {
+        return scgrid::write(grid, path).map_err(ImageError::IoError);
+    }
+    if path.extension().is_some_and(|ext| ext == "json") {
+        return write_json(grid, path).map_err(ImageError::IoError);
+    }
+    if path.extension().is_some_and(|ext| ext == "csv") {
+        return write_csv(grid, path).map_err(ImageError::IoError);
+    }
+    if path.extension().is_some_and(|ext| ext == "txt") {
+        return write_text(grid, path, &renderer().for_text_export()).map_err(ImageError::IoError);
+    }
+    if path.extension().is_some_and(|ext| ext == "ans") {
+        return write_text(grid, path, &renderer().for_ansi_export()).map_err(ImageError::IoError);
+    }
+
+    // save the result as an image using the `image` crate
+    let img = image::ImageBuffer::from_fn(
+        grid.width.try_into().unwrap(),
+        grid.height.try_into().unwrap(),
+        |x, y| {
+            let y: usize = y.try_into().unwrap();
+            let x: usize = x.try_into().unwrap();
+            image::Rgb(grid.get_color(y, x).as_slice())
+        },
+    );
+    // Cells are scaled up before the shader, so its effects are drawn at the full resolution
+    let img = options.arrange(img);
+    let img = match &options.shader {
+        Some(shader) => shader
+            .apply(&img)
+            .map_err(|e| ImageError::IoError(io::Error::other(e)))?,
+        None => img,
+    };
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(ImageError::IoError)?;
+    }
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    if let Some(netpbm) =
+        extension.filter(|ext| ext.eq_ignore_ascii_case("ppm") || ext.eq_ignore_ascii_case("pam"))
+    {
+        let pam = netpbm.eq_ignore_ascii_case("pam");
+        let file = BufWriter::new(File::create(path)?);
+        return write_netpbm(&img, file, pam).map_err(ImageError::IoError);
+    }
+    let settings = options
+        .metadata
+        .embed_settings
+        .then(|| grid.settings_summary());
+    save_tagged(
+        &img,
+        path,
+        options.color_space,
+        &options.format,
+        &options.metadata,
+        settings.as_deref(),
+        &grid.recipe(),
+    )
+}
+
+// Writes `grid`'s `layer` at `path` as a PNG, creating any missing directories on the way
+fn write_layer(grid: &Grid, layer: Layer, path: &Path, options: &ImageOptions) -> ImageResult<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    match layer {
+        Layer::Mask => options.arrange(mask_image(grid)).save(path),
+        Layer::Age => options.arrange(age_image(grid)).save(path),
+        // Streaks are drawn at the image's scale, since scaling them up would only thicken them
+        Layer::Directions => options
+            .orientation
+            .apply(directions_image(grid, options.scale))
+            .save(path),
+    }
+}
+
+/// Writes `img` into `file` as a binary PPM, or a PAM if `pam` is set: a short text header followed
+/// by the raw RGB bytes row by row. Nothing is compressed, so it's about as fast as writing the bytes
+/// at all, which makes a difference for huge grids only meant as input for other tools. The `image`
+/// crate would write PAM files with a PPM header, so both are written here.
+pub fn write_netpbm(img: &RgbImage, mut file: impl Write, pam: bool) -> io::Result<()> {
+    let (width, height) = img.dimensions();
+    if pam {
+        write!(
+            file,
+            "P7\nWIDTH {width}\nHEIGHT {height}\nDEPTH 3\nMAXVAL 255\nTUPLTYPE RGB\nENDHDR\n"
+        )?;
+    } else {
+        write!(file, "P6\n{width} {height}\n255\n")?;
+    }
+    file.write_all(img.as_raw())?;
+    file.flush()
+}
+
+// Every cell of a grid, row by row, as what `cell` gives for its (y, x)
+struct Rows<'a, T> {
+    grid: &'a Grid,
+    cell: fn(&Grid, usize, usize) -> T,
+}
+
+impl<T: Serialize> Serialize for Rows<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq((0..self.grid.height).map(|y| {
+            (0..self.grid.width)
+                .map(|x| (self.cell)(self.grid, y, x))
+                .collect::<Vec<_>>()
+        }))
+    }
+}
+
+// The grid as it's written to JSON files, for other tools to load. Rows go from top to bottom and
+// cells from left to right, taking in every cell of the grid like saved images do.
+#[derive(Serialize)]
+struct GridJson<'a> {
+    width: usize,
+    height: usize,
+    generation: u64,
+    settings: String,
+    alive: Rows<'a, bool>,
+    colors: Rows<'a, [u8; 3]>,
+}
+
+// Writes the grid as JSON: its size and settings, then whether each cell is alive and its color
+// as [red, green, blue]
+fn write_json(grid: &Grid, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let json = GridJson {
+        width: grid.width,
+        height: grid.height,
+        generation: grid.generation,
+        settings: grid.settings_summary(),
+        alive: Rows {
+            grid,
+            cell: |grid, y, x| grid.alive_states[[y, x]],
+        },
+        colors: Rows {
+            grid,
+            cell: |grid, y, x| grid.get_color(y, x).as_slice(),
+        },
+    };
+    let mut file = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(&mut file, &json)?;
+    file.flush()
+}
+
+// Writes the grid as CSV, a line for every cell with its position, color and whether it's alive
+// (1 or 0), under a header naming the columns. Cells go row by row, like in the JSON.
+fn write_csv(grid: &Grid, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "y,x,r,g,b,alive")?;
+    for y in 0..grid.height {
+        for x in 0..grid.width {
+            let [r, g, b] = grid.get_color(y, x).as_slice();
+            let alive = u8::from(grid.alive_states[[y, x]]);
+            writeln!(file, "{y},{x},{r},{g},{b},{alive}")?;
+        }
+    }
+    file.flush()
+}
+
+// Writes the grid as text, drawn with `renderer`
+fn write_text(grid: &Grid, path: &Path, renderer: &Renderer) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let text = grid.frame_with(renderer);
+    fs::write(path, text.replace("\r\n", "\n"))
+}
+
+// Saves the layers the options ask for next to the image saved at `path`, unless it was saved as
+// text or data. They're always PNGs, whatever the image is saved as, so nothing blurs their edges.
+fn save_layers(grid: &Grid, path: &Path, options: &ImageOptions) {
+    let is_text = path.extension().is_some_and(|ext| {
+        ["txt", "ans", "json", "csv", "scgrid"]
+            .iter()
+            .any(|text| ext == *text)
+    });
+    if is_text {
+        return;
+    }
+    for &layer in &options.layers {
+        let layer_path = with_filename_suffix(path, layer.suffix()).with_extension("png");
+        match write_layer(grid, layer, &layer_path, options) {
+            Ok(()) => status!("{}", t!("export.saved", path = layer_path.display())),
+            Err(e) => eprintln!("{}", t!("export.save_failed", error = format!("{e:?}"))),
+        }
+    }
+}
+
+/// What happens when an image is about to be saved over an existing file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Overwrite {
+    /// Save under the next free name instead, e.g. image_1.png, image_2.png, ...
+    Increment,
+    /// Ask whether to replace the file, saving under the next free name if not.
+    /// Runs without prompts (--yes) increment instead.
+    Ask,
+    /// Replace the existing file
+    Replace,
+}
+
+// The first of `path`, `path_1`, `path_2`, ... (numbered before the extension) that doesn't exist yet
+fn next_free_path(path: &Path) -> PathBuf {
+    (1..)
+        .map(|n| with_filename_suffix(path, &format!("_{n}")))
+        .find(|candidate| !candidate.exists())
+        .unwrap()
+}
+
+/// Where a file meant for `path` should actually be saved, so nothing is clobbered by accident
+pub fn resolve_overwrite(path: &Path, overwrite: Overwrite, interactive: bool) -> PathBuf {
+    if !path.exists() {
+        return path.to_path_buf();
+    }
+    let replace = match overwrite {
+        Overwrite::Replace => true,
+        Overwrite::Increment => false,
+        Overwrite::Ask => {
+            interactive
+                && Confirm::new(&t!("prompt.overwrite", path = path.display()))
+                    .with_default(false)
+                    .prompt()
+                    .unwrap_or(false)
+        }
+    };
+    if replace {
+        return path.to_path_buf();
+    }
+    let free = next_free_path(path);
+    status!(
+        "{}",
+        t!(
+            "export.renamed",
+            path = path.display(),
+            new_path = free.display()
+        )
+    );
+    free
+}
+
+/// Saves the grid as an image at `path`, drawn with `options`, reporting how it went in the terminal.
+/// If `path` is taken, `overwrite` decides whether to replace it or save under another name
+/// (`interactive` says whether the user can be asked). Images named by a hash are never saved
+/// twice: a taken name means the same image is already there.
+/// Returns where the image was saved, if it was.
+pub fn save_image(
+    grid: &Grid,
+    path: &Path,
+    options: &ImageOptions,
+    overwrite: Overwrite,
+    interactive: bool,
+) -> Option<PathBuf> {
+    let path = match options.hash_names {
+        Some(hash_names) => {
+            let path = match hash_names {
+                HashNames::Content => options.content_path(grid, path),
+                HashNames::Settings => path.to_path_buf(),
+            };
+            if path.exists() {
+                status!("{}", t!("export.already_saved", path = path.display()));
+                return Some(path);
+            }
+            path
+        }
+        None => resolve_overwrite(path, overwrite, interactive),
+    };
+    let img_timer = Instant::now();
+    if let Err(e) = write_image(grid, &path, options) {
+        eprintln!("{}", t!("export.save_failed", error = format!("{e:?}")));
+        None
+    } else {
+        let elapsed = format!("{:?}", img_timer.elapsed());
+        status!("{}", t!("export.save_time", elapsed = elapsed));
+        status!("{}", t!("export.saved", path = path.display()));
+        save_layers(grid, &path, options);
+        Some(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{grown_grid, temp_path};
+
+    // A grid wider than it's tall, so mixing up x and y can't go unnoticed
+    fn wide_grid() -> Grid {
+        let config = SimulationConfig {
+            width: 9,
+            height: 5,
+            starting_live_cells: 3,
+            ..SimulationConfig::default()
+        };
+        grown_grid(&config, 4)
+    }
+
+    // `grid` saved as a PNG with `orientation`, read back
+    fn saved_image(grid: &Grid, orientation: Orientation, name: &str) -> RgbImage {
+        let path = temp_path(name);
+        let options = ImageOptions {
+            orientation,
+            ..ImageOptions::default()
+        };
+        write_image(grid, &path, &options).unwrap();
+        let img = image::open(&path).unwrap().into_rgb8();
+        fs::remove_file(path).unwrap();
+        img
+    }
+
+    #[test]
+    fn images_keep_the_grid_the_way_round_it_is_shown() {
+        let grid = wide_grid();
+        let img = saved_image(&grid, Orientation::default(), "upright.png");
+        assert_eq!(img.dimensions(), (9, 5));
+        for (x, y, pixel) in img.enumerate_pixels() {
+            let color = grid.get_color(y as usize, x as usize).as_slice();
+            assert_eq!(pixel.0, color, "({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn images_are_flipped_then_turned() {
+        let grid = wide_grid();
+        let color = |y: u32, x: u32| grid.get_color(y as usize, x as usize).as_slice();
+
+        let quarter = Orientation {
+            rotate: Some(Rotation::Quarter),
+            ..Orientation::default()
+        };
+        let img = saved_image(&grid, quarter, "quarter.png");
+        assert_eq!(img.dimensions(), (5, 9));
+        // Turning clockwise puts the bottom-left corner at the top-left
+        for (x, y, pixel) in img.enumerate_pixels() {
+            assert_eq!(pixel.0, color(4 - x, y), "({x}, {y})");
+        }
+
+        let flipped_and_turned = Orientation {
+            rotate: Some(Rotation::ThreeQuarters),
+            flip_horizontal: true,
+            flip_vertical: false,
+        };
+        let img = saved_image(&grid, flipped_and_turned, "flipped.png");
+        assert_eq!(img.dimensions(), (5, 9));
+        for (x, y, pixel) in img.enumerate_pixels() {
+            assert_eq!(pixel.0, color(x, y), "({x}, {y})");
+        }
+    }
+
+    #[test]
+    fn json_holds_every_cell_row_by_row() {
+        let grid = wide_grid();
+        let path = temp_path("grid.json");
+        write_image(&grid, &path, &ImageOptions::default()).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(json["width"], 9);
+        assert_eq!(json["height"], 5);
+        assert_eq!(json["generation"], grid.generation);
+        assert_eq!(json["settings"], grid.settings_summary());
+        let rows = json["colors"].as_array().unwrap();
+        assert_eq!(rows.len(), 5);
+        for y in 0..5 {
+            assert_eq!(json["alive"][y].as_array().unwrap().len(), 9);
+            for x in 0..9 {
+                let color: [u8; 3] = serde_json::from_value(rows[y][x].clone()).unwrap();
+                assert_eq!(color, grid.get_color(y, x).as_slice(), "({x}, {y})");
+                assert_eq!(json["alive"][y][x], grid.alive_states[[y, x]], "({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn csv_has_a_line_per_cell() {
+        let grid = wide_grid();
+        let path = temp_path("grid.csv");
+        write_image(&grid, &path, &ImageOptions::default()).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("y,x,r,g,b,alive"));
+        let cells: Vec<&str> = lines.collect();
+        assert_eq!(cells.len(), 9 * 5);
+        for (i, line) in cells.iter().enumerate() {
+            let (y, x) = (i / 9, i % 9);
+            let [r, g, b] = grid.get_color(y, x).as_slice();
+            let alive = u8::from(grid.alive_states[[y, x]]);
+            assert_eq!(*line, format!("{y},{x},{r},{g},{b},{alive}"));
+        }
+        assert!(cells.iter().any(|line| line.ends_with(",1")));
+        assert!(cells.iter().any(|line| line.ends_with(",0")));
+    }
+}
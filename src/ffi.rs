@@ -0,0 +1,213 @@
+//! A minimal C interface to the simulation, for embedding it in other languages and programs
+//! (Python through ctypes, game engines, creative-coding tools) with the shared library that's
+//! built alongside the program, e.g. `libspreading_colors_ca.so`.
+//!
+//! ```c
+//! typedef struct Simulation Simulation;
+//!
+//! Simulation *spreading_colors_create(size_t width, size_t height, uint32_t starting_live_cells,
+//!                                     uint8_t colorshift, double spread_chance, bool light,
+//!                                     uint64_t seed);
+//! bool spreading_colors_step(Simulation *simulation);
+//! size_t spreading_colors_get_pixels(const Simulation *simulation, uint8_t *buffer, size_t len);
+//! void spreading_colors_destroy(Simulation *simulation);
+//! ```
+//!
+//! A simulation is created with `create`, advanced one generation at a time with `step` until it
+//! returns false, read with `get_pixels` as often as needed and freed with `destroy`.
+//! The same settings and seed always grow the same image, just like in the program. The theme is
+//! always picked by the caller, so the host's dark or light mode never changes the output.
+
+use std::{ptr, slice};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    config::SimulationConfig,
+    grid::Grid,
+    palettes::Theme,
+    simulation::{starting_grid, yx_coordinate_pairs},
+};
+
+/// A running simulation, only handled through pointers from C
+pub struct Simulation {
+    grid: Grid,
+    rng: StdRng,
+    yx_coordinate_pairs: Vec<[usize; 2]>,
+}
+
+// How many bytes an 8-bit RGB image of the grid takes up, if that fits in a usize
+fn pixel_bytes(width: usize, height: usize) -> Option<usize> {
+    width.checked_mul(height)?.checked_mul(3)
+}
+
+/// Creates a simulation with the given settings and its starting cells placed, on a white
+/// background with bright starting colors if `light` is true, otherwise on a black one.
+/// Returns null if the settings aren't valid, e.g. a grid smaller than 3x3 or one too big for
+/// its pixels to be counted in a `size_t`.
+/// The simulation has to be freed with `spreading_colors_destroy`.
+#[no_mangle]
+pub extern "C" fn spreading_colors_create(
+    width: usize,
+    height: usize,
+    starting_live_cells: u32,
+    colorshift: u8,
+    spread_chance: f64,
+    light: bool,
+    seed: u64,
+) -> *mut Simulation {
+    let config = SimulationConfig {
+        width,
+        height,
+        starting_live_cells,
+        colorshift,
+        spread_chance,
+        theme: if light { Theme::Light } else { Theme::Dark },
+        seed: Some(seed),
+        ..SimulationConfig::default()
+    };
+    if config.validate().is_err() || pixel_bytes(width, height).is_none() {
+        return ptr::null_mut();
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let grid = starting_grid(&config, &mut rng);
+    Box::into_raw(Box::new(Simulation {
+        grid,
+        rng,
+        yx_coordinate_pairs: yx_coordinate_pairs(width, height, config.boundary),
+    }))
+}
+
+/// Advances the simulation by one generation.
+/// Returns false once every cell is alive, meaning the simulation is finished.
+///
+/// # Safety
+///
+/// `simulation` has to come from `spreading_colors_create` and not be destroyed yet.
+#[no_mangle]
+pub unsafe extern "C" fn spreading_colors_step(simulation: *mut Simulation) -> bool {
+    let Some(simulation) = simulation.as_mut() else {
+        return false;
+    };
+    simulation
+        .grid
+        .step(&simulation.yx_coordinate_pairs, &mut simulation.rng)
+}
+
+/// Copies the image into `buffer` as 8-bit RGB, row by row from the top-left corner, if `len`
+/// bytes are enough to hold it. Returns the number of bytes the image takes up
+/// (width * height * 3), so passing a null buffer asks how big it has to be, or 0 if `simulation`
+/// is null.
+///
+/// # Safety
+///
+/// `simulation` has to come from `spreading_colors_create` and not be destroyed yet,
+/// and `buffer` has to be null or point to at least `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn spreading_colors_get_pixels(
+    simulation: *const Simulation,
+    buffer: *mut u8,
+    len: usize,
+) -> usize {
+    let Some(simulation) = simulation.as_ref() else {
+        return 0;
+    };
+    let Some(needed) = pixel_bytes(simulation.grid.width, simulation.grid.height) else {
+        return 0;
+    };
+    if buffer.is_null() || len < needed {
+        return needed;
+    }
+
+    let pixels = slice::from_raw_parts_mut(buffer, needed);
+    for (pixel, (_, color, _)) in pixels.chunks_exact_mut(3).zip(simulation.grid.cells()) {
+        pixel.copy_from_slice(&color.as_slice());
+    }
+    needed
+}
+
+/// Frees a simulation. Does nothing if `simulation` is null.
+///
+/// # Safety
+///
+/// `simulation` has to come from `spreading_colors_create` and not be destroyed already.
+#[no_mangle]
+pub unsafe extern "C" fn spreading_colors_destroy(simulation: *mut Simulation) {
+    if !simulation.is_null() {
+        drop(Box::from_raw(simulation));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lifecycle_runs_through_the_c_functions() {
+        let simulation = spreading_colors_create(8, 6, 2, 4, 0.5, false, 7);
+        assert!(!simulation.is_null());
+        unsafe {
+            let needed = spreading_colors_get_pixels(simulation, ptr::null_mut(), 0);
+            assert_eq!(needed, 8 * 6 * 3);
+            let mut start = vec![0xAA; needed];
+            assert_eq!(
+                spreading_colors_get_pixels(simulation, start.as_mut_ptr(), needed - 1),
+                needed
+            );
+            assert!(
+                start.iter().all(|&byte| byte == 0xAA),
+                "too small a buffer was written to"
+            );
+            spreading_colors_get_pixels(simulation, start.as_mut_ptr(), needed);
+
+            let mut generations = 0;
+            while spreading_colors_step(simulation) {
+                generations += 1;
+                assert!(generations < 10_000, "the simulation never finished");
+            }
+            let mut end = vec![0; needed];
+            spreading_colors_get_pixels(simulation, end.as_mut_ptr(), needed);
+            assert_ne!(start, end);
+            spreading_colors_destroy(simulation);
+
+            // The same settings and seed grow the same image
+            let again = spreading_colors_create(8, 6, 2, 4, 0.5, false, 7);
+            while spreading_colors_step(again) {}
+            let mut repeated = vec![0; needed];
+            spreading_colors_get_pixels(again, repeated.as_mut_ptr(), needed);
+            assert_eq!(end, repeated);
+            spreading_colors_destroy(again);
+        }
+    }
+
+    #[test]
+    fn the_theme_comes_from_the_caller() {
+        for (light, background) in [(true, [255; 3]), (false, [0; 3])] {
+            let simulation = spreading_colors_create(8, 6, 1, 4, 0.5, light, 7);
+            let mut pixels = vec![0xAA; 8 * 6 * 3];
+            unsafe {
+                spreading_colors_get_pixels(simulation, pixels.as_mut_ptr(), pixels.len());
+                spreading_colors_destroy(simulation);
+            }
+            // The edge is never simulated, so it keeps the background
+            assert_eq!(pixels[..3], background, "light: {light}");
+        }
+    }
+
+    #[test]
+    fn bad_settings_give_null() {
+        assert!(spreading_colors_create(2, 6, 1, 4, 0.5, false, 7).is_null());
+        assert!(spreading_colors_create(8, 6, 1, 4, 1.5, false, 7).is_null());
+        // Too many pixels to count
+        assert!(spreading_colors_create(usize::MAX, 3, 1, 4, 0.5, false, 7).is_null());
+        unsafe {
+            assert!(!spreading_colors_step(ptr::null_mut()));
+            assert_eq!(
+                spreading_colors_get_pixels(ptr::null(), ptr::null_mut(), 0),
+                0
+            );
+            spreading_colors_destroy(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,206 @@
+// The format saved images are written in. It's normally picked from the extension of --output, but
+// can be chosen with --format, which gives names without an extension the right one. Extensions
+// are checked before the run, so a typo doesn't cost a finished image.
+
+use std::path::Path;
+
+use clap::{Args, ValueEnum};
+
+// Extensions saved specially instead of through the `image` crate (see `export.rs`)
+const SPECIAL_EXTENSIONS: [&str; 7] = ["txt", "ans", "json", "csv", "scgrid", "ppm", "pam"];
+
+/// The default quality saved JPEG images get, the same as the `image` crate's
+pub const JPEG_QUALITY_DEFAULT: u8 = 75;
+
+/// An image format saved images can be written in with --format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Lossless and widely supported, the default
+    Png,
+    /// Lossy and small, for sharing. Fine cell borders can blur
+    Jpeg,
+    /// Uncompressed, for old tools
+    Bmp,
+    /// Lossless, for print and photo editing
+    Tiff,
+    /// Floating point OpenEXR in linear light, for grading in compositing tools
+    Exr,
+}
+
+impl Format {
+    /// The extension images in this format are given
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Png => "png",
+            Format::Jpeg => "jpg",
+            Format::Bmp => "bmp",
+            Format::Tiff => "tiff",
+            Format::Exr => "exr",
+        }
+    }
+
+    // Whether `extension` is one files in this format can have
+    fn accepts(self, extension: &str) -> bool {
+        let extensions: &[&str] = match self {
+            Format::Png => &["png"],
+            Format::Jpeg => &["jpg", "jpeg"],
+            Format::Bmp => &["bmp"],
+            Format::Tiff => &["tif", "tiff"],
+            Format::Exr => &["exr"],
+        };
+        extensions
+            .iter()
+            .any(|accepted| accepted.eq_ignore_ascii_case(extension))
+    }
+}
+
+/// How many bits each color channel of saved PNG and TIFF images has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum BitDepth {
+    /// What every viewer shows, the default
+    #[default]
+    #[value(name = "8")]
+    Eight,
+    /// For tools that expect 16-bit images. The 8-bit colors are widened, not made more precise
+    #[value(name = "16")]
+    Sixteen,
+}
+
+impl From<BitDepth> for png::BitDepth {
+    fn from(depth: BitDepth) -> Self {
+        match depth {
+            BitDepth::Eight => png::BitDepth::Eight,
+            BitDepth::Sixteen => png::BitDepth::Sixteen,
+        }
+    }
+}
+
+/// How hard saved PNG images are compressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum PngCompression {
+    /// Saves quickly, making bigger files
+    Fast,
+    /// A balance of the two
+    #[default]
+    Default,
+    /// Makes the smallest files, saving slowest
+    Best,
+}
+
+impl From<PngCompression> for png::Compression {
+    fn from(compression: PngCompression) -> Self {
+        match compression {
+            PngCompression::Fast => png::Compression::Fast,
+            PngCompression::Default => png::Compression::Default,
+            PngCompression::Best => png::Compression::Best,
+        }
+    }
+}
+
+/// The format saved images are written in, and the settings for each format
+#[derive(Debug, Clone, Args)]
+pub struct FormatOptions {
+    /// Format to save images in. Filenames without an extension get this format's, and ones with
+    /// another format's are refused. Without it the format is picked from the extension (PNG if
+    /// there's none)
+    #[arg(long, value_enum, env = "SPREADING_COLORS_FORMAT")]
+    pub format: Option<Format>,
+
+    /// Quality of saved JPEG images, from 1 (smallest) to 100 (sharpest)
+    #[arg(
+        long,
+        default_value_t = JPEG_QUALITY_DEFAULT,
+        value_name = "QUALITY",
+        value_parser = clap::value_parser!(u8).range(1..=100),
+        env = "SPREADING_COLORS_JPEG_QUALITY"
+    )]
+    pub jpeg_quality: u8,
+
+    /// How hard saved PNG images are compressed
+    #[arg(long, value_enum, default_value_t = PngCompression::Default, env = "SPREADING_COLORS_PNG_COMPRESSION")]
+    pub png_compression: PngCompression,
+
+    /// Bits per color channel of saved PNG and TIFF images. Cells only have 8-bit colors, so 16-bit
+    /// images hold the same colors widened to 16 bits (each value times 257), with no extra precision
+    #[arg(long, value_enum, default_value_t = BitDepth::Eight, value_name = "BITS", env = "SPREADING_COLORS_BIT_DEPTH")]
+    pub bit_depth: BitDepth,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            format: None,
+            jpeg_quality: JPEG_QUALITY_DEFAULT,
+            png_compression: PngCompression::default(),
+            bit_depth: BitDepth::default(),
+        }
+    }
+}
+
+impl FormatOptions {
+    /// The extension images are given when their name doesn't say
+    pub fn extension(&self) -> &'static str {
+        self.format.unwrap_or(Format::Png).extension()
+    }
+
+    /// `filename` checked for an extension images can be saved with, and given one if it has none.
+    /// Returns why it can't be used if it can't.
+    pub fn filename(&self, filename: &str) -> Result<String, String> {
+        let Some(extension) = Path::new(filename).extension().and_then(|ext| ext.to_str()) else {
+            return Ok(format!("{filename}.{}", self.extension()));
+        };
+        match self.format {
+            Some(format) if !format.accepts(extension) => Err(format!(
+                "{filename} doesn't end in .{}, which --format {} images are saved as",
+                format.extension(),
+                format
+                    .to_possible_value()
+                    .expect("formats are never skipped")
+                    .get_name()
+            )),
+            Some(_) => Ok(filename.to_string()),
+            None if SPECIAL_EXTENSIONS
+                .iter()
+                .any(|special| special.eq_ignore_ascii_case(extension))
+                || image::ImageFormat::from_extension(extension)
+                    .is_some_and(|format| format.writing_enabled()) =>
+            {
+                Ok(filename.to_string())
+            }
+            None => Err(format!(
+                "images can't be saved as .{extension} files, try .png, .jpg, .bmp, .tiff or .exr"
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extensions_are_checked_before_the_run() {
+        let options = FormatOptions::default();
+        for name in [
+            "out.png", "out.JPG", "out.tiff", "out.exr", "out.csv", "out.pam",
+        ] {
+            assert_eq!(options.filename(name).as_deref(), Ok(name));
+        }
+        assert_eq!(options.filename("out").as_deref(), Ok("out.png"));
+        // Formats `image` recognizes but can't write are refused up front, not after the run
+        for name in ["out.dds", "out.hdr", "out.xyz"] {
+            assert!(options.filename(name).is_err(), "{name}");
+        }
+    }
+
+    #[test]
+    fn format_must_match_the_extension() {
+        let options = FormatOptions {
+            format: Some(Format::Jpeg),
+            ..FormatOptions::default()
+        };
+        assert_eq!(options.filename("out").as_deref(), Ok("out.jpg"));
+        assert_eq!(options.filename("out.jpeg").as_deref(), Ok("out.jpeg"));
+        assert!(options.filename("out.png").is_err());
+    }
+}
@@ -0,0 +1,169 @@
+// Glyph palettes: sets of characters that each stand for a range of colors, so cells can be drawn
+// as glyphs (e.g. colored square emoji) instead of colored blocks. That works on terminals without
+// truecolor, and in plain text files.
+
+use std::str::FromStr;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::grid::RgbColor;
+
+/// A glyph and the color it represents
+#[derive(Debug, Clone, Copy)]
+pub struct Glyph {
+    pub text: &'static str,
+    pub color: RgbColor,
+}
+
+const fn glyph(text: &'static str, red: u8, green: u8, blue: u8) -> Glyph {
+    Glyph {
+        text,
+        color: RgbColor { red, green, blue },
+    }
+}
+
+/// The colored square emoji, with the colors most emoji fonts draw them in
+pub const EMOJI_SQUARES: [Glyph; 9] = [
+    glyph("🟥", 221, 46, 68),
+    glyph("🟧", 244, 144, 12),
+    glyph("🟨", 253, 203, 88),
+    glyph("🟩", 120, 177, 89),
+    glyph("🟦", 85, 172, 238),
+    glyph("🟪", 170, 142, 214),
+    glyph("🟫", 193, 105, 79),
+    glyph("⬛", 0, 0, 0),
+    glyph("⬜", 230, 231, 232),
+];
+
+/// Characters from the least to the most ink, for drawing cells by brightness without color
+pub const ASCII_RAMP: &str = " .:-=+*#%@";
+
+/// A set of glyphs to draw cells with, each one standing for a bucket of colors
+#[derive(Debug, Clone)]
+pub enum Palette {
+    /// Each cell is drawn as the glyph whose color is closest to its own
+    Nearest(&'static [Glyph]),
+    /// The glyphs split the color wheel into equal slices of hue, starting at red,
+    /// e.g. "🟥🟧🟨🟩🟦🟪". Black (dead) cells are left blank.
+    Hues(Vec<String>),
+    /// The characters go from dark to bright, each cell being drawn as the one for its brightness,
+    /// e.g. `ASCII_RAMP`. Black (dead) cells are left blank.
+    Ramp(&'static str),
+}
+
+impl Palette {
+    /// The glyph standing for `color`
+    pub fn glyph(&self, color: RgbColor) -> &str {
+        match self {
+            Palette::Nearest(glyphs) => nearest_glyph(glyphs, color).text,
+            Palette::Hues(glyphs) => {
+                if color.as_slice() == [0, 0, 0] {
+                    return "";
+                }
+                let bucket = (hue(color) / 360.0 * glyphs.len() as f64) as usize;
+                &glyphs[bucket.min(glyphs.len() - 1)]
+            }
+            Palette::Ramp(ramp) => {
+                let steps = ramp.chars().count();
+                let step = (brightness(color) * steps as f64) as usize;
+                let (start, glyph) = ramp.char_indices().nth(step.min(steps - 1)).unwrap();
+                &ramp[start..start + glyph.len_utf8()]
+            }
+        }
+    }
+
+    /// How many terminal columns the widest glyph takes up
+    pub fn width(&self) -> usize {
+        let widths: Vec<usize> = match self {
+            Palette::Nearest(glyphs) => glyphs.iter().map(|g| display_width(g.text)).collect(),
+            Palette::Hues(glyphs) => glyphs.iter().map(|g| display_width(g)).collect(),
+            Palette::Ramp(ramp) => vec![display_width(ramp) / ramp.chars().count()],
+        };
+        widths.into_iter().max().unwrap_or(1).max(1)
+    }
+
+    /// Whether every glyph is plain ASCII, so it can be shown without unicode support
+    pub fn is_ascii(&self) -> bool {
+        match self {
+            Palette::Nearest(glyphs) => glyphs.iter().all(|g| g.text.is_ascii()),
+            Palette::Hues(glyphs) => glyphs.iter().all(|g| g.is_ascii()),
+            Palette::Ramp(ramp) => ramp.is_ascii(),
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = String;
+
+    /// Parses the user's own glyphs. Glyphs separated by spaces or commas can be several
+    /// characters long, otherwise every character is its own glyph.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let glyphs: Vec<String> = if s.contains([' ', ',']) {
+            s.split([' ', ','])
+                .filter(|glyph| !glyph.is_empty())
+                .map(str::to_string)
+                .collect()
+        } else {
+            s.chars().map(String::from).collect()
+        };
+        if glyphs.is_empty() {
+            return Err("expected at least one glyph".to_string());
+        }
+        Ok(Palette::Hues(glyphs))
+    }
+}
+
+/// Checks that `s` is a single glyph cells can be drawn with, taking up one or two columns
+pub fn parse_glyph(s: &str) -> Result<String, String> {
+    if s.chars().any(char::is_control) || !(1..=2).contains(&display_width(s)) {
+        return Err("expected one glyph, taking up one or two columns".to_string());
+    }
+    Ok(s.to_string())
+}
+
+/// The glyph in `palette` whose color is closest to `color`
+pub fn nearest_glyph(palette: &[Glyph], color: RgbColor) -> &Glyph {
+    let distance = |glyph: &&Glyph| {
+        let [r1, g1, b1] = color.as_slice().map(i32::from);
+        let [r2, g2, b2] = glyph.color.as_slice().map(i32::from);
+        (r1 - r2).pow(2) + (g1 - g2).pow(2) + (b1 - b2).pow(2)
+    };
+    palette.iter().min_by_key(distance).unwrap()
+}
+
+// A color's hue in degrees, from 0 (red) through 120 (green) and 240 (blue) up to 360
+fn hue(color: RgbColor) -> f64 {
+    let [red, green, blue] = color.as_slice().map(|channel| f64::from(channel) / 255.0);
+    let max = red.max(green).max(blue);
+    let chroma = max - red.min(green).min(blue);
+    if chroma == 0.0 {
+        return 0.0;
+    }
+    let sector = if max == red {
+        ((green - blue) / chroma).rem_euclid(6.0)
+    } else if max == green {
+        (blue - red) / chroma + 2.0
+    } else {
+        (red - green) / chroma + 4.0
+    };
+    sector * 60.0
+}
+
+// How bright a color looks, from 0 (black) to 1 (white), weighing each channel by how bright it
+// looks to the eye (Rec. 709)
+fn brightness(color: RgbColor) -> f64 {
+    let [red, green, blue] = color.as_slice().map(|channel| f64::from(channel) / 255.0);
+    0.2126 * red + 0.7152 * green + 0.0722 * blue
+}
+
+/// How many terminal columns `text` takes up. Most emoji take two, most other characters one.
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// Pads `text` with spaces to take up exactly `width` columns, so glyphs of different widths
+/// still line up in columns
+pub fn pad_to_width(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(text));
+    format!("{text}{}", " ".repeat(padding))
+}
@@ -0,0 +1,88 @@
+// What the terminal graphics styles (Sixel, kitty's and iTerm2's) have in common: the grid is drawn
+// as an image with each cell scaled up to a square of pixels, as big as fits in the terminal while
+// leaving room for the animation's status underneath.
+
+use crossterm::terminal;
+use image::{
+    codecs::png::{CompressionType, FilterType, PngEncoder},
+    ColorType, ImageBuffer, ImageEncoder, Rgb, RgbImage,
+};
+
+use crate::{grid::Grid, simulation::STATUS_LINES};
+
+// Pixel size of a character when the terminal doesn't report it, which is a common one
+const FALLBACK_CHAR_SIZE: (usize, usize) = (8, 16);
+
+/// How many pixels across and down each simulated cell of `grid` is drawn as
+pub fn scale(grid: &Grid) -> usize {
+    layout(grid).0
+}
+
+/// The simulated cells of `grid` as a PNG, scaled up to fit the terminal,
+/// for the styles that send their images as files
+pub fn png(grid: &Grid) -> Vec<u8> {
+    let scale = scale(grid);
+    let (columns, rows) = grid.simulated_size();
+    let (width, height) = ((columns * scale) as u32, (rows * scale) as u32);
+    let border = grid.boundary().border();
+    let image: RgbImage = ImageBuffer::from_fn(width, height, |x, y| {
+        let (y, x) = (y as usize / scale + border, x as usize / scale + border);
+        Rgb(grid.get_color(y, x).as_slice())
+    });
+    let mut png = Vec::new();
+    // Frames are sent many times a second, so they're compressed fast rather than small
+    PngEncoder::new_with_quality(&mut png, CompressionType::Fast, FilterType::Sub)
+        .write_image(&image, width, height, ColorType::Rgb8)
+        .expect("encoding into memory can't fail");
+    png
+}
+
+/// The simulated cell drawn at a screen position in an image of `grid`, counted in rows and
+/// columns from the image's top-left corner, if there is one there
+pub fn cell_under(grid: &Grid, row: usize, column: usize) -> Option<(usize, usize)> {
+    let (scale, (char_width, char_height)) = layout(grid);
+    // The pixel in the middle of the character
+    let x = (column * char_width + char_width / 2) / scale;
+    let y = (row * char_height + char_height / 2) / scale;
+    let (width, height) = grid.simulated_size();
+    let border = grid.boundary().border();
+    (y < height && x < width).then_some((y + border, x + border))
+}
+
+/// How many pixels across and down (columns, rows) characters of the terminal take up
+pub fn pixels_within((columns, rows): (usize, usize)) -> (usize, usize) {
+    let (_, _, (char_width, char_height)) = window();
+    (columns * char_width, rows * char_height)
+}
+
+// How many pixels across and down each cell is drawn as,
+// and the pixel size of the terminal's characters
+fn layout(grid: &Grid) -> (usize, (usize, usize)) {
+    let (columns, rows, char_size) = window();
+    let available = (
+        columns * char_size.0,
+        rows.saturating_sub(STATUS_LINES) * char_size.1,
+    );
+    let (width, height) = grid.simulated_size();
+    let scale = (available.0 / width).min(available.1 / height).max(1);
+    (scale, char_size)
+}
+
+// How many columns and rows of characters the terminal has, and their pixel size
+fn window() -> (usize, usize, (usize, usize)) {
+    let (columns, rows, width, height) = match terminal::window_size() {
+        Ok(size) => (
+            usize::from(size.columns),
+            usize::from(size.rows),
+            usize::from(size.width),
+            usize::from(size.height),
+        ),
+        Err(_) => (80, 24, 0, 0),
+    };
+    let char_size = if width == 0 || height == 0 || columns == 0 || rows == 0 {
+        FALLBACK_CHAR_SIZE
+    } else {
+        (width / columns, height / rows)
+    };
+    (columns, rows, char_size)
+}
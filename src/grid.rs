@@ -0,0 +1,922 @@
+use std::time::Duration;
+
+use ndarray::{s, Array2};
+
+use rand::{rngs::StdRng, seq::IteratorRandom, Rng};
+
+use crate::{
+    braille,
+    config::{Boundary, Neighborhood, SimulationConfig},
+    graphics,
+    history::CellChange,
+    i18n::t,
+    iterm, kitty,
+    palettes::{ColorPalette, PALETTE_JITTER},
+    rules::Rule,
+    sixel,
+    terminal::{self, renderer, Renderer},
+    verbosity::{self, verbose, Verbosity},
+};
+
+/// Colony of cells that aren't alive. Colonies that are alive are numbered from 1.
+pub const NO_COLONY: u32 = 0;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RgbColor {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl RgbColor {
+    pub fn as_slice(&self) -> [u8; 3] {
+        [self.red, self.green, self.blue]
+    }
+
+    /// Returns a random color
+    pub fn random(rng: &mut StdRng) -> Self {
+        Self {
+            red: rng.gen(),
+            green: rng.gen(),
+            blue: rng.gen(),
+        }
+    }
+
+    // Increases or decreases a color value (red, green, or blue) given a shift value.
+    // A shift of 0 (from a temperature of 0) leaves it as it is.
+    fn shift_hue(hue: u8, shift: u8, rng: &mut StdRng) -> u8 {
+        if shift == 0 {
+            return hue;
+        }
+        let r = rng.gen_range(0..shift);
+        if rng.gen() {
+            hue.saturating_sub(r)
+        } else {
+            hue.saturating_add(r)
+        }
+    }
+
+    /// Shifts each of a color's Red, Green, and Blue values randomly,
+    /// given a `shift` value and a random number generator
+    pub fn shift_color(&self, shift: u8, rng: &mut StdRng) -> Self {
+        Self {
+            red: RgbColor::shift_hue(self.red, shift, rng),
+            green: RgbColor::shift_hue(self.green, shift, rng),
+            blue: RgbColor::shift_hue(self.blue, shift, rng),
+        }
+    }
+
+    /// Applies `f` to each of the color's Red, Green, and Blue values
+    pub fn map_channels(&self, mut f: impl FnMut(u8) -> u8) -> Self {
+        Self {
+            red: f(self.red),
+            green: f(self.green),
+            blue: f(self.blue),
+        }
+    }
+}
+
+impl From<[u8; 3]> for RgbColor {
+    fn from(value: [u8; 3]) -> Self {
+        let [red, green, blue] = value;
+        Self { red, green, blue }
+    }
+}
+
+/// A random magnitude (a colorshift or jitter) scaled by the temperature
+pub fn heated(magnitude: u8, temperature: f64) -> u8 {
+    (f64::from(magnitude) * temperature).round().min(255.0) as u8
+}
+
+/// A random cell of a grid or volume `size` cells long in each dimension, `border` cells or more
+/// from its edges. Its position is picked from the last dimension to the first.
+pub fn random_cell<const N: usize>(
+    size: [usize; N],
+    border: usize,
+    rng: &mut StdRng,
+) -> [usize; N] {
+    let mut cell = [0; N];
+    for (position, size) in cell.iter_mut().zip(size).rev() {
+        *position = rng.gen_range(border..(size - border));
+    }
+    cell
+}
+
+/// The cell a live cell spreads to this generation, if any: one of its `dead_neighbors` picked at
+/// random, if the cell beats the `spread_chance`. Grids and volumes spread alike.
+pub fn pick_child<T>(
+    dead_neighbors: impl Iterator<Item = T>,
+    spread_chance: f64,
+    rng: &mut StdRng,
+) -> Option<T> {
+    let child = dead_neighbors.choose(rng)?;
+    (rng.gen_range(0.0..1.0) < spread_chance).then_some(child)
+}
+
+#[derive(Debug, Clone)]
+pub struct Grid {
+    pub alive_states: Array2<bool>,
+    // red_states: Array2<u8>,
+    // green_states: Array2<u8>,
+    // blue_states: Array2<u8>,
+    color_states: Array2<RgbColor>,
+
+    // Which colony each cell belongs to. Every orphan founds a colony, which its children join.
+    colony_states: Array2<u32>,
+    // Number of colonies founded so far
+    colonies: u32,
+    // Colony that cells painted in with the brush join, once anything has been painted
+    painted_colony: Option<u32>,
+
+    // Dimensions of the simulation
+    pub width: usize,
+    pub height: usize,
+
+    // Number of generations simulated so far
+    pub generation: u64,
+
+    /*
+    Time to sleep between frames
+    (this only matters if the simulation is being animated in the terminal.)
+    */
+    pub frametime: Duration,
+
+    /*
+    When a living cell spreads to a dead cell,
+    the new cell's color is generated using
+    the parent's color and this value
+    */
+    colorshift: u8,
+    spread_chance: f64,
+    // Scales the colorshift and the jitter of orphans' colors
+    temperature: f64,
+    // What happens at the edges. Only grids that exclude them have a border.
+    boundary: Boundary,
+    // Which cells around a live cell it can spread to, how far, and where they are from it
+    neighborhood: Neighborhood,
+    spread_radius: u32,
+    neighbor_offsets: Vec<[isize; 2]>,
+    // Seed the run was started with, when it was known, so saved images can record it
+    seed: Option<u64>,
+
+    // Colors orphans are picked from
+    palette: ColorPalette,
+    // Color of cells that aren't alive
+    background: RgbColor,
+
+    // Decides how cells spread and what color their children are. Can be swapped mid-run.
+    rule: Rule,
+
+    // Every cell change since the last `take_changes`, while recording is turned on
+    changes: Option<Vec<CellChange>>,
+    // The generation each cell was last brought to life in, while `track_births` is on
+    births: Option<Array2<u64>>,
+    // Which way each cell was spread into, while `track_births` is on: 1 + the index of its
+    // place in `neighbor_offsets` from its parent, or 0 for cells that didn't spread from another
+    sources: Option<Array2<u16>>,
+}
+
+impl Grid {
+    /// Creates an empty (entirely dead) grid using the given settings
+    pub fn new(config: &SimulationConfig) -> Self {
+        // the amount of time that the animation sleeps between frames to keep a constant framerate
+        let frametime = {
+            let frame_rate: u64 = config.framerate.try_into().unwrap();
+            Duration::from_micros(1_000_000 / frame_rate)
+        };
+
+        let grid_shape = [config.height, config.width];
+        let background = config.theme.background();
+        Self {
+            alive_states: Array2::from_elem(grid_shape, false),
+            // red_states: Array2::zeros(grid_shape),
+            // green_states: Array2::zeros(grid_shape),
+            // blue_states: Array2::zeros(grid_shape),
+            color_states: Array2::from_elem(grid_shape, background),
+            colony_states: Array2::from_elem(grid_shape, NO_COLONY),
+            colonies: 0,
+            painted_colony: None,
+            width: config.width,
+            height: config.height,
+            generation: 0,
+            frametime,
+            colorshift: config.colorshift,
+            spread_chance: config.spread_chance,
+            temperature: config.temperature,
+            boundary: config.boundary,
+            neighborhood: config.neighborhood,
+            spread_radius: config.spread_radius,
+            neighbor_offsets: config.neighborhood.offsets(config.spread_radius),
+            seed: config.seed,
+            palette: config.current_palette(),
+            background,
+            rule: Rule::default(),
+            changes: None,
+            births: None,
+            sources: None,
+        }
+    }
+
+    /// Recreates a grid saved partway through a run, from the state of every cell (row by row)
+    /// and the number of generations and colonies it had. Fails if there are too few cells.
+    pub fn restore(
+        config: &SimulationConfig,
+        generation: u64,
+        colonies: u32,
+        cells: impl IntoIterator<Item = (bool, RgbColor, u32)>,
+    ) -> Option<Self> {
+        let mut grid = Self::new(config);
+        let mut cells = cells.into_iter();
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                let (alive, color, colony) = cells.next()?;
+                grid.alive_states[[y, x]] = alive;
+                grid.color_states[[y, x]] = color;
+                grid.colony_states[[y, x]] = colony;
+            }
+        }
+        grid.generation = generation;
+        grid.colonies = colonies;
+        Some(grid)
+    }
+
+    /// A copy of the grid with every cell's color passed through `recolor`
+    pub fn recolored(&self, recolor: impl Fn(RgbColor) -> RgbColor) -> Self {
+        let mut grid = self.clone();
+        grid.color_states.mapv_inplace(recolor);
+        grid
+    }
+
+    /// A grid of (height, width) simulated cells with the same settings, each a copy of the
+    /// simulated cell of this one that `source` gives for it (counting from 0 in both)
+    pub fn sample(
+        &self,
+        size: (usize, usize),
+        source: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Self {
+        let border = self.boundary.border();
+        self.sample_cells(size, |y, x| {
+            let (y, x) = source(y, x);
+            (y + border, x + border)
+        })
+    }
+
+    /// The whole grid repeated `times` times across and down as the simulated cells of a bigger
+    /// one, to show whether a wrapping grid's edges meet without seams
+    pub fn tiled(&self, times: usize) -> Self {
+        let size = (self.height * times, self.width * times);
+        self.sample_cells(size, |y, x| (y % self.height, x % self.width))
+    }
+
+    // Like `sample`, but `source` gives any cell of this grid, its border included. Sampled grids
+    // are only drawn, never simulated, so they have a border whatever this grid's boundary is.
+    fn sample_cells(
+        &self,
+        (height, width): (usize, usize),
+        source: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Self {
+        let shape = [height + 2, width + 2];
+        // Cells outside the simulated area stay dead, like the border of any grid
+        let copied = |y: usize, x: usize| {
+            ((1..=height).contains(&y) && (1..=width).contains(&x)).then(|| source(y - 1, x - 1))
+        };
+        Self {
+            alive_states: Array2::from_shape_fn(shape, |(y, x)| {
+                copied(y, x).is_some_and(|cell| self.alive_states[cell])
+            }),
+            color_states: Array2::from_shape_fn(shape, |(y, x)| {
+                copied(y, x).map_or(self.background, |cell| self.color_states[cell])
+            }),
+            colony_states: Array2::from_shape_fn(shape, |(y, x)| {
+                copied(y, x).map_or(NO_COLONY, |cell| self.colony_states[cell])
+            }),
+            colonies: self.colonies,
+            painted_colony: self.painted_colony,
+            width: width + 2,
+            height: height + 2,
+            generation: self.generation,
+            frametime: self.frametime,
+            colorshift: self.colorshift,
+            spread_chance: self.spread_chance,
+            temperature: self.temperature,
+            boundary: Boundary::Exclude,
+            neighborhood: self.neighborhood,
+            spread_radius: self.spread_radius,
+            neighbor_offsets: self.neighbor_offsets.clone(),
+            seed: self.seed,
+            palette: self.palette,
+            background: self.background,
+            rule: self.rule,
+            changes: None,
+            births: self.births.as_ref().map(|births| {
+                Array2::from_shape_fn(shape, |(y, x)| copied(y, x).map_or(0, |cell| births[cell]))
+            }),
+            sources: self.sources.as_ref().map(|sources| {
+                Array2::from_shape_fn(shape, |(y, x)| copied(y, x).map_or(0, |cell| sources[cell]))
+            }),
+        }
+    }
+
+    /// A grid `factor` times smaller across and down, each of its cells standing for a square of
+    /// this one's: alive if any of them is, in their average color (dead ones counting with the
+    /// background color) and in the colony of the one in the middle
+    pub fn downsample(&self, factor: usize) -> Self {
+        let (width, height) = self.simulated_size();
+        let border = self.boundary.border();
+        let size = (height.div_ceil(factor), width.div_ceil(factor));
+        let middle = factor / 2;
+        let mut small = self.sample(size, |y, x| {
+            (
+                (y * factor + middle).min(height - 1),
+                (x * factor + middle).min(width - 1),
+            )
+        });
+        for y in 0..size.0 {
+            for x in 0..size.1 {
+                let block = s![
+                    border + y * factor..border + ((y + 1) * factor).min(height),
+                    border + x * factor..border + ((x + 1) * factor).min(width)
+                ];
+                let colors = self.color_states.slice(block);
+                let mut sum = [0; 3];
+                for color in colors {
+                    for (sum, channel) in sum.iter_mut().zip(color.as_slice()) {
+                        *sum += usize::from(channel);
+                    }
+                }
+                let average = sum.map(|sum| (sum / colors.len()) as u8);
+                small.alive_states[[y + 1, x + 1]] =
+                    self.alive_states.slice(block).iter().any(|a| *a);
+                small.color_states[[y + 1, x + 1]] = RgbColor::from(average);
+            }
+        }
+        small
+    }
+
+    /// Every cell's state (whether it's alive, its color and colony), row by row
+    pub fn cells(&self) -> impl Iterator<Item = (bool, RgbColor, u32)> + '_ {
+        self.alive_states
+            .iter()
+            .zip(&self.color_states)
+            .zip(&self.colony_states)
+            .map(|((alive, color), colony)| (*alive, *color, *colony))
+    }
+
+    /// Number of colonies founded so far
+    pub fn colony_count(&self) -> u32 {
+        self.colonies
+    }
+
+    /// Prints the grid to the terminal
+    pub fn show(&self) {
+        print!("{}", self.frame());
+    }
+
+    /// Renders the grid as text, one line per row, in color if the terminal supports it.
+    /// Lines end in "\r\n" so the frame also draws correctly while the terminal is in raw mode.
+    /// Rows are cut off at the terminal's width, since wrapped lines would be unreadable.
+    pub fn frame(&self) -> String {
+        let renderer = renderer();
+        let columns = terminal::screen_size().map_or(usize::MAX, |(columns, _)| {
+            columns.saturating_sub(2 * renderer.frame_width())
+        });
+        self.frame_within(renderer, (columns / renderer.cell_width(), usize::MAX))
+    }
+
+    /// Renders the grid as text with the given renderer, one line per row of characters
+    pub fn frame_with(&self, renderer: &Renderer) -> String {
+        self.frame_within(renderer, (usize::MAX, usize::MAX))
+    }
+
+    /// Renders the grid like `frame_with`, but only the characters that fit in (columns, rows)
+    /// starting from the top-left corner. Images are scaled to fit instead.
+    pub fn frame_within(&self, renderer: &Renderer, (columns, rows): (usize, usize)) -> String {
+        // Images are drawn from the grid's colors, so simulated color blindness is applied to those
+        if let Some(color_blindness) = renderer
+            .color_blindness()
+            .filter(|_| renderer.draws_image())
+        {
+            let seen = self.recolored(|color| color_blindness.simulate(color));
+            let renderer = renderer.clone().with_color_blindness(None);
+            return seen.frame_within(&renderer, (columns, rows));
+        }
+        if renderer.draws_sixel() {
+            return sixel::image(self);
+        }
+        if renderer.draws_kitty() {
+            return kitty::image(self);
+        }
+        if renderer.draws_iterm() {
+            return iterm::image(self);
+        }
+        let mut frame = String::new();
+        let (drawn_columns, drawn_rows) = self.drawn_size(renderer);
+        let (columns, rows) = (drawn_columns.min(columns), drawn_rows.min(rows));
+        let framed = renderer.frame_width() > 0;
+        if framed {
+            frame += &format!("{}\r\n", renderer.frame_line(columns, true));
+        }
+        let side = if framed { renderer.frame_side() } else { "" };
+        for row in 0..rows {
+            frame.push_str(side);
+            for column in 0..columns {
+                // let [red, green, blue] = self.get_color(y, x).as_slice();
+                // print!("{}", self.cell_char.truecolor(*red, *green, *blue));
+                frame.push_str(&self.draw_char(renderer, row, column));
+            }
+            frame.push_str(side);
+            frame.push_str("\r\n");
+        }
+        if framed {
+            frame += &format!("{}\r\n", renderer.frame_line(columns, false));
+        }
+        frame
+    }
+
+    /// How many characters across and down the given renderer draws the simulated cells in
+    pub fn drawn_size(&self, renderer: &Renderer) -> (usize, usize) {
+        let (across, down) = renderer.cells_per_char();
+        let (width, height) = self.simulated_size();
+        (width.div_ceil(across), height.div_ceil(down))
+    }
+
+    /// The character at (row, column) of a frame as the given renderer draws it.
+    /// Most styles draw a cell per character, Braille packs a block of them into each.
+    pub fn draw_char(&self, renderer: &Renderer, row: usize, column: usize) -> String {
+        let (y, x) = self.char_origin(renderer, row, column);
+        if renderer.draws_braille() {
+            let (braille, color) = braille::block(self, y, x);
+            return renderer.braille_cell(braille, color);
+        }
+        self.draw_cell(renderer, y, x)
+    }
+
+    /// The color the character at (row, column) of a frame is drawn in
+    pub fn char_color(&self, renderer: &Renderer, row: usize, column: usize) -> RgbColor {
+        let (y, x) = self.char_origin(renderer, row, column);
+        if renderer.draws_braille() {
+            return braille::block(self, y, x).1;
+        }
+        self.get_color(y, x)
+    }
+
+    /// The simulated cell drawn at a screen position, counted in rows and columns from a frame's
+    /// top-left corner, if there is one there. Characters standing for several cells give the one
+    /// in their middle.
+    pub fn cell_under(
+        &self,
+        renderer: &Renderer,
+        row: usize,
+        column: usize,
+    ) -> Option<(usize, usize)> {
+        if renderer.draws_image() {
+            return graphics::cell_under(self, row, column);
+        }
+        let (across, down) = renderer.cells_per_char();
+        let (y, x) = self.char_origin(renderer, row, column / renderer.cell_width());
+        let (y, x) = (y + (down - 1) / 2, x + (across - 1) / 2);
+        let border = self.boundary.border();
+        (y < self.height - border && x < self.width - border).then_some((y, x))
+    }
+
+    // The top-left cell of those the character at (row, column) of a frame stands for
+    fn char_origin(&self, renderer: &Renderer, row: usize, column: usize) -> (usize, usize) {
+        let (across, down) = renderer.cells_per_char();
+        let border = self.boundary.border();
+        (row * down + border, column * across + border)
+    }
+
+    /// The cell at (y, x) as the given renderer draws it
+    pub fn draw_cell(&self, renderer: &Renderer, y: usize, x: usize) -> String {
+        let color = self.get_color(y, x);
+        if renderer.draws_colonies() {
+            renderer.colony_cell(self.get_colony(y, x), color)
+        } else if renderer.draws_new_cells() && self.is_newborn(y, x) {
+            renderer.new_cell(color)
+        } else if self.alive_states[[y, x]] {
+            renderer.cell(color)
+        } else {
+            renderer.dead_cell(color)
+        }
+    }
+
+    /// Number of simulated (non-border) cells that are alive
+    pub fn alive_count(&self) -> usize {
+        self.alive_states
+            .indexed_iter()
+            .filter(|((y, x), alive)| **alive && self.is_simulated(*y, *x))
+            .count()
+    }
+
+    /// Percentage of the simulated (non-border) cells that are alive
+    pub fn fill_percent(&self) -> f64 {
+        let (width, height) = self.simulated_size();
+        100.0 * self.alive_count() as f64 / (width * height) as f64
+    }
+
+    /// How many cells across and down are simulated, leaving out the border if there is one
+    pub fn simulated_size(&self) -> (usize, usize) {
+        let border = 2 * self.boundary.border();
+        (self.width - border, self.height - border)
+    }
+
+    /// What happens to growth at the edges of the grid
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+
+    // Whether the cell at (y, x) is simulated, rather than part of the border
+    fn is_simulated(&self, y: usize, x: usize) -> bool {
+        self.boundary != Boundary::Exclude
+            || ((1..self.height - 1).contains(&y) && (1..self.width - 1).contains(&x))
+    }
+
+    /// The rule currently deciding how cells spread
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    /// The settings the grid was run with, written out for people, e.g.
+    /// "512x256, colorshift 6, spread chance 0.45, temperature 1, seed 12345, palette sunset,
+    /// rule drift, 873 generations", with the boundary, neighborhood and spread radius after the
+    /// size (e.g. "wrap boundary, von-neumann neighborhood, radius 3") unless they're the defaults
+    pub fn settings_summary(&self) -> String {
+        let seed = self
+            .seed
+            .map(|seed| format!(", seed {seed}"))
+            .unwrap_or_default();
+        let boundary = match self.boundary {
+            Boundary::Exclude => String::new(),
+            boundary => format!(", {boundary} boundary"),
+        };
+        let neighborhood = match self.neighborhood {
+            Neighborhood::Moore => String::new(),
+            neighborhood => format!(", {neighborhood} neighborhood"),
+        };
+        let radius = match self.spread_radius {
+            1 => String::new(),
+            radius => format!(", radius {radius}"),
+        };
+        format!(
+            "{}x{}{boundary}{neighborhood}{radius}, colorshift {}, spread chance {}, temperature {}{seed}, palette {}, \
+             rule {}, {} generations",
+            self.width,
+            self.height,
+            self.colorshift,
+            self.spread_chance,
+            self.temperature,
+            self.palette,
+            self.rule,
+            self.generation
+        )
+    }
+
+    /// The settings the grid was run with as (name, value), for tools to read back,
+    /// e.g. ("Colorshift", "6"). The seed is left out when it isn't known, and the boundary,
+    /// neighborhood and spread radius when they're the defaults.
+    pub fn recipe(&self) -> Vec<(&'static str, String)> {
+        let mut recipe = vec![
+            ("Width", self.width.to_string()),
+            ("Height", self.height.to_string()),
+            ("Colorshift", self.colorshift.to_string()),
+            ("Spread chance", self.spread_chance.to_string()),
+            ("Temperature", self.temperature.to_string()),
+            ("Palette", self.palette.to_string()),
+            ("Rule", self.rule.to_string()),
+            ("Generations", self.generation.to_string()),
+        ];
+        if self.spread_radius != 1 {
+            recipe.insert(2, ("Spread radius", self.spread_radius.to_string()));
+        }
+        if self.neighborhood != Neighborhood::Moore {
+            recipe.insert(2, ("Neighborhood", self.neighborhood.to_string()));
+        }
+        if self.boundary != Boundary::Exclude {
+            recipe.insert(2, ("Boundary", self.boundary.to_string()));
+        }
+        if let Some(seed) = self.seed {
+            recipe.insert(0, ("Seed", seed.to_string()));
+        }
+        recipe
+    }
+
+    /// Swaps the active rule. Cells that are already alive keep their colors.
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    pub fn get_color(&self, y: usize, x: usize) -> RgbColor {
+        self.color_states[[y, x]]
+    }
+
+    /// The colony a cell belongs to, or `NO_COLONY` if it's dead
+    pub fn get_colony(&self, y: usize, x: usize) -> u32 {
+        self.colony_states[[y, x]]
+    }
+
+    // Founds a new colony and returns its number
+    fn found_colony(&mut self) -> u32 {
+        self.colonies += 1;
+        self.colonies
+    }
+
+    fn set_color(&mut self, y: usize, x: usize, color: RgbColor) {
+        self.color_states[[y, x]] = color;
+        // println!("Setting {y} {x} to {color:?}");
+    }
+
+    /// Sets whether a cell is alive, its color and colony, recording the change if recording is on
+    fn set_cell(&mut self, y: usize, x: usize, alive: bool, color: RgbColor, colony: u32) {
+        if let Some(changes) = &mut self.changes {
+            changes.push(CellChange {
+                y,
+                x,
+                alive_before: self.alive_states[[y, x]],
+                color_before: self.color_states[[y, x]],
+                colony_before: self.colony_states[[y, x]],
+                birth_before: self.births.as_ref().map_or(0, |births| births[[y, x]]),
+                source_before: self.sources.as_ref().map_or(0, |sources| sources[[y, x]]),
+            });
+        }
+        if let Some(births) = &mut self.births {
+            if alive && !self.alive_states[[y, x]] {
+                births[[y, x]] = self.generation + 1;
+            }
+        }
+        // Children say where they spread from once they're placed
+        if let Some(sources) = &mut self.sources {
+            if alive && !self.alive_states[[y, x]] {
+                sources[[y, x]] = 0;
+            }
+        }
+        self.alive_states[[y, x]] = alive;
+        self.set_color(y, x, color);
+        self.colony_states[[y, x]] = colony;
+    }
+
+    /// Kills a cell, so neighboring colonies can grow back into it
+    pub fn kill_cell(&mut self, y: usize, x: usize) {
+        self.set_cell(y, x, false, self.background, NO_COLONY);
+    }
+
+    /// Brings a cell to life with the given color (or recolors it, if it's already alive).
+    /// Recolored cells stay in their colony, new ones join the colony of painted cells.
+    pub fn paint_cell(&mut self, y: usize, x: usize, color: RgbColor) {
+        let colony = if self.alive_states[[y, x]] {
+            self.get_colony(y, x)
+        } else {
+            match self.painted_colony {
+                Some(colony) => colony,
+                None => {
+                    let colony = self.found_colony();
+                    self.painted_colony = Some(colony);
+                    colony
+                }
+            }
+        };
+        self.set_cell(y, x, true, color, colony);
+    }
+
+    /// Starts recording every cell change so they can be undone later
+    pub fn start_recording(&mut self) {
+        self.changes.get_or_insert_with(Vec::new);
+    }
+
+    /// Starts keeping track of when each cell came to life and which way it spread from its
+    /// parent, so cells born in the latest generation can be drawn differently. Cells alive
+    /// already count as born in generation 0, from no parent.
+    pub fn track_births(&mut self) {
+        let shape = self.alive_states.raw_dim();
+        self.births.get_or_insert_with(|| Array2::zeros(shape));
+        self.sources.get_or_insert_with(|| Array2::zeros(shape));
+    }
+
+    /// Whether the cell at (y, x) was born in the latest generation, as far as births are tracked
+    pub fn is_newborn(&self, y: usize, x: usize) -> bool {
+        self.births
+            .as_ref()
+            .is_some_and(|births| births[[y, x]] == self.generation && self.alive_states[[y, x]])
+    }
+
+    /// The generation the cell at (y, x) came to life in, if it's alive and births are tracked
+    pub fn birth_generation(&self, y: usize, x: usize) -> Option<u64> {
+        let births = self.births.as_ref()?;
+        self.alive_states[[y, x]].then(|| births[[y, x]])
+    }
+
+    /// Which way the cell at (y, x) spread from its parent, as (rows down, columns right), if it's
+    /// alive, births are tracked and it had a parent rather than being spawned or painted
+    pub fn spread_direction(&self, y: usize, x: usize) -> Option<(isize, isize)> {
+        let sources = self.sources.as_ref()?;
+        match sources[[y, x]] {
+            0 => None,
+            _ if !self.alive_states[[y, x]] => None,
+            source => {
+                let [dy, dx] = self.neighbor_offsets[usize::from(source - 1)];
+                Some((dy, dx))
+            }
+        }
+    }
+
+    /// Returns the changes recorded since the last call, or nothing if recording is off
+    pub fn take_changes(&mut self) -> Vec<CellChange> {
+        self.changes
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Undoes recorded changes, restoring each cell to its state before the change, including
+    /// when it was born and which way it spread
+    pub fn revert(&mut self, changes: &[CellChange]) {
+        for change in changes.iter().rev() {
+            self.alive_states[[change.y, change.x]] = change.alive_before;
+            self.set_color(change.y, change.x, change.color_before);
+            self.colony_states[[change.y, change.x]] = change.colony_before;
+            if let Some(births) = &mut self.births {
+                births[[change.y, change.x]] = change.birth_before;
+            }
+            if let Some(sources) = &mut self.sources {
+                sources[[change.y, change.x]] = change.source_before;
+            }
+        }
+    }
+
+    // Returns a String representing a cell displayed in its color
+    fn get_cell_on_its_color(&self, y: usize, x: usize) -> String {
+        renderer().cell(self.get_color(y, x))
+    }
+
+    // Prints a message saying that this cell spread somewhere
+    fn spread_message(&self, y: usize, x: usize, new_y: usize, new_x: usize) {
+        let parent = self.get_cell_on_its_color(y, x);
+        let child = self.get_cell_on_its_color(new_y, new_x);
+        println!(
+            "{parent} / {:?} {y},{x} spread to {child} / {:?} {new_y},{new_x}",
+            self.get_color(y, x).as_slice(),
+            self.get_color(new_y, new_x).as_slice()
+        );
+    }
+
+    /// Makes a cell reproduce into its `neighbor`th neighbor, at (new_y, new_x)
+    fn make_child(
+        &mut self,
+        (y, x): (usize, usize),
+        neighbor: usize,
+        [new_y, new_x]: [usize; 2],
+        rng: &mut StdRng,
+    ) {
+        // let [red, green, blue] = self.get_color(y, x);
+
+        // Shift each color randomly
+        // let new_red = RGB_Color::shift_color();
+        // let new_green = RGB_Color::shift_color(green, rng, self.colorshift);
+        // let new_blue = RGB_Color::shift_color(blue, rng, self.colorshift);
+
+        // Get current color, and let the active rule derive the child's color from it
+        let current_color = self.get_color(y, x);
+        let colorshift = heated(self.colorshift, self.temperature);
+        let new_color: RgbColor = self.rule.child_color(current_color, colorshift, rng);
+
+        // Place cell
+        let colony = self.get_colony(y, x);
+        self.set_cell(new_y, new_x, true, new_color, colony);
+        if let Some(sources) = &mut self.sources {
+            sources[[new_y, new_x]] = neighbor as u16 + 1;
+        }
+        // println!("Cell at [{y} {x}] with Color {color_slice:?} spread to [{new_y} {new_x}] w/ Color ({new_color_slice:?})");
+        if verbosity::enabled(Verbosity::VeryVerbose) {
+            self.spread_message(y, x, new_y, new_x);
+        }
+    }
+
+    // Places a cell with a random color at a random position on the grid
+    pub fn spawn_orphan_at_random_position(&mut self, rng: &mut StdRng) {
+        // Index of new orphan cell, anywhere but the border
+        let [y, x] = random_cell([self.height, self.width], self.boundary.border(), rng);
+
+        // Place cell
+        let color = self
+            .palette
+            .orphan_color(heated(PALETTE_JITTER, self.temperature), rng);
+        let colony = self.found_colony();
+        self.set_cell(y, x, true, color, colony);
+        let cell = renderer().cell(color);
+        verbose!("{}", t!("run.spawning_orphan", cell = cell, y = y, x = x));
+    }
+
+    // The cells a cell at (y, x) can spread to, with their index in `neighbor_offsets`. Cells near
+    // the edges of a wrapping grid reach around to the opposite edge, other grids' edges cut their
+    // neighborhoods short.
+    fn neighbors(&self, y: usize, x: usize) -> impl Iterator<Item = (usize, [usize; 2])> + '_ {
+        let size = [self.height, self.width];
+        self.neighbor_offsets
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, &offset)| {
+                let neighbor = self.boundary.neighbor([y, x], offset, size)?;
+                Some((i, neighbor))
+            })
+    }
+
+    // Spreads a cell to one of its dead neighbors, picked at random, if it beats the spread chance
+    fn spread_to_random_dead_nbor(&mut self, y: usize, x: usize, rng: &mut StdRng) {
+        let dead_neighbors = self
+            .neighbors(y, x)
+            .filter(|(_, ind)| !self.alive_states[*ind]);
+        let spread_chance = self.rule.spread_chance(self.spread_chance);
+        if let Some((neighbor, new_cell)) = pick_child(dead_neighbors, spread_chance, rng) {
+            self.make_child((y, x), neighbor, new_cell, rng);
+        }
+    }
+
+    /// Advances the simulation by one generation, letting every live cell try to spread.
+    /// Returns false once every cell is alive, meaning the simulation is finished.
+    pub fn step(&mut self, yx_coordinate_pairs: &[[usize; 2]], rng: &mut StdRng) -> bool {
+        let mut seen_dead_cell = false;
+
+        for &[y, x] in yx_coordinate_pairs {
+            if self.alive_states[[y, x]] {
+                // println!("{} @ {y},{x} is ALIVE", self.get_cell_on_its_color(y, x));
+                self.spread_to_random_dead_nbor(y, x, rng);
+            } else {
+                seen_dead_cell = true;
+            }
+        }
+        self.generation += 1;
+        seen_dead_cell
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::simulation::{starting_grid, yx_coordinate_pairs};
+
+    // Everything recorded about the cells: whether they're alive, their colors and colonies, when
+    // they were born and which way they spread
+    type Snapshot = (
+        Array2<bool>,
+        Array2<[u8; 3]>,
+        Array2<u32>,
+        Option<Array2<u64>>,
+        Option<Array2<u16>>,
+    );
+
+    fn snapshot(grid: &Grid) -> Snapshot {
+        (
+            grid.alive_states.clone(),
+            grid.color_states.map(RgbColor::as_slice),
+            grid.colony_states.clone(),
+            grid.births.clone(),
+            grid.sources.clone(),
+        )
+    }
+
+    #[test]
+    fn reverting_steps_restores_births_and_directions() {
+        let config = SimulationConfig {
+            width: 10,
+            height: 8,
+            starting_live_cells: 3,
+            seed: Some(7),
+            ..SimulationConfig::default()
+        };
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut grid = starting_grid(&config, &mut rng);
+        grid.track_births();
+        grid.start_recording();
+        let cells = yx_coordinate_pairs(config.width, config.height, config.boundary);
+        for _ in 0..2 {
+            grid.step(&cells, &mut rng);
+        }
+        grid.take_changes();
+
+        let before = snapshot(&grid);
+        let generation = grid.generation;
+        let (y, x) = (0..config.height)
+            .flat_map(|y| (0..config.width).map(move |x| (y, x)))
+            .find(|&(y, x)| grid.spread_direction(y, x).is_some())
+            .expect("some cell spread in the first generations");
+        let mut generations = Vec::new();
+        for _ in 0..3 {
+            grid.step(&cells, &mut rng);
+            generations.push(grid.take_changes());
+        }
+        // A brush stroke killing a cell that had spread and painting it back gives it a new birth
+        // and no direction, which rewinding the generation it was made in has to undo
+        grid.kill_cell(y, x);
+        grid.paint_cell(y, x, RgbColor::from([1, 2, 3]));
+        generations.last_mut().unwrap().extend(grid.take_changes());
+        assert_eq!(grid.spread_direction(y, x), None);
+
+        for changes in generations.iter().rev() {
+            grid.revert(changes);
+            grid.generation -= 1;
+        }
+        assert_eq!(grid.generation, generation);
+        assert_eq!(snapshot(&grid), before);
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::VecDeque;
+
+use crate::grid::RgbColor;
+
+// Number of generations kept for rewinding, unless the user asks for more or less
+pub const HISTORY_SIZE_DEFAULT: usize = 500;
+
+/// The state a cell had before it was changed, so the change can be undone
+#[derive(Debug, Clone, Copy)]
+pub struct CellChange {
+    pub y: usize,
+    pub x: usize,
+    pub alive_before: bool,
+    pub color_before: RgbColor,
+    pub colony_before: u32,
+    // The cell's birth generation and spread direction before, while births are tracked
+    pub birth_before: u64,
+    pub source_before: u16,
+}
+
+/// A bounded ring buffer of the changes made by recent generations.
+/// Only changes are stored (not whole grids), so even large grids can keep a long history.
+#[derive(Debug)]
+pub struct History {
+    generations: VecDeque<Vec<CellChange>>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            generations: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Remembers the changes made by one generation, forgetting the oldest generation when full
+    pub fn push(&mut self, changes: Vec<CellChange>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.generations.len() == self.capacity {
+            self.generations.pop_front();
+        }
+        self.generations.push_back(changes);
+    }
+
+    /// Adds changes made between generations (e.g. brush strokes) to the most recent generation,
+    /// so rewinding that generation undoes them too. They're dropped if there's nothing to rewind.
+    pub fn append_to_latest(&mut self, changes: Vec<CellChange>) {
+        if let Some(latest) = self.generations.back_mut() {
+            latest.extend(changes);
+        }
+    }
+
+    /// Takes back the changes made by the most recent generation, if any are left
+    pub fn pop(&mut self) -> Option<Vec<CellChange>> {
+        self.generations.pop_back()
+    }
+
+    /// How many generations can currently be rewound
+    pub fn len(&self) -> usize {
+        self.generations.len()
+    }
+
+    /// Whether there's nothing left to rewind
+    pub fn is_empty(&self) -> bool {
+        self.generations.is_empty()
+    }
+}
@@ -0,0 +1,136 @@
+// The catalog of user-facing messages, so prompts and status output can be translated.
+//
+// Messages live in TOML files under `locales/`, one per language, and are looked up by
+// `section.name` keys with the `t!` macro. English is built in and fills in anything a translation
+// leaves out. The language comes from `SPREADING_COLORS_LANG`, or else the usual locale variables.
+//
+// To add a translation, copy `locales/en.toml` to `locales/<language>.toml`, translate the messages
+// and add it to `BUILT_IN_LOCALES`. Translations can also be tried out without rebuilding, by
+// putting them in `<config dir>/spreading_colors_ca/locales/<language>.toml`.
+
+use std::{collections::HashMap, env, fmt::Display, fs, path::PathBuf, sync::OnceLock};
+
+// Language the program is written in, used for anything a translation doesn't cover
+const DEFAULT_LANGUAGE: &str = "en";
+
+// Translations compiled into the binary, as (language, catalog) pairs
+const BUILT_IN_LOCALES: [(&str, &str); 1] =
+    [(DEFAULT_LANGUAGE, include_str!("../locales/en.toml"))];
+
+type Catalog = HashMap<String, String>;
+
+/// Looks up a message in the user's language and fills in its placeholders,
+/// e.g. `t!("run.seed", seed = 42)` or `t!("prompt.width")`
+#[macro_export]
+macro_rules! t {
+    ($key:literal) => {
+        $crate::i18n::message($key, &[])
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::message(
+            $key,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+pub use crate::t;
+
+/// The message stored under `key`, with every `{name}` placeholder replaced by its value.
+/// Use `t!` instead of calling this directly.
+pub fn message(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let (translation, english) = catalogs();
+    let Some(template) = translation
+        .as_ref()
+        .and_then(|catalog| catalog.get(key))
+        .or_else(|| english.get(key))
+    else {
+        // Showing the key beats showing nothing, and makes the missing message easy to find
+        return key.to_string();
+    };
+
+    args.iter()
+        .fold(template.clone(), |message, (name, value)| {
+            message.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+}
+
+// The user's language (if it isn't English) and English catalogs, loaded on first use
+fn catalogs() -> &'static (Option<Catalog>, Catalog) {
+    static CATALOGS: OnceLock<(Option<Catalog>, Catalog)> = OnceLock::new();
+    CATALOGS.get_or_init(|| {
+        let english = parse_catalog(BUILT_IN_LOCALES[0].1).expect("the English catalog is valid");
+        let language = language();
+        let translation = (language != DEFAULT_LANGUAGE)
+            .then(|| load_catalog(&language))
+            .flatten();
+        (translation, english)
+    })
+}
+
+// Picks the language from `SPREADING_COLORS_LANG` or the locale, e.g. "de_DE.UTF-8" -> "de"
+fn language() -> String {
+    ["SPREADING_COLORS_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .map(|locale| {
+            locale
+                .split(['_', '.', '@', '-'])
+                .next()
+                .unwrap_or_default()
+                .to_lowercase()
+        })
+        .filter(|language| !language.is_empty() && language != "c" && language != "posix")
+        .unwrap_or(DEFAULT_LANGUAGE.to_string())
+}
+
+// Where a user's own translation of `language` would be
+fn user_catalog_path(language: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| {
+        dir.join("spreading_colors_ca")
+            .join("locales")
+            .join(format!("{language}.toml"))
+    })
+}
+
+// Loads the catalog for `language`, preferring the user's own translation over a built-in one.
+// Unknown languages just fall back to English.
+fn load_catalog(language: &str) -> Option<Catalog> {
+    if let Some(path) = user_catalog_path(language) {
+        if let Ok(contents) = fs::read_to_string(&path) {
+            match parse_catalog(&contents) {
+                Ok(catalog) => return Some(catalog),
+                // This can't go through the catalog, since it's the catalog that's broken
+                Err(e) => eprintln!("Ignoring the translation at {}: {e}", path.display()),
+            }
+        }
+    }
+    BUILT_IN_LOCALES
+        .iter()
+        .find(|(built_in, _)| *built_in == language)
+        .and_then(|(_, contents)| parse_catalog(contents).ok())
+}
+
+// Flattens a TOML catalog into `section.name` keys
+fn parse_catalog(contents: &str) -> Result<Catalog, toml::de::Error> {
+    fn flatten(table: toml::Table, prefix: &str, catalog: &mut Catalog) {
+        for (name, value) in table {
+            let key = if prefix.is_empty() {
+                name
+            } else {
+                format!("{prefix}.{name}")
+            };
+            match value {
+                toml::Value::String(message) => {
+                    catalog.insert(key, message);
+                }
+                toml::Value::Table(table) => flatten(table, &key, catalog),
+                _ => {}
+            }
+        }
+    }
+
+    let mut catalog = Catalog::new();
+    flatten(contents.parse()?, "", &mut catalog);
+    Ok(catalog)
+}
@@ -0,0 +1,23 @@
+// iTerm2 rendering: the grid sent as a PNG through iTerm2's inline image protocol, which iTerm2,
+// WezTerm and mintty show as actual pixels, laid out as `graphics.rs` describes. Like kitty's
+// protocol it keeps every color exact.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::{graphics, grid::Grid};
+
+/// The simulated cells of `grid` as an iTerm2 inline image escape sequence, scaled up to fit the
+/// terminal, followed by a line break so text printed after it starts underneath
+pub fn image(grid: &Grid) -> String {
+    let png = graphics::png(grid);
+    // The image is shown where the cursor is (inline=1), at its own size in pixels, so the
+    // terminal doesn't scale it again and blur the cells
+    let scale = graphics::scale(grid);
+    let (columns, rows) = grid.simulated_size();
+    let (width, height) = (columns * scale, rows * scale);
+    format!(
+        "\x1B]1337;File=inline=1;size={};width={width}px;height={height}px;preserveAspectRatio=0:{}\x07\r\n",
+        png.len(),
+        STANDARD.encode(&png)
+    )
+}
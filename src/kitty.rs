@@ -0,0 +1,38 @@
+// Kitty graphics rendering: the grid sent as a PNG through the kitty graphics protocol, which kitty,
+// WezTerm and Ghostty show as actual pixels, laid out as `graphics.rs` describes. Unlike Sixel it
+// keeps every color exact, so the animation looks just like the saved image.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::{graphics, grid::Grid};
+
+// Every frame replaces the image with this id, instead of piling up new ones
+const IMAGE_ID: u32 = 1;
+// Most base64 bytes the protocol takes in one escape sequence
+const CHUNK_SIZE: usize = 4096;
+
+/// The simulated cells of `grid` as kitty graphics escape sequences, scaled up to fit the terminal,
+/// followed by a line break so text printed after it starts underneath
+pub fn image(grid: &Grid) -> String {
+    let payload = STANDARD.encode(graphics::png(grid));
+
+    // The first chunk says what to do: transmit and show (a=T) a PNG (f=100) without the terminal
+    // replying (q=2), since replies would be read as key presses. m=1 means more chunks follow.
+    let chunks: Vec<&str> = payload
+        .as_bytes()
+        .chunks(CHUNK_SIZE)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap())
+        .collect();
+    let mut sequence = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        let keys = if i == 0 {
+            format!("a=T,f=100,q=2,i={IMAGE_ID},m={more}")
+        } else {
+            format!("m={more}")
+        };
+        sequence += &format!("\x1B_G{keys};{chunk}\x1B\\");
+    }
+    sequence += "\r\n";
+    sequence
+}
@@ -0,0 +1,64 @@
+//! A cellular automaton that produces pretty images from spreading colors.
+//!
+//! The program in `main.rs` is built on this library, which can also be embedded elsewhere:
+//! as a Rust crate, or through the C interface in `ffi` when built as a shared library.
+
+pub mod ansi;
+pub mod archive;
+#[cfg(feature = "bevy")]
+pub mod bevy_plugin;
+pub mod braille;
+pub mod brush;
+pub mod cast;
+pub mod cli;
+pub mod color_space;
+pub mod compare;
+pub mod completions;
+pub mod config;
+pub mod contact_sheet;
+pub mod doctor;
+pub mod entropy;
+pub mod estimate;
+pub mod export;
+pub mod ffi;
+pub mod format;
+pub mod glyphs;
+pub mod graphics;
+pub mod grid;
+pub mod history;
+pub mod i18n;
+pub mod iterm;
+pub mod kitty;
+pub mod manifest;
+pub mod metadata;
+pub mod monitors;
+pub mod notify;
+pub mod palettes;
+pub mod plugins;
+pub mod presets;
+pub mod progress;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod randomize;
+pub mod replay;
+pub mod resolution;
+pub mod rules;
+pub mod scgrid;
+pub mod shader;
+pub mod simulation;
+pub mod sixel;
+pub mod state;
+pub mod swatches;
+pub mod sweep;
+pub mod terminal;
+#[cfg(test)]
+mod testing;
+pub mod timelapse;
+pub mod tui;
+pub mod verbosity;
+pub mod video;
+pub mod viewport;
+pub mod vision;
+pub mod volume;
+pub mod wallpaper;
+pub mod worker;
@@ -1,418 +1,1034 @@
-use colored::*; // for printing colored text to the terminal (for visualizing the simulation)
-use inquire::{Confirm, Text}; // For prompting the user for input through the terminal
+use inquire::{validator::Validation, Confirm, Select, Text}; // For prompting the user for input through the terminal
 
 use std::{
     fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    process,
     str::FromStr,
     time::{Duration, Instant},
 };
 
-use ndarray::Array2;
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 
-use rand::{rngs::ThreadRng, seq::IteratorRandom, thread_rng, Rng};
+use chrono::Local;
+use clap::Parser;
+use spreading_colors_ca::{
+    archive, cli, compare, completions, config, contact_sheet, doctor, entropy, estimate, export,
+    grid, i18n, manifest, monitors, notify, palettes, presets, randomize, rules, simulation, state,
+    swatches, sweep, terminal, timelapse, tui, verbosity, video, viewport, volume, wallpaper,
+    worker,
+};
 
-// ====================
-//   GLOBAL CONSTANTS
-// ====================
+use archive::ArchiveWriter;
+use cli::{Cli, Command, RunArgs};
+use config::*;
+use contact_sheet::ContactSheet;
+use estimate::{
+    confirm_export_size, expected_generations, format_bytes, frame_bytes, image_bytes, memory_bytes,
+};
+use export::{descriptive_filename, save_image, with_filename_suffix, ImageOptions};
+use grid::Grid;
+use i18n::t;
+use manifest::save_manifest;
+use monitors::MonitorLayout;
+use randomize::RANDOMIZED_SETTINGS;
+use simulation::{
+    render, simulation_animated, simulation_in_background, starting_grid, yx_coordinate_pairs,
+    Animation, Progress, Snapshots,
+};
+use terminal::Capabilities;
+use timelapse::{Sampling, Timelapse};
+use verbosity::{set_verbosity, status, Verbosity};
+use video::Video;
 
-// DEFAULTS (if user skips prompts these defaults are used in place of user input)
-const WIDTH_DEFAULT: usize = 32;
-const HEIGHT_DEFAULT: usize = 16;
-const FRAMERATE_DEFAULT: usize = 32;
-const SHOW_WHILE_RUNNING_DEFAULT: bool = false;
-const COLORSHIFT_DEFAULT: u8 = 4;
-const STARTING_LIVE_CELLS_DEFAULT: u32 = 1;
-const SPREAD_CHANCE_DEFAULT: f64 = 0.5;
+// Prompts for a number of at least `min` (and at most `max`, if given), asking again until the
+// input is one. If the prompt is skipped, then default_value is returned.
+fn parsed_prompt_skippable<T>(prompt: &str, default_value: T, min: T, max: Option<T>) -> T
+where
+    T: FromStr + Display + PartialOrd + Copy + 'static,
+{
+    let validator = move |input: &str| {
+        let in_range = input
+            .trim()
+            .parse::<T>()
+            .is_ok_and(|value| value >= min && max.is_none_or(|max| value <= max));
+        Ok(if in_range {
+            Validation::Valid
+        } else {
+            let message = match max {
+                Some(max) => t!("prompt.invalid_range", input = input, min = min, max = max),
+                None => t!("prompt.invalid_minimum", input = input, min = min),
+            };
+            Validation::Invalid(message.into())
+        })
+    };
+    Text::new(prompt)
+        .with_default(&default_value.to_string())
+        .with_validator(validator)
+        .prompt_skippable()
+        .expect("parsed_prompt failed to parse prompt")
+        .and_then(|input| input.trim().parse::<T>().ok())
+        .unwrap_or(default_value)
+}
 
-const LIVE_CELL_CHAR: char = '█'; // character used to represent 'live' cells
+// Prompts for a spread chance above 0 and at most 1, asking again until the input is one. A chance
+// of 0 is refused, since nothing would ever spread. If the prompt is skipped, then default_value
+// is returned.
+fn spread_chance_prompt_skippable(default_value: f64) -> f64 {
+    let validator = |input: &str| {
+        Ok(match input.trim().parse::<f64>() {
+            Ok(chance) if chance > 0.0 && chance <= 1.0 => Validation::Valid,
+            _ => Validation::Invalid(t!("prompt.invalid_spread_chance", input = input).into()),
+        })
+    };
+    Text::new(&t!("prompt.spread_chance"))
+        .with_default(&default_value.to_string())
+        .with_validator(validator)
+        .prompt_skippable()
+        .ok()
+        .flatten()
+        .and_then(|input| input.trim().parse().ok())
+        .unwrap_or(default_value)
+}
 
-#[derive(Debug, Clone, Copy)]
-struct RgbColor {
-    red: u8,
-    green: u8,
-    blue: u8,
+// Asks a yes/no question. If the prompt is skipped or can't be shown, then default is returned.
+fn confirm_skippable(prompt: &str, default: bool) -> bool {
+    Confirm::new(prompt)
+        .with_default(default)
+        .prompt_skippable()
+        .ok()
+        .flatten()
+        .unwrap_or(default)
 }
 
-impl RgbColor {
-    fn as_slice(&self) -> [u8; 3] {
-        [self.red, self.green, self.blue]
-    }
+// Lines left free under a plain preview, for the prompt asked after it and the line it's answered on
+const PREVIEW_PROMPT_LINES: usize = 2;
+// How many copies across and down wrapping grids are previewed in
+const TILED_PREVIEW: usize = 2;
 
-    // Returns a random color
-    fn random(rng: &mut ThreadRng) -> Self {
-        Self {
-            red: rng.gen(),
-            green: rng.gen(),
-            blue: rng.gen(),
-        }
-    }
+// Settings the user is prompted for, in the order they're asked. The seed isn't one of them,
+// since a fresh seed is picked for every run unless one is given on the command line.
+const PROMPTED_SETTINGS: [&str; 7] = [
+    "width",
+    "height",
+    "starting_live_cells",
+    "framerate",
+    "show_while_running",
+    "colorshift",
+    "spread_chance",
+];
 
-    // Increases or decreases a color value (red, green, or blue) given a shift value
-    fn shift_hue(hue: u8, shift: u8, rng: &mut ThreadRng) -> u8 {
-        let r = rng.gen_range(0..shift);
-        if rng.gen() {
-            hue.saturating_sub(r)
-        } else {
-            hue.saturating_add(r)
+// Prompts for one setting, offering its current value as the default
+fn prompt_setting(config: &mut SimulationConfig, key: &str) {
+    match key {
+        "width" => {
+            config.width =
+                parsed_prompt_skippable(&t!("prompt.width"), config.width, MIN_GRID_SIZE, None)
         }
-    }
-
-    /// Shifts each of a color's Red, Green, and Blue values randomly,
-    /// given a `shift` value and a random number generator
-    fn shift_color(&self, shift: u8, rng: &mut ThreadRng) -> Self {
-        Self {
-            red: RgbColor::shift_hue(self.red, shift, rng),
-            green: RgbColor::shift_hue(self.green, shift, rng),
-            blue: RgbColor::shift_hue(self.blue, shift, rng),
+        "height" => {
+            config.height =
+                parsed_prompt_skippable(&t!("prompt.height"), config.height, MIN_GRID_SIZE, None)
+        }
+        "starting_live_cells" => {
+            config.starting_live_cells = parsed_prompt_skippable(
+                &t!("prompt.starting_live_cells"),
+                config.starting_live_cells,
+                1,
+                None,
+            )
         }
+        "framerate" => {
+            config.framerate =
+                parsed_prompt_skippable(&t!("prompt.framerate"), config.framerate, 1, None)
+        }
+        "show_while_running" => {
+            config.show_while_running =
+                confirm_skippable(&t!("prompt.animate"), config.show_while_running)
+        }
+        "colorshift" => {
+            config.colorshift = parsed_prompt_skippable(
+                &t!("prompt.colorshift"),
+                config.colorshift,
+                1,
+                Some(u8::MAX),
+            )
+        }
+        "spread_chance" => {
+            config.spread_chance = spread_chance_prompt_skippable(config.spread_chance)
+        }
+        _ => unreachable!("only prompted settings are asked for"),
     }
 }
 
-impl From<[u8; 3]> for RgbColor {
-    fn from(value: [u8; 3]) -> Self {
-        let [red, green, blue] = value;
-        Self { red, green, blue }
+// The current value of a prompted setting, for showing it to the user
+fn setting_value(config: &SimulationConfig, key: &str) -> String {
+    match key {
+        "width" => config.width.to_string(),
+        "height" => config.height.to_string(),
+        "starting_live_cells" => config.starting_live_cells.to_string(),
+        "framerate" => config.framerate.to_string(),
+        "show_while_running" => config.show_while_running.to_string(),
+        "colorshift" => config.colorshift.to_string(),
+        "spread_chance" => config.spread_chance.to_string(),
+        _ => unreachable!("only prompted settings are shown"),
     }
 }
 
-#[derive(Debug, Clone)]
-struct Grid {
-    alive_states: Array2<bool>,
-    // red_states: Array2<u8>,
-    // green_states: Array2<u8>,
-    // blue_states: Array2<u8>,
-    color_states: Array2<RgbColor>,
-
-    // Dimensions of the simulation
-    width: usize,
-    height: usize,
-
-    /*
-    Time to sleep between frames
-    (this only matters if the simulation is being animated in the terminal.)
-    */
-    frametime: Duration,
-
-    /*
-    When a living cell spreads to a dead cell,
-    the new cell's color is generated using
-    the parent's color and this value
-    */
-    colorshift: u8,
-    cell_char: String,
-    spread_chance: f64,
+// Prompts for every setting individually, falling back to the defaults for skipped prompts
+fn prompt_custom_settings() -> SimulationConfig {
+    let mut config = SimulationConfig::default();
+    for key in PROMPTED_SETTINGS {
+        prompt_setting(&mut config, key);
+    }
+    review_settings(config)
 }
 
-impl Grid {
-    /// Prints the grid to the terminal
-    fn show(&self) {
-        for y in 1..(self.height - 1) {
-            for x in 1..(self.width - 1) {
-                // let [red, green, blue] = self.get_color(y, x).as_slice();
-                // print!("{}", self.cell_char.truecolor(*red, *green, *blue));
-                print!("{}", self.get_cell_on_its_color(y, x))
-            }
-            println!();
+// Shows every chosen setting and lets the user fix any one of them,
+// until they're happy to start (or skip the question)
+fn review_settings(mut config: SimulationConfig) -> SimulationConfig {
+    let start = t!("prompt.review_start");
+    loop {
+        println!("{}", t!("prompt.review_summary"));
+        let key_width = PROMPTED_SETTINGS.iter().map(|key| key.len()).max().unwrap();
+        for key in PROMPTED_SETTINGS {
+            println!("  {key:<key_width$}  {}", setting_value(&config, key));
         }
-    }
 
-    fn get_color(&self, y: usize, x: usize) -> RgbColor {
-        self.color_states[[y, x]]
+        let mut options = vec![start.clone()];
+        options.extend(
+            PROMPTED_SETTINGS
+                .iter()
+                .map(|key| t!("prompt.review_edit", setting = key)),
+        );
+        let Ok(choice) = Select::new(&t!("prompt.review"), options.clone())
+            .with_page_size(options.len())
+            .prompt()
+        else {
+            return config;
+        };
+        if choice == start {
+            return config;
+        }
+        // The chosen option lines up with its setting, offset by the start option
+        let index = options.iter().position(|o| *o == choice).unwrap() - 1;
+        prompt_setting(&mut config, PROMPTED_SETTINGS[index]);
     }
+}
 
-    fn set_color(&mut self, y: usize, x: usize, color: RgbColor) {
-        self.color_states[[y, x]] = color;
-        // println!("Setting {y} {x} to {color:?}");
+// Offers to save freshly entered settings so they show up in the preset selector next time
+fn offer_to_save_preset(config: &SimulationConfig) {
+    if !confirm_skippable(&t!("prompt.save_preset"), false) {
+        return;
     }
-
-    // Returns a String representing a cell displayed in its color
-    fn get_cell_on_its_color(&self, y: usize, x: usize) -> ColoredString {
-        let [r, g, b] = self.get_color(y, x).as_slice();
-        self.cell_char.truecolor(r, g, b)
+    let validator = |input: &str| {
+        // Empty names are turned down after the prompt, like a skipped one
+        Ok(
+            if input.trim().is_empty() || presets::is_valid_name(input.trim()) {
+                Validation::Valid
+            } else {
+                Validation::Invalid(t!("preset.invalid_name").into())
+            },
+        )
+    };
+    let Ok(name) = Text::new(&t!("prompt.preset_name"))
+        .with_validator(validator)
+        .prompt()
+    else {
+        return;
+    };
+    let name = name.trim();
+    if name.is_empty() {
+        println!("{}", t!("preset.empty_name"));
+        return;
     }
 
-    // Prints a message saying that this cell spread somewhere
-    fn spread_message(&self, y: usize, x: usize, new_y: usize, new_x: usize) {
-        let parent = self.get_cell_on_its_color(y, x);
-        let child = self.get_cell_on_its_color(new_y, new_x);
-        println!(
-            "{parent} / {:?} {y},{x} spread to {child} / {:?} {new_y},{new_x}",
-            self.get_color(y, x).as_slice(),
-            self.get_color(new_y, new_x).as_slice()
-        );
+    match presets::save_preset(name, config) {
+        Ok(path) => println!("{}", t!("preset.saved", name = name, path = path.display())),
+        Err(e) => println!("{}", t!("preset.save_failed", error = e)),
     }
+}
 
-    /// Makes a cell reproduce
-    fn make_child(&mut self, y: usize, x: usize, new_y: usize, new_x: usize, rng: &mut ThreadRng) {
-        // let [red, green, blue] = self.get_color(y, x);
-
-        // Shift each color randomly
-        // let new_red = RGB_Color::shift_color();
-        // let new_green = RGB_Color::shift_color(green, rng, self.colorshift);
-        // let new_blue = RGB_Color::shift_color(blue, rng, self.colorshift);
-
-        // Get current color, and shift each of its color channels randomly using self.colorshift
-        let current_color = self.get_color(y, x);
-        let new_color: RgbColor = current_color.shift_color(self.colorshift, rng);
-
-        // Place cell
-        self.alive_states[[new_y, new_x]] = true;
-        self.set_color(new_y, new_x, new_color);
-        // println!("Cell at [{y} {x}] with Color {color_slice:?} spread to [{new_y} {new_x}] w/ Color ({new_color_slice:?})");
-        // self.spread_message(y, x, new_y, new_x);
-    }
-
-    // Places a cell with a random color at a random position on the grid
-    fn spawn_orphan_at_random_position(&mut self, rng: &mut ThreadRng) {
-        // Index of new orphan cell
-        let x = rng.gen_range(1..(self.width - 1));
-        let y = rng.gen_range(1..(self.height - 1));
-
-        // Place cell
-        self.alive_states[[y, x]] = true;
-        let color = RgbColor::random(rng);
-        self.set_color(y, x, color);
-        let [red, green, blue] = color.as_slice();
-        let color_str = self.cell_char.truecolor(red, green, blue);
-        println!("Spawning orphan {color_str} @ {y},{x}");
-    }
-
-    // Checks all eight orthogonal neighbors of a cell and returns their x and y indices in the grid
-    fn spread_to_random_dead_nbor(&mut self, y: usize, x: usize, rng: &mut ThreadRng) {
-        if let Some([new_y, new_x]) = [
-            [y - 1, x - 1],
-            [y - 1, x],
-            [y - 1, x + 1],
-            [y, x - 1],
-            [y, x + 1],
-            [y + 1, x - 1],
-            [y + 1, x],
-            [y + 1, x + 1],
-        ]
-        .into_iter()
-        .filter(|ind| !self.alive_states[*ind])
-        .choose(rng)
+// Asks the user how they'd like to configure the simulation.
+// Saved presets are only offered when at least one exists.
+fn choose_settings() -> SimulationConfig {
+    let default_settings = t!("prompt.default_settings");
+    let custom_settings = t!("prompt.custom_settings");
+
+    let preset_names = presets::list_presets();
+    if preset_names.is_empty() {
+        return if Confirm::new(&t!("prompt.use_defaults"))
+            .prompt()
+            .unwrap_or(true)
         {
-            if rng.gen_range(0.0..1.0) < self.spread_chance {
-                self.make_child(y, x, new_y, new_x, rng);
-            }
+            SimulationConfig::default()
+        } else {
+            let config = prompt_custom_settings();
+            offer_to_save_preset(&config);
+            config
+        };
+    }
+
+    let mut options = vec![default_settings.clone(), custom_settings.clone()];
+    options.extend(
+        preset_names
+            .iter()
+            .map(|name| t!("prompt.preset_option", name = name)),
+    );
+
+    match Select::new(&t!("prompt.choose_settings"), options.clone()).prompt() {
+        Ok(choice) if choice == custom_settings => {
+            let config = prompt_custom_settings();
+            offer_to_save_preset(&config);
+            config
         }
+        Ok(choice) if choice != default_settings => {
+            // The chosen option lines up with its preset, offset by the two fixed options
+            let index = options.iter().position(|o| *o == choice).unwrap() - 2;
+            let name = &preset_names[index];
+            presets::load_preset(name).unwrap_or_else(|e| {
+                println!("{}", t!("preset.load_failed", name = name, error = e));
+                println!("{}", t!("preset.using_defaults"));
+                SimulationConfig::default()
+            })
+        }
+        _ => SimulationConfig::default(),
     }
 }
 
-// Same as parsed prompt, but this prompt is skippable.
-// If the prompt is skipped or the user's input cannot be parsed as type T, then default_value is returned.
-fn parsed_prompt_skippable<T: FromStr + Display>(prompt: &str, default_value: T) -> T {
-    Text::new(prompt)
-        .with_default(&default_value.to_string())
-        .prompt_skippable()
-        .expect("parsed_prompt failed to parse prompt")
-        .unwrap()
-        .to_lowercase()
-        .parse::<T>()
-        .unwrap_or(default_value)
+// Reports settings the simulation can't run with, then exits
+fn exit_with_config_error(e: ConfigError) -> ! {
+    eprintln!("{}", t!("run.invalid_settings", error = e));
+    process::exit(1);
 }
 
-fn confirm_skippable(prompt: &str, default: bool) -> bool {
-    Confirm::new(prompt).with_default(default).prompt().unwrap()
+fn main() {
+    let cli = Cli::parse();
+    set_verbosity(Verbosity::from_flags(cli.quiet, cli.verbose));
+    match cli.into_command() {
+        Command::Run(mut args) => {
+            // Names that can't be saved are refused before the run rather than after it
+            args.output = args.output.map(|output| {
+                args.format.filename(&output).unwrap_or_else(|error| {
+                    eprintln!("{}", t!("export.invalid_name", error = error));
+                    process::exit(1);
+                })
+            });
+            run(&args)
+        }
+        Command::Worker(args) => worker::run(&args),
+        Command::Sweep(args) => sweep::run(&args),
+        Command::Compare(args) => compare::run(&args),
+        Command::Rules(args) => rules::run(&args),
+        Command::Completions(args) => completions::run(&args),
+        Command::Doctor(args) => doctor::run(&args),
+        Command::Volume(args) => volume::run(&args),
+    }
 }
 
-fn main() {
+// Reports a saved run that can't be continued, then exits
+fn exit_with_resume_error(path: &Path, e: state::StateError) -> ! {
+    let path = path.display();
+    eprintln!("{}", t!("run.resume_failed", path = path, error = e));
+    process::exit(1);
+}
+
+// Runs a single simulation, prompting for anything that wasn't given on the command line
+fn run(cli: &RunArgs) {
+    // Frames written to stdout would be mixed up with anything else printed there
+    if cli.frames_to_stdout {
+        set_verbosity(Verbosity::Quiet);
+    }
     // ==============================
     //     SET SIMULATION SETTINGS
     // ==============================
-    let (
-        width,
-        height,
-        starting_live_cells,
-        framerate,
-        show_while_running,
-        colorshift,
-        spread_chance,
-    ) = if Confirm::new("Run with default settings?")
-        .prompt()
-        .unwrap_or(true)
-    {
-        (
-            WIDTH_DEFAULT,
-            HEIGHT_DEFAULT,
-            STARTING_LIVE_CELLS_DEFAULT,
-            FRAMERATE_DEFAULT,
-            SHOW_WHILE_RUNNING_DEFAULT,
-            COLORSHIFT_DEFAULT,
-            SPREAD_CHANCE_DEFAULT,
-        )
+    // A saved run, config file, preset or settings given as flags skip the settings prompts entirely
+    let mut resumed = None;
+    let mut config = if let Some(path) = &cli.resume {
+        let saved = state::load(path).unwrap_or_else(|e| exit_with_resume_error(path, e));
+        let config = saved.config.clone();
+        resumed = Some(saved);
+        config
+    } else if let Some(path) = &cli.config {
+        SimulationConfig::from_file(path, cli.profile.as_deref())
+            .unwrap_or_else(|e| exit_with_config_error(e))
+    } else if let Some(name) = &cli.preset {
+        presets::load_preset(name).unwrap_or_else(|e| {
+            eprintln!("{}", t!("preset.load_failed", name = name, error = e));
+            process::exit(1);
+        })
+    } else if cli.yes || cli.dry_run || cli.has_setting_overrides() {
+        SimulationConfig::default()
     } else {
-        (
-            parsed_prompt_skippable("Enter Width in pixels", WIDTH_DEFAULT),
-            parsed_prompt_skippable("Enter Height in pixels", HEIGHT_DEFAULT),
-            parsed_prompt_skippable(
-                "Enter the number of Starting Live Cells",
-                STARTING_LIVE_CELLS_DEFAULT,
-            ),
-            parsed_prompt_skippable("Enter framerate", FRAMERATE_DEFAULT),
-            confirm_skippable(
-                "Animate in the terminal while running?",
-                SHOW_WHILE_RUNNING_DEFAULT,
-            ),
-            parsed_prompt_skippable("Enter colorshift value", COLORSHIFT_DEFAULT),
-            parsed_prompt_skippable("Enter spreadchance (0.0 -> 1.0)", SPREAD_CHANCE_DEFAULT),
+        choose_settings()
+    };
+    cli.apply_overrides(&mut config);
+    if cli.randomize {
+        randomize::randomize(&mut config, &mut thread_rng());
+    }
+
+    // Wallpapers are sized to fit the monitors, either spanning all of them or one per monitor.
+    // Without any (e.g. over SSH), the configured size is used instead.
+    let monitors = cli.monitors.and_then(|layout| {
+        let monitors = monitors::detect().unwrap_or_else(|error| {
+            eprintln!("{}", t!("run.monitors_failed", error = error));
+            Vec::new()
+        });
+        let size = match layout {
+            MonitorLayout::Span => monitors::spanning_size(&monitors),
+            MonitorLayout::Each => monitors.first().map(|m| (m.width, m.height)),
+        };
+        let Some(size) = size else {
+            let (width, height) = (config.width, config.height);
+            eprintln!("{}", t!("run.no_monitors", width = width, height = height));
+            return None;
+        };
+        (config.width, config.height) = size;
+        Some(monitors)
+    });
+    if let Err(e) = config.validate() {
+        exit_with_config_error(e);
+    }
+    // Nothing would ever spread, so the run would never finish
+    if config.spread_chance <= 0.0 {
+        eprintln!("{}", t!("run.never_finishes"));
+        process::exit(1);
+    }
+    let image_options = ImageOptions::new(
+        cli.shader.as_deref(),
+        cli.scale,
+        cli.orientation,
+        cli.color_space,
+        &cli.format,
+        &cli.metadata,
+        cli.hash_names,
+    )
+    .unwrap_or_else(|error| {
+        eprintln!("{}", error.message());
+        process::exit(1);
+    })
+    .with_layers(&cli.layers);
+    // How many images this run renders, all in the background if there's more than one
+    let batch_size = match (cli.monitors, &monitors) {
+        (Some(MonitorLayout::Each), Some(monitors)) => monitors.len(),
+        _ => cli.count as usize,
+    };
+
+    // Screen readers can't follow an animation, so accessible runs report their progress in text
+    terminal::init(cli.renderer(Capabilities::detect()));
+    if cli.accessible && config.show_while_running {
+        status!("{}", t!("run.accessible_no_animation"));
+        config.show_while_running = false;
+    }
+    if cli.frames_to_stdout {
+        config.show_while_running = false;
+    }
+
+    if cli.dry_run {
+        print_dry_run(&config, cli, batch_size);
+        return;
+    }
+
+    if cli.notify {
+        notify::notify_on_panic();
+    }
+    terminal::report_downgrades();
+
+    let auto_palette = config.palette == Some(palettes::ColorPalette::Auto);
+    config.resolve_auto();
+    if auto_palette {
+        status!("{}", t!("run.palette", palette = config.current_palette()));
+    }
+
+    if let Some(source) = cli.entropy {
+        let name = format!("{source:?}").to_lowercase();
+        status!("{}", t!("run.gathering_entropy", source = name));
+        match entropy::gather_seed(source) {
+            Ok(seed) => config.seed = Some(seed),
+            Err(error) => status!("{}", t!("run.entropy_failed", source = name, error = error)),
+        }
+    }
+
+    if batch_size > 1 {
+        let mut configs: Vec<SimulationConfig> = match &monitors {
+            Some(monitors) => monitors.iter().map(|m| m.fit(&config)).collect(),
+            None => vec![config.clone(); batch_size],
+        };
+        // Every image of a randomized batch gets its own picks
+        if cli.randomize {
+            for config in configs.iter_mut().skip(1) {
+                randomize::randomize(config, &mut thread_rng());
+            }
+        }
+        let estimated_bytes = configs
+            .iter()
+            .map(|config| image_bytes(config, cli.scale))
+            .fold(0, u64::saturating_add);
+        let what = t!("run.batch_size", count = batch_size);
+        if confirm_export_size(&what, estimated_bytes, cli.max_export_size, !cli.yes) {
+            run_batch(&configs, cli, &image_options);
+        }
+        return;
+    }
+
+    // Background runs save a snapshot at every breakpoint the run is expected to reach
+    let mut break_at = cli.break_at.clone();
+    if !config.show_while_running && !break_at.is_empty() {
+        let expected = expected_generations(&config).unwrap_or(u64::MAX);
+        let snapshots = break_at.iter().filter(|g| **g <= expected).count() as u64;
+        let what = t!("run.snapshots_size", count = snapshots);
+        if !confirm_export_size(
+            &what,
+            snapshots.saturating_mul(image_bytes(&config, cli.scale)),
+            cli.max_export_size,
+            !cli.yes,
+        ) {
+            break_at.clear();
+        }
+    }
+    let sampling = Sampling::new(
+        cli.timelapse_sampling,
+        cli.timelapse_every,
+        cli.timelapse_frames,
+        expected_generations(&config),
+    );
+    // Timelapse frames are kept until the end, so they're checked like snapshots
+    let keep_frames = cli.timelapse.is_some() && {
+        let expected = expected_generations(&config).unwrap_or(u64::MAX);
+        let frames = sampling.frames_over(expected);
+        let what = t!("run.timelapse_size", count = frames);
+        confirm_export_size(
+            &what,
+            frames.saturating_mul(frame_bytes(&config)),
+            cli.max_export_size,
+            !cli.yes,
         )
     };
+    let video = cli.export_video.as_ref().and_then(|path| {
+        let fps = cli.video_fps.unwrap_or(config.framerate as f64);
+        let size = (config.width, config.height);
+        Video::start(path, size, fps, cli.video_scale)
+            .inspect_err(|e| eprintln!("{}", t!("run.video_failed", error = e)))
+            .ok()
+    });
+    // Frame sequences are checked like snapshots too, since they're as many images
+    let frames_dir = cli.frames_dir.as_ref().filter(|dir| {
+        let expected = expected_generations(&config).unwrap_or(u64::MAX);
+        let frames = sampling.frames_over(expected);
+        let what = t!("run.frames_size", count = frames);
+        confirm_export_size(
+            &what,
+            frames.saturating_mul(frame_bytes(&config)),
+            cli.max_export_size,
+            !cli.yes,
+        ) && fs::create_dir_all(dir)
+            .inspect_err(|e| eprintln!("{}", t!("run.frames_failed", error = e)))
+            .is_ok()
+    });
+    let archive = cli.frames_archive.as_ref().and_then(|path| {
+        let size = (config.width as u32, config.height as u32);
+        ArchiveWriter::create(path, size)
+            .inspect_err(|e| eprintln!("{}", t!("run.archive_failed", error = e)))
+            .ok()
+    });
+    let mut timelapse = Timelapse::new(sampling);
+    if keep_frames {
+        timelapse = timelapse.keep_frames();
+    }
+    if let Some(video) = video {
+        timelapse = timelapse.with_video(video);
+    }
+    if let Some(dir) = frames_dir {
+        timelapse = timelapse.with_frame_dir(dir.clone());
+    }
+    if cli.frames_to_stdout {
+        timelapse = timelapse.with_stdout();
+    }
+    if let Some(archive) = archive {
+        timelapse = timelapse.with_archive(archive);
+    }
+    let mut timelapse = timelapse.is_recording().then_some(timelapse);
 
+    if cli.randomize {
+        print_randomized(&config);
+    }
     let now = Instant::now(); // Begin timing the program
-    let frametime = {
-        let frame_rate: u64 = framerate.try_into().unwrap();
-        Duration::from_micros(1_000_000 / frame_rate)
-    }; // the amount of time that the animation sleeps between frames to keep a constant framerate
-
-    let grid_shape = [height, width];
-    let mut grid = Grid {
-        alive_states: Array2::from_elem(grid_shape, false),
-        // red_states: Array2::zeros(grid_shape),
-        // green_states: Array2::zeros(grid_shape),
-        // blue_states: Array2::zeros(grid_shape),
-        color_states: Array2::from_elem(
-            grid_shape,
-            RgbColor {
-                red: 0,
-                green: 0,
-                blue: 0,
-            },
-        ),
-        width,
-        height,
-        frametime,
-        colorshift,
-        cell_char: LIVE_CELL_CHAR.to_string(),
-        spread_chance,
-    };
 
-    // =======================
-    //  PLACE STARTING CELLS
-    // =======================
-    let mut rng = thread_rng();
-    for _ in 0..starting_live_cells {
-        grid.spawn_orphan_at_random_position(&mut rng);
-    }
-
-    /*
-     ____ ___ __  __ _   _ _        _  _____ ___ ___  _   _
-    / ___|_ _|  \/  | | | | |      / \|_   _|_ _/ _ \| \ | |
-    \___ \| || |\/| | | | | |     / _ \ | |  | | | | |  \| |
-     ___) | || |  | | |_| | |___ / ___ \| |  | | |_| | |\  |
-    |____/___|_|  |_|\___/|_____/_/   \_\_| |___\___/|_| \_|
-    */
-
-    // Produces all the indices of a Vec<Vec<_>> with some width and height
-    // Height is the .len() of the outer vec
-    // Width is the .len() of the inner vec
-    // In theory this would improve performance. In practice it does not.
-    let mut yx_coordinate_pairs = Vec::with_capacity(width * height);
-    for y in 1..(height - 1) {
-        for x in 1..(width - 1) {
-            yx_coordinate_pairs.push([y, x]);
+    // Runs are reproducible from their seed, so print it in case the result is worth regenerating
+    let seed = *config.seed.get_or_insert_with(|| thread_rng().gen());
+    status!("{}", t!("run.seed", seed = seed));
+    let filename = cli
+        .output
+        .clone()
+        .unwrap_or_else(|| descriptive_filename(&config, seed, cli.format.extension()));
+    let filename = image_options
+        .settings_path(Path::new(&filename), &config)
+        .display()
+        .to_string();
+    let (mut rng, mut grid) = match resumed {
+        Some(saved) => {
+            let path = cli.resume.as_deref().unwrap();
+            let grid = saved
+                .into_grid(&config)
+                .unwrap_or_else(|e| exit_with_resume_error(path, e));
+            status!("{}", t!("run.resuming", generation = grid.generation));
+            // Offsetting the seed by the generation keeps a resumed run from replaying
+            // the random numbers its start used
+            let rng = StdRng::seed_from_u64(seed.wrapping_add(grid.generation));
+            (rng, grid)
         }
+        None => {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let grid = starting_grid(&config, &mut rng);
+            (rng, grid)
+        }
+    };
+    // Cells already alive in resumed runs count as born at the start, since when isn't saved
+    if image_options.needs_births() {
+        grid.track_births();
+    }
+    if cli.save_state.is_some() && !config.show_while_running {
+        simulation::stop_on_interrupt();
     }
-    // Make immutable, since it will never be modified again.
-    yx_coordinate_pairs.shrink_to_fit();
+    if cli.cast.is_some() && !config.show_while_running {
+        eprintln!("{}", t!("run.cast_unused"));
+    }
+    let yx_coordinate_pairs = yx_coordinate_pairs(config.width, config.height, config.boundary);
 
     // ANIMATE or RUN IN BACKGROUND
     // Depending on what the user decided earlier.
-    let final_grid: Grid = if show_while_running {
-        // simulation_animated(grid, &yx_coordinate_pairs)
-        todo!();
+    let final_grid: Grid = if config.show_while_running {
+        simulation_animated(
+            grid,
+            &config,
+            &mut rng,
+            &yx_coordinate_pairs,
+            Animation {
+                break_at: &break_at,
+                history_size: cli.history_size,
+                draw_every: cli.draw_every,
+                cast: cli.cast.as_deref(),
+                timelapse: timelapse.as_mut(),
+            },
+        )
     } else {
-        simulation_in_background(grid, &yx_coordinate_pairs)
+        let output = cli.out_dir.join(&filename);
+        let snapshots = Snapshots {
+            at: &break_at,
+            output: &output,
+            options: &image_options,
+            overwrite: cli.overwrite,
+        };
+        simulation_in_background(
+            grid,
+            &mut rng,
+            &yx_coordinate_pairs,
+            Some(&snapshots),
+            if cli.accessible {
+                Progress::Lines
+            } else {
+                Progress::Bar
+            },
+            cli.preview_every,
+            timelapse.as_mut(),
+        )
     };
 
     // Print results
-    println!("Finished in {:?}", now.elapsed());
-    save_results(final_grid);
-}
+    let runtime = now.elapsed();
+    let elapsed = format!("{runtime:?}");
+    status!("{}", t!("run.finished", elapsed = elapsed));
+
+    if let Some(timelapse) = &mut timelapse {
+        timelapse.capture_now(&final_grid);
+    }
+    if let (Some(path), Some(timelapse)) = (&cli.export_video, &mut timelapse) {
+        match timelapse.finish_video() {
+            Some(Ok(())) => status!(
+                "{}",
+                t!(
+                    "run.video_saved",
+                    count = timelapse.len(),
+                    path = path.display()
+                )
+            ),
+            Some(Err(e)) => eprintln!("{}", t!("run.video_failed", error = e)),
+            None => {}
+        }
+    }
+    if let (Some(dir), Some(timelapse)) = (&cli.frames_dir, &mut timelapse) {
+        match timelapse.finish_frame_dir() {
+            Some(Ok(())) => status!(
+                "{}",
+                t!(
+                    "run.frames_saved",
+                    count = timelapse.len(),
+                    dir = dir.display()
+                )
+            ),
+            Some(Err(e)) => eprintln!("{}", t!("run.frames_failed", error = e)),
+            None => {}
+        }
+    }
+    if let (Some(path), Some(timelapse)) = (&cli.frames_archive, &mut timelapse) {
+        match timelapse.finish_archive() {
+            Some(Ok(())) => status!(
+                "{}",
+                t!(
+                    "run.archive_saved",
+                    count = timelapse.len(),
+                    path = path.display()
+                )
+            ),
+            Some(Err(e)) => eprintln!("{}", t!("run.archive_failed", error = e)),
+            None => {}
+        }
+    }
+    if let Some(Err(e)) = timelapse.as_mut().and_then(Timelapse::finish_stdout) {
+        eprintln!("{}", t!("run.stdout_failed", error = e));
+    }
+    if let (Some(path), Some(timelapse)) =
+        (&cli.timelapse, timelapse.as_ref().filter(|_| keep_frames))
+    {
+        let frame_time = Duration::from_secs_f64(1.0 / config.framerate as f64);
+        match timelapse.save(path, frame_time) {
+            Ok(()) => status!(
+                "{}",
+                t!(
+                    "run.timelapse_saved",
+                    count = timelapse.len(),
+                    path = path.display()
+                )
+            ),
+            Err(e) => eprintln!("{}", t!("run.timelapse_failed", error = e)),
+        }
+    }
 
-fn save_results(grid: Grid) {
-    // Show the final result in the terminal if desired
-    if confirm_skippable("Preview final image in terminal?", false) {
-        grid.show();
+    if let Some(path) = &cli.save_state {
+        let path_text = path.display();
+        match state::save(&final_grid, &config, path) {
+            Ok(()) => status!("{}", t!("run.state_saved", path = path_text)),
+            Err(e) => eprintln!("{}", t!("run.state_save_failed", error = e)),
+        }
     }
 
-    // Save final result as an image if desired
-    if confirm_skippable("Save final frame as an image?", false) {
-        let filename = Text::new("Enter a filename for your picture")
-            .prompt()
-            .unwrap_or("image.png".to_string());
-
-        let img_timer = Instant::now();
-        // save the result as an image using the `image` crate
-        let img = image::ImageBuffer::from_fn(
-            grid.width.try_into().unwrap(),
-            grid.height.try_into().unwrap(),
-            |y, x| {
-                let y: usize = y.try_into().unwrap();
-                let x: usize = x.try_into().unwrap();
-                image::Rgb(grid.get_color(y, x).as_slice())
-            },
+    if let Some(path) = &cli.export_palette {
+        let colors = swatches::representative_colors(&final_grid, cli.palette_colors.into());
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Spreading colors");
+        match swatches::write(&colors, name, path) {
+            Ok(()) => status!(
+                "{}",
+                t!(
+                    "run.palette_saved",
+                    count = colors.len(),
+                    path = path.display()
+                )
+            ),
+            Err(e) => eprintln!("{}", t!("run.palette_failed", error = e)),
+        }
+    }
+
+    // Interactive runs are done once the simulation finishes, since the prompts come next
+    if cli.notify && !cli.yes {
+        notify::notify(
+            &t!("notification.finished"),
+            &t!("notification.waiting", elapsed = elapsed),
         );
-        if let Err(e) = img.save(&format!("output_images/{filename}")) {
-            println!("Sorry, the file wasn't able to because of this error -> {e:?}")
-        } else {
-            println!(
-                "Finished generating and saving image in {:?}",
-                img_timer.elapsed()
-            );
-            println!("{filename} was saved in the output_images directory");
+    }
+    let generations = final_grid.generation;
+    let saved = save_results(final_grid, &config, cli, &image_options, &filename);
+    if let Some(path) = &saved {
+        save_manifest(
+            path,
+            &config,
+            generations,
+            runtime,
+            randomized(cli),
+            cli.overwrite,
+            !cli.yes,
+        );
+        if cli.set_wallpaper {
+            set_wallpaper(path);
+        }
+    }
+    if cli.notify && cli.yes {
+        match saved {
+            Some(path) => notify::notify(
+                &t!("notification.finished"),
+                &t!(
+                    "notification.saved",
+                    elapsed = elapsed,
+                    path = path.display()
+                ),
+            ),
+            None => notify::notify(&t!("notification.failed"), &t!("notification.not_saved")),
         }
     }
 }
 
-// Runs the simulation without visualizing it in the terminal.
-// This is faster, and helpful if you only want the final output image.
-// Returns the final state of the grid in-case the user wants to save it as an image.
-fn simulation_in_background(mut grid: Grid, yx_coordinate_pairs: &Vec<[usize; 2]>) -> Grid {
-    let mut rng = thread_rng(); // random number generator
+// Prints the settings a run would use once defaults, the config file, env vars and flags
+// are all resolved, along with estimates of how long it runs and how much memory and disk it takes
+fn print_dry_run(config: &SimulationConfig, cli: &RunArgs, batch_size: usize) {
+    println!("{}", t!("dry_run.settings"));
+    // The same format config files use, so the output can be saved and reused as one
+    print!("{}", toml::to_string(config).unwrap());
+    if config.seed.is_none() {
+        println!("{}", t!("dry_run.random_seed"));
+    }
+
+    let filename = match (&cli.output, config.seed) {
+        (Some(output), _) => output.clone(),
+        (None, Some(seed)) => descriptive_filename(config, seed, cli.format.extension()),
+        (None, None) => descriptive_filename(config, "<random>", cli.format.extension()),
+    };
+    let output = cli.out_dir.join(filename);
+    println!();
+    if batch_size > 1 {
+        let dir = output.parent().unwrap_or(&cli.out_dir);
+        println!(
+            "{}",
+            t!("dry_run.batch", count = batch_size, dir = dir.display())
+        );
+    } else if config.show_while_running {
+        println!("{}", t!("dry_run.animate"));
+    } else {
+        println!("{}", t!("dry_run.background", path = output.display()));
+    }
+    if !cli.break_at.is_empty() {
+        let generations: Vec<String> = cli.break_at.iter().map(u64::to_string).collect();
+        println!(
+            "{}",
+            t!("dry_run.breakpoints", generations = generations.join(", "))
+        );
+    }
+
+    match expected_generations(config) {
+        Some(generations) => println!("{}", t!("dry_run.length", generations = generations)),
+        None => println!("{}", t!("dry_run.endless")),
+    }
+    let memory = format_bytes(memory_bytes(config, cli.history_size));
+    println!("{}", t!("dry_run.memory", size = memory));
+    let image_size = format_bytes(image_bytes(config, cli.scale));
+    println!("{}", t!("dry_run.image_size", size = image_size));
+    let capabilities = terminal::renderer().capabilities();
+    println!("{}", t!("dry_run.terminal", capabilities = capabilities));
+}
+
+// Prints the settings --randomize picked for a run
+fn print_randomized(config: &SimulationConfig) {
+    status!(
+        "{}",
+        t!(
+            "run.randomized",
+            colorshift = config.colorshift,
+            spread_chance = config.spread_chance,
+            starting_live_cells = config.starting_live_cells,
+            palette = config.current_palette()
+        )
+    );
+}
 
-    // Only show the resulting art after its finished rendering (much faster!)
-    println!("Running in background");
+// The settings recorded as picked at random in the manifests of this run's images
+fn randomized(cli: &RunArgs) -> &'static [&'static str] {
+    if cli.randomize {
+        &RANDOMIZED_SETTINGS
+    } else {
+        &[]
+    }
+}
 
-    loop {
-        let mut seen_dead_cell = false;
-
-        for [y, x] in yx_coordinate_pairs {
-            let y = *y;
-            let x = *x;
-            if grid.alive_states[[y, x]] {
-                // println!("{} @ {y},{x} is ALIVE", grid.get_cell_on_its_color(y, x));
-                grid.spread_to_random_dead_nbor(y, x, &mut rng);
-            } else {
-                seen_dead_cell = true;
+// Names the `index`th image of a batch, e.g. "image.png" -> "image_003_seed1234.png"
+fn batch_path(output: &Path, index: u32, seed: u64) -> PathBuf {
+    with_filename_suffix(output, &format!("_{index:03}_seed{seed}"))
+}
+
+// Renders an image for each of `configs` in the background without any prompts, each with its
+// own seed. When a seed is given, the batch uses it and the seeds right after it, so it can be
+// reproduced.
+fn run_batch(configs: &[SimulationConfig], cli: &RunArgs, image_options: &ImageOptions) {
+    let batch_timer = Instant::now();
+    let count = configs.len();
+    let first_seed = configs[0].seed.unwrap_or_else(|| thread_rng().gen());
+    let mut failures = 0;
+    // The first image is the primary monitor's, when there's one per monitor
+    let mut wallpaper = None;
+    let mut contact_sheet = ContactSheet::default();
+    for (index, config) in (1..).zip(configs) {
+        let seed = first_seed.wrapping_add(u64::from(index - 1));
+        status!(
+            "{}",
+            t!("run.batch_image", index = index, count = count, seed = seed)
+        );
+
+        if cli.randomize {
+            print_randomized(config);
+        }
+
+        let config = SimulationConfig {
+            seed: Some(seed),
+            ..config.clone()
+        };
+        // Descriptive names already include the seed, so only chosen names need numbering
+        let path = match &cli.output {
+            Some(output) => batch_path(&cli.out_dir.join(output), index, seed),
+            None => cli
+                .out_dir
+                .join(descriptive_filename(&config, seed, cli.format.extension())),
+        };
+        let path = image_options.settings_path(&path, &config);
+        if image_options.already_saved(&path) {
+            continue;
+        }
+
+        let timer = Instant::now();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let grid = render(&config, &mut rng, image_options.needs_births());
+        let runtime = timer.elapsed();
+        contact_sheet.add(&grid, index, &config);
+        match save_image(&grid, &path, image_options, cli.overwrite, false) {
+            Some(path) => {
+                save_manifest(
+                    &path,
+                    &config,
+                    grid.generation,
+                    runtime,
+                    randomized(cli),
+                    cli.overwrite,
+                    false,
+                );
+                if index == 1 {
+                    wallpaper = Some(path);
+                }
             }
+            None => failures += 1,
         }
-        if !seen_dead_cell {
-            return grid;
+    }
+    if let Some(path) = wallpaper.filter(|_| cli.set_wallpaper) {
+        set_wallpaper(&path);
+    }
+    if !cli.no_contact_sheet && !contact_sheet.is_empty() {
+        let path = match &cli.output {
+            Some(output) => with_filename_suffix(&cli.out_dir.join(output), "_contact_sheet"),
+            None => cli.out_dir.join(format!(
+                "{}_contact_sheet",
+                Local::now().format("%Y-%m-%d_%H%M")
+            )),
+        };
+        let path = path.with_extension("png");
+        match contact_sheet.save(&path) {
+            Ok(()) => status!("{}", t!("run.contact_sheet_saved", path = path.display())),
+            Err(e) => eprintln!("{}", t!("run.contact_sheet_failed", error = e)),
         }
     }
+
+    let elapsed = format!("{:?}", batch_timer.elapsed());
+    status!(
+        "{}",
+        t!("run.batch_finished", count = count, elapsed = elapsed)
+    );
+    if cli.notify {
+        let dir = cli.out_dir.display();
+        if failures == 0 {
+            notify::notify(
+                &t!("notification.batch_finished"),
+                &t!(
+                    "notification.batch_saved",
+                    count = count,
+                    dir = dir,
+                    elapsed = elapsed
+                ),
+            );
+        } else {
+            notify::notify(
+                &t!("notification.batch_failed"),
+                &t!(
+                    "notification.batch_failures",
+                    failures = failures,
+                    count = count,
+                    dir = dir
+                ),
+            );
+        }
+    }
+}
+
+// Sets the image at `path` as the desktop background, saying whether it worked
+fn set_wallpaper(path: &Path) {
+    match wallpaper::set(path) {
+        Ok(()) => status!("{}", t!("run.wallpaper_set", path = path.display())),
+        Err(error) => eprintln!("{}", t!("run.wallpaper_failed", error = error)),
+    }
 }
 
-// fn save_vec_as_image(v: &Vec<Vec<[u8; 3]>>, filename: &str) {
-//     let height = v.len();
-//     let width = v[0].len();
-
-//     // Try using the Image crate to save the image at the desired location
-//     let img = image::ImageBuffer::from_fn(
-//         width.try_into().unwrap(),
-//         height.try_into().unwrap(),
-//         |y, x| {
-//             let y: usize = y.try_into().unwrap();
-//             let x: usize = x.try_into().unwrap();
-//             image::Rgb(v[y][x])
-//         },
-//     );
-
-//     // Print whether saving the image succeeded or not.
-//     if let Err(e) = img.save(&format!("output_images/{filename}")) {
-//         println!("Sorry, the file wasn't able to because of this error -> {e:?}")
-//     } else {
-//         println!("{filename} was saved in the output_images directory")
-//     }
-// }
+// Previews and saves the final grid (simulated from `config`) as the user wishes, returning where
+// it was saved (if it was). `filename` is used when the user doesn't enter one: the one given with
+// --output, or a descriptive one.
+fn save_results(
+    grid: Grid,
+    config: &SimulationConfig,
+    cli: &RunArgs,
+    image_options: &ImageOptions,
+    filename: &str,
+) -> Option<PathBuf> {
+    // In non-interactive mode the image is always saved, and never previewed
+    if cli.yes {
+        return save_image(
+            &grid,
+            &cli.out_dir.join(filename),
+            image_options,
+            cli.overwrite,
+            false,
+        );
+    }
+
+    // Show the final result in the terminal if desired. The preview is nothing but colors,
+    // so it isn't offered in accessible mode.
+    // It's shown in the full-screen interface if the terminal supports it.
+    // Wrapping grids are shown tiled, so any seams between the copies stand out.
+    let wraps = config.boundary == Boundary::Wrap;
+    let question = if wraps {
+        t!("prompt.preview_tiled", times = TILED_PREVIEW)
+    } else {
+        t!("prompt.preview")
+    };
+    let preview = !cli.accessible && confirm_skippable(&question, false);
+    let tiled = (preview && wraps).then(|| grid.tiled(TILED_PREVIEW));
+    let shown = tiled.as_ref().unwrap_or(&grid);
+    if preview && (!tui::supported() || tui::preview(shown, config).is_err()) {
+        // Grids bigger than the terminal are shrunk to fit it, leaving room for the next prompt
+        let small = terminal::screen_size().and_then(|(columns, rows)| {
+            let area = (columns, rows.saturating_sub(PREVIEW_PROMPT_LINES));
+            viewport::shrink_to_fit(shown, terminal::renderer(), area)
+        });
+        small.as_ref().unwrap_or(shown).show();
+    }
+
+    // Save final result as an image if desired
+    if confirm_skippable(&t!("prompt.save_image"), false) {
+        let filename = match &cli.output {
+            Some(output) => output.clone(),
+            None => Text::new(&t!("prompt.filename"))
+                .with_default(filename)
+                .prompt()
+                .unwrap_or(filename.to_string()),
+        };
+        let filename = match cli.format.filename(&filename) {
+            Ok(filename) => filename,
+            Err(error) => {
+                eprintln!("{}", t!("export.invalid_name", error = error));
+                return None;
+            }
+        };
+        return save_image(
+            &grid,
+            &cli.out_dir.join(filename),
+            image_options,
+            cli.overwrite,
+            true,
+        );
+    }
+    None
+}
@@ -0,0 +1,132 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use crate::{
+    config::SimulationConfig,
+    export::{resolve_overwrite, Overwrite},
+    i18n::t,
+    verbosity::verbose,
+};
+
+/// Everything needed to reproduce or analyze a rendered image, written beside it as JSON
+#[derive(Debug, Serialize)]
+pub struct RunManifest<'a> {
+    pub crate_version: &'static str,
+    pub image: &'a Path,
+    /// The settings the image was rendered with, including the seed that was used
+    pub config: &'a SimulationConfig,
+    pub generations: u64,
+    pub runtime_secs: f64,
+    /// Name of the job this image was rendered for, when rendered by a worker
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job: Option<&'a str>,
+    /// Which worker rendered the image, when rendered by a worker
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub worker: Option<&'a str>,
+    /// The settings that were picked at random with --randomize
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub randomized: &'a [&'a str],
+}
+
+impl RunManifest<'_> {
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)
+    }
+}
+
+/// Where the manifest of an image saved at `image` goes: beside it, named after its whole file
+/// name (image.png -> image.png.manifest.json), so it can never take the place of another export
+pub fn manifest_path(image: &Path) -> PathBuf {
+    let mut name = image.file_name().unwrap_or_default().to_os_string();
+    name.push(".manifest.json");
+    image.with_file_name(name)
+}
+
+/// Writes the manifest of an image saved at `image` beside it, see `manifest_path`,
+/// so the image can be reproduced or analyzed later. Failing to is reported, but isn't fatal.
+/// `config` has to include the seed that was used. A taken manifest name is handled like a
+/// taken image name, as `overwrite` says.
+pub fn save_manifest(
+    image: &Path,
+    config: &SimulationConfig,
+    generations: u64,
+    runtime: Duration,
+    randomized: &[&str],
+    overwrite: Overwrite,
+    interactive: bool,
+) {
+    let path = resolve_overwrite(&manifest_path(image), overwrite, interactive);
+    let manifest = RunManifest {
+        crate_version: env!("CARGO_PKG_VERSION"),
+        image,
+        config,
+        generations,
+        runtime_secs: runtime.as_secs_f64(),
+        job: None,
+        worker: None,
+        randomized,
+    };
+    match manifest.write(&path) {
+        Ok(()) => verbose!("{}", t!("export.manifest_saved", path = path.display())),
+        Err(e) => eprintln!("{}", t!("export.manifest_failed", error = e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        export::{save_image, ImageOptions},
+        testing::{grown_grid, temp_path},
+    };
+
+    #[test]
+    fn manifests_leave_exports_with_the_same_stem_alone() {
+        let config = SimulationConfig {
+            width: 8,
+            height: 6,
+            ..SimulationConfig::default()
+        };
+        let grid = grown_grid(&config, 3);
+        let dir = temp_path("manifests");
+        fs::create_dir_all(&dir).unwrap();
+        let options = ImageOptions::default();
+        for name in ["g.json", "g.png"] {
+            let image = save_image(
+                &grid,
+                &dir.join(name),
+                &options,
+                Overwrite::Increment,
+                false,
+            )
+            .expect("the image is saved");
+            assert_eq!(image, dir.join(name));
+            let runtime = Duration::ZERO;
+            save_manifest(
+                &image,
+                &config,
+                3,
+                runtime,
+                &[],
+                Overwrite::Increment,
+                false,
+            );
+        }
+
+        let read = |name: &str| -> serde_json::Value {
+            serde_json::from_slice(&fs::read(dir.join(name)).unwrap()).unwrap()
+        };
+        assert_eq!(read("g.json")["width"], 8, "the grid export is still there");
+        for image in ["g.json", "g.png"] {
+            let manifest = read(&format!("{image}.manifest.json"));
+            assert_eq!(manifest["image"], dir.join(image).to_str().unwrap());
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+// Authorship metadata embedded in exported images, so generated art carries its attribution when
+// it's shared: who made it, its title, its copyright or license notice and, if asked for, the
+// settings it was made with. It's written as XMP, which most photo apps and sites read, and also
+// as EXIF and PNG text for the ones that only read those. Only PNG and JPEG exports carry it.
+
+use clap::Args;
+
+// The program named as the images' creator
+/// What images are said to be made with
+pub const SOFTWARE: &str = concat!("spreading_colors_ca ", env!("CARGO_PKG_VERSION"));
+
+// EXIF tags for each field, in the ascending order they have to be written in
+const EXIF_IMAGE_DESCRIPTION: u16 = 0x010E;
+const EXIF_SOFTWARE: u16 = 0x0131;
+const EXIF_ARTIST: u16 = 0x013B;
+const EXIF_COPYRIGHT: u16 = 0x8298;
+// EXIF's type for NUL-terminated text
+const EXIF_ASCII: u16 = 2;
+
+/// Who made exported images and under what terms, embedded in PNG and JPEG images
+#[derive(Debug, Clone, Default, Args)]
+pub struct Metadata {
+    /// Name of the artist, embedded in saved PNG and JPEG images
+    #[arg(long, env = "SPREADING_COLORS_AUTHOR")]
+    pub author: Option<String>,
+
+    /// Title of the artwork, embedded in saved PNG and JPEG images
+    #[arg(long, env = "SPREADING_COLORS_TITLE")]
+    pub title: Option<String>,
+
+    /// Copyright or license notice embedded in saved PNG and JPEG images,
+    /// e.g. "© 2024 Jane Doe" or "CC BY 4.0"
+    #[arg(long, env = "SPREADING_COLORS_LICENSE")]
+    pub license: Option<String>,
+
+    /// Embed the settings each image was made with in it too, including the seed,
+    /// so whoever has the image can make it again
+    #[arg(long, env = "SPREADING_COLORS_EMBED_SETTINGS")]
+    pub embed_settings: bool,
+}
+
+impl Metadata {
+    // Whether there's anything besides the settings to embed
+    fn has_authorship(&self) -> bool {
+        self.author.is_some() || self.title.is_some() || self.license.is_some()
+    }
+
+    /// An XMP packet holding the metadata and an image's `settings` (when they're embedded),
+    /// or None if there's nothing to embed
+    pub fn xmp(&self, settings: Option<&str>) -> Option<String> {
+        if !self.has_authorship() && settings.is_none() {
+            return None;
+        }
+        let mut properties = format!(
+            "   <xmp:CreatorTool>{}</xmp:CreatorTool>\n",
+            escape_xml(SOFTWARE)
+        );
+        if let Some(author) = &self.author {
+            properties += &format!(
+                "   <dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>\n",
+                escape_xml(author)
+            );
+        }
+        let alternatives = [
+            ("title", self.title.as_deref()),
+            ("rights", self.license.as_deref()),
+            ("description", settings),
+        ];
+        for (property, text) in alternatives {
+            if let Some(text) = text {
+                properties += &format!(
+                    "   <dc:{property}><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:{property}>\n",
+                    escape_xml(text)
+                );
+            }
+        }
+        Some(format!(
+            "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\n\
+             <x:xmpmeta xmlns:x=\"adobe:ns:meta/\">\n \
+             <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\n  \
+             <rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" \
+             xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\">\n\
+             {properties}  \
+             </rdf:Description>\n \
+             </rdf:RDF>\n\
+             </x:xmpmeta>\n\
+             <?xpacket end=\"r\"?>"
+        ))
+    }
+
+    /// The metadata as EXIF (a TIFF header and one directory, without the "Exif" marker JPEG
+    /// puts before it), or None if no authorship was given. EXIF has nowhere fitting for the
+    /// settings, so they're left to the XMP.
+    pub fn exif(&self) -> Option<Vec<u8>> {
+        if !self.has_authorship() {
+            return None;
+        }
+        let fields: Vec<(u16, &str)> = [
+            (EXIF_IMAGE_DESCRIPTION, self.title.as_deref()),
+            (EXIF_SOFTWARE, Some(SOFTWARE)),
+            (EXIF_ARTIST, self.author.as_deref()),
+            (EXIF_COPYRIGHT, self.license.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(tag, text)| Some((tag, text?)))
+        .collect();
+
+        // Big-endian TIFF header pointing at the directory right after it. Text longer than the
+        // 4 bytes an entry has room for goes after the directory, with the entry pointing at it.
+        let mut exif = b"MM\0\x2A\0\0\0\x08".to_vec();
+        let directory_size = 2 + 12 * fields.len() + 4;
+        let mut data = Vec::new();
+        exif.extend((fields.len() as u16).to_be_bytes());
+        for (tag, text) in fields {
+            let mut bytes = text.as_bytes().to_vec();
+            bytes.push(0);
+            exif.extend(tag.to_be_bytes());
+            exif.extend(EXIF_ASCII.to_be_bytes());
+            exif.extend((bytes.len() as u32).to_be_bytes());
+            if bytes.len() <= 4 {
+                bytes.resize(4, 0);
+                exif.extend(bytes);
+            } else {
+                let offset = 8 + directory_size + data.len();
+                exif.extend((offset as u32).to_be_bytes());
+                data.extend(bytes);
+            }
+        }
+        // No directory follows this one
+        exif.extend([0; 4]);
+        exif.extend(data);
+        Some(exif)
+    }
+
+    /// The metadata as PNG text chunks, as (keyword, text) with the keywords the PNG spec
+    /// defines, including an image's `settings` when they're embedded
+    pub fn png_text(&self, settings: Option<&str>) -> Vec<(&'static str, String)> {
+        if !self.has_authorship() && settings.is_none() {
+            return Vec::new();
+        }
+        [
+            ("Title", self.title.as_deref()),
+            ("Author", self.author.as_deref()),
+            ("Copyright", self.license.as_deref()),
+            ("Description", settings),
+            ("Software", Some(SOFTWARE)),
+        ]
+        .into_iter()
+        .filter_map(|(keyword, text)| Some((keyword, text?.to_string())))
+        .collect()
+    }
+}
+
+// `text` with the characters XML gives a meaning to escaped
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
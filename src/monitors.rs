@@ -0,0 +1,93 @@
+// Monitor geometry, so wallpapers can be rendered at exactly the size of the user's screens:
+// either one image spanning every monitor as they're arranged, or one image per monitor.
+// Rotated (portrait) monitors report their rotated size, so they get portrait images.
+
+use clap::ValueEnum;
+use display_info::DisplayInfo;
+
+use crate::config::SimulationConfig;
+
+/// How images are fitted to the monitors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MonitorLayout {
+    /// One image covering every monitor as they're arranged, to be spanned across them
+    Span,
+    /// One image per monitor at its own resolution, with consecutive seeds
+    Each,
+}
+
+/// Where a monitor sits on the desktop and how big it is, in physical pixels
+#[derive(Debug, Clone, Copy)]
+pub struct Monitor {
+    pub x: i64,
+    pub y: i64,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Monitor {
+    /// `config` resized to fill this monitor
+    pub fn fit(&self, config: &SimulationConfig) -> SimulationConfig {
+        SimulationConfig {
+            width: self.width,
+            height: self.height,
+            ..config.clone()
+        }
+    }
+}
+
+/// Every connected monitor, with the primary one first
+pub fn detect() -> Result<Vec<Monitor>, String> {
+    let mut displays = DisplayInfo::all().map_err(|e| e.to_string())?;
+    if displays.is_empty() {
+        return Err("no monitors were found".to_string());
+    }
+    displays.sort_by_key(|display| !display.is_primary);
+
+    // Positions and sizes are in logical pixels on scaled (e.g. HiDPI) displays
+    let physical = |pixels: f64, scale: f32| (pixels * f64::from(scale)).round();
+    Ok(displays
+        .iter()
+        .map(|display| Monitor {
+            x: physical(f64::from(display.x), display.scale_factor) as i64,
+            y: physical(f64::from(display.y), display.scale_factor) as i64,
+            width: physical(f64::from(display.width), display.scale_factor) as usize,
+            height: physical(f64::from(display.height), display.scale_factor) as usize,
+        })
+        .collect())
+}
+
+/// The width and height of the smallest image covering every monitor, if there are any
+pub fn spanning_size(monitors: &[Monitor]) -> Option<(usize, usize)> {
+    let left = monitors.iter().map(|m| m.x).min()?;
+    let top = monitors.iter().map(|m| m.y).min()?;
+    let right = monitors.iter().map(|m| m.x + m.width as i64).max()?;
+    let bottom = monitors.iter().map(|m| m.y + m.height as i64).max()?;
+    Some(((right - left) as usize, (bottom - top) as usize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spanning_covers_every_monitor() {
+        let monitors = [
+            Monitor {
+                x: 0,
+                y: 0,
+                width: 1920,
+                height: 1080,
+            },
+            // A portrait monitor to the left, hanging lower
+            Monitor {
+                x: -1080,
+                y: 200,
+                width: 1080,
+                height: 1920,
+            },
+        ];
+        assert_eq!(spanning_size(&monitors), Some((3000, 2120)));
+        assert_eq!(spanning_size(&[]), None);
+    }
+}
@@ -0,0 +1,38 @@
+// Desktop notifications for long runs, so nobody has to babysit the terminal.
+// Builds without the `notify` feature just report the notification in the terminal.
+
+use std::panic;
+
+use crate::{i18n::t, verbosity::status};
+
+/// Shows a desktop notification, falling back to the terminal if that isn't possible
+pub fn notify(summary: &str, body: &str) {
+    #[cfg(feature = "notify")]
+    {
+        let shown = notify_rust::Notification::new()
+            .appname("spreading_colors_ca")
+            .summary(summary)
+            .body(body)
+            .show();
+        if let Err(e) = shown {
+            let error = format!("{e:?}");
+            status!("{}", t!("notification.show_failed", error = error));
+            status!("{summary}: {body}");
+        }
+    }
+
+    #[cfg(not(feature = "notify"))]
+    status!(
+        "{}",
+        t!("notification.unavailable", summary = summary, body = body)
+    );
+}
+
+/// Makes a panic show a desktop notification too, so failed runs don't go unnoticed
+pub fn notify_on_panic() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        notify(&t!("notification.failed"), &info.to_string());
+    }));
+}
@@ -0,0 +1,220 @@
+// Color palettes for the starting cells, so the colors an image grows from can suit a mood or
+// the time of day instead of being completely random. Children still drift away from their
+// parent's color, so a palette only steers where each colony starts.
+//
+// Themes pick the background (the color of dead cells) and the palette used when the settings
+// don't name one, following the system's dark or light mode unless told otherwise.
+
+use std::fmt::{self, Display, Formatter};
+
+use chrono::{Local, Timelike};
+use clap::ValueEnum;
+use rand::{rngs::StdRng, seq::IteratorRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::grid::RgbColor;
+
+/// How far the starting colors can stray from the palette's colors, so colonies don't all
+/// start out in exactly the same few colors
+pub const PALETTE_JITTER: u8 = 24;
+
+/// Which colors the starting cells are picked from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorPalette {
+    /// Any color at all (the original behavior)
+    #[default]
+    Random,
+    /// Picks one of the other palettes by the time of day, following the palette schedule
+    Auto,
+    /// Soft peach, coral and gold
+    Sunrise,
+    /// Bright, saturated primaries
+    Day,
+    /// Deep reds, oranges and purples
+    Sunset,
+    /// Dark blues and violets
+    Night,
+}
+
+/// Whether images are drawn on a dark or a light background
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// Follows the system's dark or light mode, and is dark if it can't be detected
+    #[default]
+    Auto,
+    /// Black background, any starting colors
+    Dark,
+    /// White background, bright starting colors
+    Light,
+}
+
+impl Theme {
+    /// The theme to use: `auto` becomes the system's dark or light mode, any other theme stays the same
+    pub fn resolve(self) -> Self {
+        match self {
+            Theme::Auto => match dark_light::detect() {
+                dark_light::Mode::Light => Theme::Light,
+                dark_light::Mode::Dark | dark_light::Mode::Default => Theme::Dark,
+            },
+            theme => theme,
+        }
+    }
+
+    /// Color of the cells that aren't alive
+    pub fn background(self) -> RgbColor {
+        match self.resolve() {
+            Theme::Light => rgb(255, 255, 255),
+            _ => rgb(0, 0, 0),
+        }
+    }
+
+    /// The palette starting cells are picked from when the settings don't name one
+    pub fn default_palette(self) -> ColorPalette {
+        match self.resolve() {
+            Theme::Light => ColorPalette::Day,
+            _ => ColorPalette::Random,
+        }
+    }
+}
+
+impl Display for Theme {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Theme::Auto => "auto",
+            Theme::Dark => "dark",
+            Theme::Light => "light",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// From `from` (a local time like "21:30") until the next entry, runs use `palette`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleEntry {
+    pub from: String,
+    pub palette: ColorPalette,
+}
+
+// The schedule `auto` follows unless the config has its own `palette_schedule`
+const DEFAULT_SCHEDULE: [(&str, ColorPalette); 4] = [
+    ("05:00", ColorPalette::Sunrise),
+    ("09:00", ColorPalette::Day),
+    ("17:00", ColorPalette::Sunset),
+    ("21:00", ColorPalette::Night),
+];
+
+const fn rgb(red: u8, green: u8, blue: u8) -> RgbColor {
+    RgbColor { red, green, blue }
+}
+
+// Soft peach, coral and gold
+const SUNRISE: [RgbColor; 5] = [
+    rgb(255, 183, 94),
+    rgb(255, 138, 101),
+    rgb(255, 214, 165),
+    rgb(247, 111, 142),
+    rgb(255, 200, 87),
+];
+
+// Bright, saturated primaries
+const DAY: [RgbColor; 5] = [
+    rgb(66, 165, 245),
+    rgb(102, 187, 106),
+    rgb(255, 238, 88),
+    rgb(38, 198, 218),
+    rgb(236, 64, 122),
+];
+
+// Deep reds, oranges and purples
+const SUNSET: [RgbColor; 5] = [
+    rgb(255, 94, 77),
+    rgb(214, 40, 57),
+    rgb(155, 89, 182),
+    rgb(255, 140, 0),
+    rgb(108, 52, 131),
+];
+
+// Dark blues and violets
+const NIGHT: [RgbColor; 5] = [
+    rgb(13, 27, 62),
+    rgb(27, 38, 79),
+    rgb(44, 62, 80),
+    rgb(72, 52, 112),
+    rgb(20, 60, 70),
+];
+
+impl ColorPalette {
+    /// The colors starting cells are picked from, or nothing if they can be any color
+    pub fn colors(self) -> Option<&'static [RgbColor]> {
+        match self {
+            ColorPalette::Random | ColorPalette::Auto => None,
+            ColorPalette::Sunrise => Some(&SUNRISE),
+            ColorPalette::Day => Some(&DAY),
+            ColorPalette::Sunset => Some(&SUNSET),
+            ColorPalette::Night => Some(&NIGHT),
+        }
+    }
+
+    /// A color for a cell appearing out of nowhere: one of the palette's colors, strayed from by
+    /// up to `jitter`, or any color at all if it has none
+    pub fn orphan_color(self, jitter: u8, rng: &mut StdRng) -> RgbColor {
+        match self.colors() {
+            Some(colors) => colors.iter().choose(rng).unwrap().shift_color(jitter, rng),
+            None => RgbColor::random(rng),
+        }
+    }
+
+    /// The palette to use right now: `auto` becomes whichever palette `schedule` (or the default
+    /// schedule) picks for the current local time, any other palette stays the same
+    pub fn resolve(self, schedule: Option<&[ScheduleEntry]>) -> Self {
+        if self != ColorPalette::Auto {
+            return self;
+        }
+        let now = Local::now();
+        let minute = now.hour() * 60 + now.minute();
+        let entries: Vec<(u32, ColorPalette)> = match schedule {
+            Some(schedule) => schedule
+                .iter()
+                .filter_map(|entry| Some((parse_time(&entry.from)?, entry.palette)))
+                .collect(),
+            None => DEFAULT_SCHEDULE
+                .iter()
+                .map(|(from, palette)| (parse_time(from).unwrap(), *palette))
+                .collect(),
+        };
+
+        // Before the first entry of the day, the last entry of the previous day still applies
+        let latest_started = entries
+            .iter()
+            .filter(|(from, _)| *from <= minute)
+            .max_by_key(|(from, _)| *from);
+        latest_started
+            .or(entries.iter().max_by_key(|(from, _)| *from))
+            .map_or(ColorPalette::Random, |(_, palette)| *palette)
+    }
+}
+
+impl Display for ColorPalette {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColorPalette::Random => "random",
+            ColorPalette::Auto => "auto",
+            ColorPalette::Sunrise => "sunrise",
+            ColorPalette::Day => "day",
+            ColorPalette::Sunset => "sunset",
+            ColorPalette::Night => "night",
+        };
+        write!(f, "{name}")
+    }
+}
+
+/// Minutes since midnight of a time like "06:30", or nothing if it isn't a valid time
+pub fn parse_time(time: &str) -> Option<u32> {
+    let (hour, minute) = time.trim().split_once(':')?;
+    let hour: u32 = hour.parse().ok()?;
+    let minute: u32 = minute.parse().ok()?;
+    (hour < 24 && minute < 60).then_some(hour * 60 + minute)
+}
@@ -0,0 +1,167 @@
+// Rules from plugins: shared libraries in `<config dir>/spreading_colors_ca/plugins` that add
+// growth rules without rebuilding the program. They're picked up at startup and show up in
+// `rules list` and the animation's rule cycle. Loading them needs the `plugins` feature.
+//
+// The interface is plain C, so plugins can be written in any language and don't need to be built
+// with the same compiler as the program. A plugin exports
+//
+//     const PluginDeclaration *spreading_colors_plugin_v1(void);
+//
+// returning a declaration that lives as long as the library, whose rules each have a name, a
+// factor for the chance to spread, and a function deriving a child's color (as 0xRRGGBB) from its
+// parent's color, the colorshift and a random number. Taking the random number as an argument
+// keeps runs using plugin rules reproducible from their seed.
+
+use std::{ffi::c_char, path::PathBuf, sync::OnceLock};
+
+use crate::grid::RgbColor;
+
+/// Version of the plugin interface, bumped whenever `PluginDeclaration` or `PluginRule` change
+#[cfg_attr(not(feature = "plugins"), allow(dead_code))]
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+/// What a plugin's entry point returns
+#[cfg_attr(not(feature = "plugins"), allow(dead_code))]
+#[repr(C)]
+pub struct PluginDeclaration {
+    /// Must be `PLUGIN_ABI_VERSION`, or the plugin isn't loaded
+    pub abi_version: u32,
+    pub rule_count: usize,
+    pub rules: *const PluginRule,
+}
+
+/// A rule as a plugin declares it
+#[cfg_attr(not(feature = "plugins"), allow(dead_code))]
+#[repr(C)]
+pub struct PluginRule {
+    /// Null-terminated UTF-8 name, shown in `rules list` and the animation's status line
+    pub name: *const c_char,
+    /// The grid's spread chance is multiplied by this, e.g. 0.5 to spread half as often
+    pub spread_factor: f64,
+    /// The color of a child (0xRRGGBB) whose parent has the color `parent` (also 0xRRGGBB)
+    pub child_color: extern "C" fn(parent: u32, colorshift: u8, random: u64) -> u32,
+}
+
+/// A rule loaded from a plugin, ready to be used
+#[derive(Debug)]
+pub struct LoadedRule {
+    pub name: String,
+    /// The library the rule came from
+    pub source: PathBuf,
+    spread_factor: f64,
+    child_color: extern "C" fn(u32, u8, u64) -> u32,
+}
+
+impl LoadedRule {
+    /// The chance that a live cell spreads this generation, given the grid's `spread_chance`
+    pub fn spread_chance(&self, spread_chance: f64) -> f64 {
+        (spread_chance * self.spread_factor).clamp(0.0, 1.0)
+    }
+
+    /// The color of a new cell whose parent has the color `parent`
+    pub fn child_color(&self, parent: RgbColor, colorshift: u8, random: u64) -> RgbColor {
+        let [red, green, blue] = parent.as_slice().map(u32::from);
+        let child = (self.child_color)(red << 16 | green << 8 | blue, colorshift, random);
+        RgbColor::from([(child >> 16) as u8, (child >> 8) as u8, child as u8])
+    }
+}
+
+/// Where plugins are loaded from
+pub fn plugins_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("spreading_colors_ca").join("plugins"))
+}
+
+/// Every rule the installed plugins provide, loaded on first use
+pub fn plugin_rules() -> &'static [LoadedRule] {
+    static RULES: OnceLock<Vec<LoadedRule>> = OnceLock::new();
+    RULES.get_or_init(load_plugins)
+}
+
+/// Whether this build can load plugins at all
+pub fn supported() -> bool {
+    cfg!(feature = "plugins")
+}
+
+// Loads every plugin in the plugins directory. Broken plugins are skipped with a warning,
+// so one bad library doesn't stop the program from starting.
+#[cfg(feature = "plugins")]
+fn load_plugins() -> Vec<LoadedRule> {
+    let Some(dir) = plugins_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = std::fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION)
+        })
+        .collect();
+    paths.sort();
+
+    let mut rules = Vec::new();
+    for path in paths {
+        match load_plugin(&path) {
+            Ok(plugin_rules) => rules.extend(plugin_rules),
+            Err(e) => eprintln!(
+                "{}",
+                crate::i18n::t!("rules.plugin_ignored", path = path.display(), error = e)
+            ),
+        }
+    }
+    rules
+}
+
+#[cfg(feature = "plugins")]
+fn load_plugin(path: &std::path::Path) -> Result<Vec<LoadedRule>, String> {
+    // SAFETY: loading a library runs its initializers. Plugins are code the user chose to
+    // install, which is as trusted as the program itself.
+    let library = unsafe { libloading::Library::new(path) }.map_err(|e| e.to_string())?;
+    // SAFETY: the entry point's signature is part of the plugin interface described above
+    let declaration = unsafe {
+        let entry_point = library
+            .get::<extern "C" fn() -> *const PluginDeclaration>(b"spreading_colors_plugin_v1\0")
+            .map_err(|e| e.to_string())?;
+        entry_point().as_ref().ok_or("its declaration is missing")?
+    };
+    if declaration.abi_version != PLUGIN_ABI_VERSION {
+        return Err(format!(
+            "it was built for plugin interface version {}, but this program uses version {PLUGIN_ABI_VERSION}",
+            declaration.abi_version
+        ));
+    }
+
+    let declared = if declaration.rule_count == 0 {
+        &[]
+    } else {
+        // SAFETY: the declaration promises `rule_count` rules at `rules`, living as long as the library
+        unsafe { std::slice::from_raw_parts(declaration.rules, declaration.rule_count) }
+    };
+    let mut rules = Vec::new();
+    for rule in declared {
+        if rule.name.is_null() {
+            return Err("one of its rules has no name".to_string());
+        }
+        // SAFETY: names are null-terminated strings living as long as the library
+        let name = unsafe { std::ffi::CStr::from_ptr(rule.name) }
+            .to_str()
+            .map_err(|_| "one of its rule names isn't valid UTF-8".to_string())?;
+        rules.push(LoadedRule {
+            name: name.to_string(),
+            source: path.to_path_buf(),
+            spread_factor: rule.spread_factor,
+            child_color: rule.child_color,
+        });
+    }
+
+    // The rules point into the library, so it has to stay loaded for as long as the program runs
+    std::mem::forget(library);
+    Ok(rules)
+}
+
+#[cfg(not(feature = "plugins"))]
+fn load_plugins() -> Vec<LoadedRule> {
+    Vec::new()
+}
@@ -0,0 +1,78 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::config::SimulationConfig;
+
+const PRESET_EXTENSION: &str = "toml";
+
+/// Directory where user presets are stored,
+/// e.g. `~/.config/spreading_colors_ca/presets` on Linux.
+pub fn presets_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("spreading_colors_ca").join("presets"))
+}
+
+/// Whether `name` can be used for a preset. Names are file names in the presets directory, so
+/// they can't hold path separators or `..`, which would reach outside of it.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.trim().is_empty() && !name.contains(std::path::is_separator) && !name.contains("..")
+}
+
+// Where the preset called `name` is kept, refusing names that aren't valid
+fn preset_path(dir: &Path, name: &str) -> io::Result<PathBuf> {
+    if !is_valid_name(name) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{name}' isn't a valid preset name"),
+        ));
+    }
+    Ok(dir.join(format!("{name}.{PRESET_EXTENSION}")))
+}
+
+/// Returns the names of all saved presets, sorted alphabetically.
+/// A missing presets directory simply means there are no presets yet.
+pub fn list_presets() -> Vec<String> {
+    let Some(dir) = presets_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == PRESET_EXTENSION))
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Loads the preset with the given name
+pub fn load_preset(name: &str) -> io::Result<SimulationConfig> {
+    let dir = presets_dir().ok_or_else(no_config_dir)?;
+    let contents = fs::read_to_string(preset_path(&dir, name)?)?;
+    toml::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Saves `config` as a preset, overwriting any preset with the same name.
+/// Returns the path the preset was written to.
+pub fn save_preset(name: &str, config: &SimulationConfig) -> io::Result<PathBuf> {
+    let dir = presets_dir().ok_or_else(no_config_dir)?;
+    let path = preset_path(&dir, name)?;
+    fs::create_dir_all(&dir)?;
+
+    let contents = toml::to_string_pretty(config)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+fn no_config_dir() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        "could not determine the user's config directory",
+    )
+}
@@ -0,0 +1,134 @@
+// The progress bar background runs show while they fill the grid, with an estimate of how long
+// is left. It's redrawn in place on its own line, so it's only shown when that line is a terminal.
+
+use std::{
+    collections::VecDeque,
+    io::{self, IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    grid::Grid,
+    i18n::t,
+    terminal::renderer,
+    verbosity::{self, Verbosity},
+};
+
+// How many characters wide the bar itself is
+const BAR_WIDTH: usize = 30;
+// How often the bar is redrawn at most, since counting the live cells takes a pass over the grid
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+// How far back the filling speed the estimate goes by is measured. Grids fill at very different
+// speeds as they go, so only the recent speed says much about what's left.
+const SPEED_WINDOW: Duration = Duration::from_secs(5);
+
+/// A progress bar for the share of the grid that's filled, with the time left at the recent speed
+pub struct ProgressBar {
+    // The fill percentage at each redraw over the last `SPEED_WINDOW`
+    samples: VecDeque<(Instant, f64)>,
+    last_drawn: Option<Instant>,
+    shown: bool,
+}
+
+impl ProgressBar {
+    /// A bar that's drawn if stdout is a terminal and status messages aren't silenced
+    pub fn new() -> Self {
+        Self {
+            samples: VecDeque::new(),
+            last_drawn: None,
+            shown: io::stdout().is_terminal() && verbosity::enabled(Verbosity::Normal),
+        }
+    }
+
+    /// Redraws the bar for how full `grid` is, unless it was just drawn and `force` isn't set
+    pub fn update(&mut self, grid: &Grid, force: bool) {
+        if !self.shown {
+            return;
+        }
+        let now = Instant::now();
+        if !force
+            && self
+                .last_drawn
+                .is_some_and(|last| now - last < REDRAW_INTERVAL)
+        {
+            return;
+        }
+        self.last_drawn = Some(now);
+
+        let percent = grid.fill_percent();
+        self.samples.push_back((now, percent));
+        while self
+            .samples
+            .front()
+            .is_some_and(|&(time, _)| now - time > SPEED_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+        let eta = match self.time_left(percent) {
+            Some(left) => t!("run.eta", time = format_time(left)),
+            None => t!("run.estimating"),
+        };
+        let line = t!(
+            "run.progress_bar",
+            bar = bar(percent),
+            percent = format!("{percent:3.0}"),
+            generation = grid.generation,
+            eta = eta
+        );
+        print!("\r{line}\x1B[K");
+        io::stdout().flush().unwrap();
+    }
+
+    /// Takes the bar off its line, so other messages can be printed there.
+    /// It comes back with the next `update`.
+    pub fn clear(&mut self) {
+        if self.shown && self.last_drawn.take().is_some() {
+            print!("\r\x1B[K");
+            io::stdout().flush().unwrap();
+        }
+    }
+
+    /// Leaves the bar where it is, moving on to the next line
+    pub fn finish(&mut self) {
+        if self.shown && self.last_drawn.is_some() {
+            println!();
+        }
+    }
+
+    // How long filling the rest of the grid takes at the speed over the samples, if it's filling
+    fn time_left(&self, percent: f64) -> Option<Duration> {
+        let &(start, start_percent) = self.samples.front()?;
+        let &(end, _) = self.samples.back()?;
+        let speed = (percent - start_percent) / (end - start).as_secs_f64();
+        (speed > 0.0 && speed.is_finite())
+            .then(|| Duration::from_secs_f64((100.0 - percent).max(0.0) / speed))
+    }
+}
+
+impl Default for ProgressBar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The bar for `percent`, in blocks or, without unicode, #'s
+fn bar(percent: f64) -> String {
+    let (filled, empty) = if renderer().capabilities().unicode {
+        ("█", "░")
+    } else {
+        ("#", "-")
+    };
+    let done = ((percent / 100.0 * BAR_WIDTH as f64).round() as usize).min(BAR_WIDTH);
+    format!("{}{}", filled.repeat(done), empty.repeat(BAR_WIDTH - done))
+}
+
+// A duration as hours, minutes and seconds, leaving off the hours when there aren't any
+fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{seconds:02}")
+    } else {
+        format!("{minutes}:{seconds:02}")
+    }
+}
@@ -0,0 +1,132 @@
+//! Python bindings, so the simulation can be scripted from notebooks. Needs the `python` feature,
+//! and is built into an importable module with e.g. `maturin develop --features python`:
+//!
+//! ```python
+//! import spreading_colors_ca
+//!
+//! grid = spreading_colors_ca.Grid(width=256, height=256, colorshift=6, seed=42)
+//! while grid.step():
+//!     pass
+//! image = grid.color_states()  # numpy array of shape (height, width, 3)
+//! ```
+
+use numpy::{
+    ndarray::{Array2, Array3},
+    IntoPyArray, PyArray2, PyArray3,
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+use crate::{
+    config::{
+        SimulationConfig, COLORSHIFT_DEFAULT, HEIGHT_DEFAULT, SPREAD_CHANCE_DEFAULT,
+        STARTING_LIVE_CELLS_DEFAULT, WIDTH_DEFAULT,
+    },
+    grid,
+    simulation::{starting_grid, yx_coordinate_pairs},
+};
+
+/// A simulation with its starting cells placed, advanced one generation at a time with `step()`
+#[pyclass(name = "Grid", module = "spreading_colors_ca")]
+pub struct PyGrid {
+    grid: grid::Grid,
+    rng: StdRng,
+    yx_coordinate_pairs: Vec<[usize; 2]>,
+    seed: u64,
+}
+
+#[pymethods]
+impl PyGrid {
+    /// Raises ValueError if the settings aren't valid, e.g. a grid smaller than 3x3
+    #[new]
+    #[pyo3(signature = (
+        width = WIDTH_DEFAULT,
+        height = HEIGHT_DEFAULT,
+        starting_live_cells = STARTING_LIVE_CELLS_DEFAULT,
+        colorshift = COLORSHIFT_DEFAULT,
+        spread_chance = SPREAD_CHANCE_DEFAULT,
+        seed = None,
+    ))]
+    fn new(
+        width: usize,
+        height: usize,
+        starting_live_cells: u32,
+        colorshift: u8,
+        spread_chance: f64,
+        seed: Option<u64>,
+    ) -> PyResult<Self> {
+        let seed = seed.unwrap_or_else(|| thread_rng().gen());
+        let mut config = SimulationConfig {
+            width,
+            height,
+            starting_live_cells,
+            colorshift,
+            spread_chance,
+            seed: Some(seed),
+            ..SimulationConfig::default()
+        };
+        config
+            .validate()
+            .map_err(|e| PyValueError::new_err(e.to_string()))?;
+        config.resolve_auto();
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let grid = starting_grid(&config, &mut rng);
+        Ok(Self {
+            grid,
+            rng,
+            yx_coordinate_pairs: yx_coordinate_pairs(width, height, config.boundary),
+            seed,
+        })
+    }
+
+    /// Advances the simulation by one generation.
+    /// Returns False once every cell is alive, meaning the simulation is finished.
+    fn step(&mut self) -> bool {
+        self.grid.step(&self.yx_coordinate_pairs, &mut self.rng)
+    }
+
+    /// Number of generations simulated so far
+    #[getter]
+    fn generation(&self) -> u64 {
+        self.grid.generation
+    }
+
+    #[getter]
+    fn width(&self) -> usize {
+        self.grid.width
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.grid.height
+    }
+
+    /// The seed the simulation was started with, to reproduce it later
+    #[getter]
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Every cell's color as a uint8 array of shape (height, width, 3), ready for e.g. matplotlib.
+    /// It's a copy, so it doesn't change as the simulation goes on.
+    fn color_states<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray3<u8>> {
+        let shape = (self.grid.height, self.grid.width, 3);
+        Array3::from_shape_fn(shape, |(y, x, channel)| {
+            self.grid.get_color(y, x).as_slice()[channel]
+        })
+        .into_pyarray(py)
+    }
+
+    /// Whether each cell is alive, as a bool array of shape (height, width)
+    fn alive_states<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<bool>> {
+        let shape = (self.grid.height, self.grid.width);
+        Array2::from_shape_fn(shape, |(y, x)| self.grid.alive_states[[y, x]]).into_pyarray(py)
+    }
+}
+
+/// The `spreading_colors_ca` Python module
+#[pymodule]
+fn spreading_colors_ca(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyGrid>()
+}
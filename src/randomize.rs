@@ -0,0 +1,39 @@
+// Settings picked at random for `--randomize`, for discovering new looks without knowing which
+// numbers to try. The ranges are curated to grow something worth looking at: past them, images
+// tend to turn into flat color or static. What was picked is printed and recorded in a manifest
+// beside the image, so a lucky find can be rendered again.
+
+use std::ops::RangeInclusive;
+
+use clap::ValueEnum;
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{config::SimulationConfig, palettes::ColorPalette};
+
+/// The settings `--randomize` picks, as they're named in config files
+pub const RANDOMIZED_SETTINGS: [&str; 4] = [
+    "colorshift",
+    "spread_chance",
+    "starting_live_cells",
+    "palette",
+];
+
+const COLORSHIFT_RANGE: RangeInclusive<u8> = 2..=20;
+const SPREAD_CHANCE_RANGE: RangeInclusive<f64> = 0.2..=0.9;
+const STARTING_LIVE_CELLS_RANGE: RangeInclusive<u32> = 1..=12;
+
+/// Replaces the randomized settings in `config` with ones picked from the curated ranges
+pub fn randomize(config: &mut SimulationConfig, rng: &mut impl Rng) {
+    config.colorshift = rng.gen_range(COLORSHIFT_RANGE);
+    // Rounded, so the value reads (and can be typed back in) as easily as a hand-picked one
+    config.spread_chance = (rng.gen_range(SPREAD_CHANCE_RANGE) * 100.0).round() / 100.0;
+    config.starting_live_cells = rng.gen_range(STARTING_LIVE_CELLS_RANGE);
+
+    // `auto` depends on the time of day, so it wouldn't record which palette was actually used
+    let palettes: Vec<ColorPalette> = ColorPalette::value_variants()
+        .iter()
+        .copied()
+        .filter(|palette| *palette != ColorPalette::Auto)
+        .collect();
+    config.palette = palettes.choose(rng).copied();
+}
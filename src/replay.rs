@@ -0,0 +1,41 @@
+// Replays of whole runs as a single web page: the frames of a timelapse are embedded as PNGs in
+// an HTML file (`replay.html`) with a small player, which can pause, step, seek and change speed.
+// Nothing else is needed to watch one, so it can be sent to anyone with a browser.
+
+use std::{io::Write, time::Duration};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use image::{
+    codecs::png::{CompressionType, FilterType, PngEncoder},
+    ColorType, ImageEncoder, ImageResult, RgbImage,
+};
+
+use crate::metadata::SOFTWARE;
+
+// The page, with placeholders for the frames and what they need
+const TEMPLATE: &str = include_str!("replay.html");
+
+/// Writes `frames` into `output` as a web page playing them back, each frame shown for `frame_time`
+pub fn write_html(
+    frames: &[RgbImage],
+    mut output: impl Write,
+    frame_time: Duration,
+) -> ImageResult<()> {
+    let (width, height) = frames.first().map_or((1, 1), RgbImage::dimensions);
+    let mut encoded = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let mut png = Vec::new();
+        PngEncoder::new_with_quality(&mut png, CompressionType::Best, FilterType::Adaptive)
+            .write_image(frame, frame.width(), frame.height(), ColorType::Rgb8)?;
+        encoded.push(format!("\"{}\"", STANDARD.encode(png)));
+    }
+    let page = TEMPLATE
+        .replace("__SOFTWARE__", SOFTWARE)
+        .replace("__TITLE__", "Spreading colors replay")
+        .replace("__WIDTH__", &width.to_string())
+        .replace("__HEIGHT__", &height.to_string())
+        .replace("__FRAME_MS__", &frame_time.as_millis().max(1).to_string())
+        .replace("__FRAMES__", &encoded.join(",\n"));
+    output.write_all(page.as_bytes())?;
+    Ok(output.flush()?)
+}
@@ -0,0 +1,147 @@
+// Grid sizes written as one value, like `1920x1080`, `4k` or `terminal`, so both dimensions are
+// given (and checked) together instead of as separate width and height numbers.
+
+use std::str::FromStr;
+
+use crate::{config::MIN_GRID_SIZE, simulation::STATUS_LINES, terminal::Renderer};
+
+// Common resolutions by name, as width and height, including the usual desktop and laptop screens
+// for wallpapers
+const NAMED_SIZES: [(&str, usize, usize); 17] = [
+    ("720p", 1280, 720),
+    ("hd", 1280, 720),
+    ("wxga", 1366, 768),
+    ("hd+", 1600, 900),
+    ("1080p", 1920, 1080),
+    ("fhd", 1920, 1080),
+    ("wuxga", 1920, 1200),
+    ("uwfhd", 2560, 1080),
+    ("1440p", 2560, 1440),
+    ("qhd", 2560, 1440),
+    ("2k", 2560, 1440),
+    ("wqxga", 2560, 1600),
+    ("uwqhd", 3440, 1440),
+    ("2160p", 3840, 2160),
+    ("4k", 3840, 2160),
+    ("5k", 5120, 2880),
+    ("8k", 7680, 4320),
+];
+
+/// A grid size given with `--size`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridSize {
+    /// Exactly this many pixels (cells) wide and high
+    Exact { width: usize, height: usize },
+    /// Filling the terminal, which was this many columns and rows when the flag was read
+    Terminal { columns: usize, rows: usize },
+}
+
+impl GridSize {
+    /// The grid's width and height, including its border. Drawn by `renderer`, an animation of
+    /// the grid fills the terminal with its status lines.
+    pub fn dimensions(self, renderer: &Renderer) -> (usize, usize) {
+        match self {
+            GridSize::Exact { width, height } => (width, height),
+            // The border isn't drawn, so it doesn't take up any room. The frame around it does.
+            GridSize::Terminal { columns, rows } => {
+                let (across, down) = renderer.cells_per_char();
+                let frame = 2 * renderer.frame_width();
+                let columns = columns.saturating_sub(frame);
+                let rows = rows.saturating_sub(STATUS_LINES + frame);
+                (
+                    (columns / renderer.cell_width() * across + 2).max(MIN_GRID_SIZE),
+                    (rows * down + 2).max(MIN_GRID_SIZE),
+                )
+            }
+        }
+    }
+}
+
+impl FromStr for GridSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        if s == "terminal" {
+            let (columns, rows) = crossterm::terminal::size()
+                .map_err(|e| format!("the terminal's size can't be read: {e}"))?;
+            return Ok(GridSize::Terminal {
+                columns: columns.into(),
+                rows: rows.into(),
+            });
+        }
+        if let Some((_, width, height)) = NAMED_SIZES.iter().find(|(name, ..)| *name == s) {
+            return Ok(GridSize::Exact {
+                width: *width,
+                height: *height,
+            });
+        }
+
+        let (width, height) = s.split_once(['x', '×']).ok_or_else(|| {
+            format!("'{s}' isn't a size, expected WIDTHxHEIGHT (like 1920x1080), 720p, 1080p, 1440p, 4k, 5k, 8k, a desktop size like wxga, wuxga or uwqhd, or terminal")
+        })?;
+        let (Ok(width), Ok(height)) = (width.trim().parse(), height.trim().parse()) else {
+            return Err(format!(
+                "'{s}' isn't a size, both the width and height have to be whole numbers"
+            ));
+        };
+        if width < MIN_GRID_SIZE || height < MIN_GRID_SIZE {
+            return Err(format!(
+                "{width}x{height} is too small, the width and height both have to be at least {MIN_GRID_SIZE}"
+            ));
+        }
+        Ok(GridSize::Exact { width, height })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exact(width: usize, height: usize) -> Result<GridSize, String> {
+        Ok(GridSize::Exact { width, height })
+    }
+
+    #[test]
+    fn sizes_parse() {
+        for (size, parsed) in [
+            ("1920x1080", exact(1920, 1080)),
+            ("64×32", exact(64, 32)),
+            (" 100 X 50 ", exact(100, 50)),
+            ("3x3", exact(3, 3)),
+            ("720p", exact(1280, 720)),
+            ("1080p", exact(1920, 1080)),
+            ("4K", exact(3840, 2160)),
+            ("wuxga", exact(1920, 1200)),
+            ("uwqhd", exact(3440, 1440)),
+        ] {
+            assert_eq!(size.parse(), parsed, "{size}");
+        }
+    }
+
+    #[test]
+    fn every_named_size_parses() {
+        for (name, width, height) in NAMED_SIZES {
+            assert_eq!(name.parse(), exact(width, height), "{name}");
+        }
+    }
+
+    #[test]
+    fn bad_sizes_are_rejected() {
+        for (size, error) in [
+            ("2x100", "too small"),
+            ("100x0", "too small"),
+            ("huge", "isn't a size"),
+            ("", "isn't a size"),
+            ("1920", "isn't a size"),
+            ("1920x", "whole numbers"),
+            ("x1080", "whole numbers"),
+            ("19.5x10", "whole numbers"),
+            ("-5x10", "whole numbers"),
+            ("10x10x10", "whole numbers"),
+        ] {
+            let message = size.parse::<GridSize>().unwrap_err();
+            assert!(message.contains(error), "{size}: {message}");
+        }
+    }
+}
@@ -0,0 +1,216 @@
+// Grid dumps: the colors and live cells of a grid in a compact binary file, for other renderers
+// to read and for resuming runs quickly. Unlike state files (see `state.rs`) they don't hold the
+// settings or the colonies, only what a picture of the grid needs:
+//
+//   "SCGRID" and a format version byte
+//   width and height (u32 each) and the generation (u64)
+//   every cell's color row by row: red, green, blue (1 byte each)
+//   whether each cell is alive, a bit per cell in the same order, from the lowest bit of each byte
+//   (the last byte padded with zeros)
+//
+// Numbers are little-endian.
+
+use std::{
+    fs,
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    grid::{Grid, RgbColor},
+    state::StateError,
+};
+
+const MAGIC: &[u8] = b"SCGRID";
+const FORMAT_VERSION: u8 = 1;
+
+// Bytes of the dimensions and generation after the version
+const HEADER_BYTES: usize = 4 + 4 + 8;
+
+/// A grid read from a dump
+#[derive(Debug, Clone)]
+pub struct GridDump {
+    pub width: usize,
+    pub height: usize,
+    pub generation: u64,
+    /// Every cell's color, row by row
+    pub colors: Vec<RgbColor>,
+    /// Whether every cell is alive, row by row
+    pub alive: Vec<bool>,
+}
+
+/// Dumps `grid` at `path`, creating any missing directories on the way
+pub fn write(grid: &Grid, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let too_big = || io::Error::other("the grid is too big to dump");
+    let mut file = BufWriter::new(fs::File::create(path)?);
+    file.write_all(MAGIC)?;
+    file.write_all(&[FORMAT_VERSION])?;
+    file.write_all(
+        &u32::try_from(grid.width)
+            .map_err(|_| too_big())?
+            .to_le_bytes(),
+    )?;
+    file.write_all(
+        &u32::try_from(grid.height)
+            .map_err(|_| too_big())?
+            .to_le_bytes(),
+    )?;
+    file.write_all(&grid.generation.to_le_bytes())?;
+    for (_, color, _) in grid.cells() {
+        file.write_all(&color.as_slice())?;
+    }
+    let mut byte = 0;
+    for (index, (alive, _, _)) in grid.cells().enumerate() {
+        byte |= u8::from(alive) << (index % 8);
+        if index % 8 == 7 {
+            file.write_all(&[byte])?;
+            byte = 0;
+        }
+    }
+    if !(grid.width * grid.height).is_multiple_of(8) {
+        file.write_all(&[byte])?;
+    }
+    file.flush()
+}
+
+/// Reads a grid dumped with `write`
+pub fn read(path: &Path) -> Result<GridDump, StateError> {
+    let mut contents = Vec::new();
+    fs::File::open(path)?.read_to_end(&mut contents)?;
+    let invalid = |message: &str| StateError::Invalid(message.to_string());
+
+    let rest = contents
+        .strip_prefix(MAGIC)
+        .ok_or_else(|| invalid("it doesn't start like a grid dump"))?;
+    let (&version, rest) = rest
+        .split_first()
+        .ok_or_else(|| invalid("the file is cut short"))?;
+    if version != FORMAT_VERSION {
+        return Err(StateError::Invalid(format!(
+            "it was dumped in format version {version}, but only version {FORMAT_VERSION} can be read"
+        )));
+    }
+    let (header, rest) = rest
+        .split_first_chunk::<HEADER_BYTES>()
+        .ok_or_else(|| invalid("the file is cut short"))?;
+    let width = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let height = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+    let generation = u64::from_le_bytes(header[8..].try_into().unwrap());
+
+    let cells = width
+        .checked_mul(height)
+        .ok_or_else(|| invalid("its size is impossible"))?;
+    let color_bytes = cells
+        .checked_mul(3)
+        .ok_or_else(|| invalid("its size is impossible"))?;
+    if rest.len() != color_bytes + cells.div_ceil(8) {
+        return Err(invalid("its length doesn't match its size"));
+    }
+    let (colors, alive) = rest.split_at(color_bytes);
+    Ok(GridDump {
+        width,
+        height,
+        generation,
+        colors: colors
+            .chunks_exact(3)
+            .map(|rgb| RgbColor::from([rgb[0], rgb[1], rgb[2]]))
+            .collect(),
+        alive: (0..cells)
+            .map(|index| alive[index / 8] >> (index % 8) & 1 == 1)
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::SimulationConfig,
+        testing::{grown_grid, temp_path},
+    };
+
+    fn grid(width: usize, height: usize) -> Grid {
+        let config = SimulationConfig {
+            width,
+            height,
+            starting_live_cells: 3,
+            ..SimulationConfig::default()
+        };
+        grown_grid(&config, 4)
+    }
+
+    // Dumps `grid` and reads it back
+    fn round_trip(grid: &Grid, name: &str) -> GridDump {
+        let path = temp_path(name);
+        write(grid, &path).unwrap();
+        let dump = read(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        dump
+    }
+
+    fn assert_matches(dump: &GridDump, grid: &Grid) {
+        assert_eq!((dump.width, dump.height), (grid.width, grid.height));
+        assert_eq!(dump.generation, grid.generation);
+        let (alive, colors): (Vec<bool>, Vec<[u8; 3]>) = grid
+            .cells()
+            .map(|(alive, color, _)| (alive, color.as_slice()))
+            .unzip();
+        assert_eq!(dump.alive, alive);
+        let dumped: Vec<[u8; 3]> = dump.colors.iter().map(RgbColor::as_slice).collect();
+        assert_eq!(dumped, colors);
+        assert!(alive.contains(&true) && alive.contains(&false));
+    }
+
+    #[test]
+    fn dumps_read_back_the_same() {
+        // 32 cells fill whole bytes of live bits, 63 leave the last one padded
+        for (width, height) in [(8, 4), (9, 7)] {
+            let grid = grid(width, height);
+            let name = format!("{width}x{height}.scgrid");
+            assert_matches(&round_trip(&grid, &name), &grid);
+        }
+    }
+
+    #[test]
+    fn dumps_have_the_documented_length() {
+        let grid = grid(9, 7);
+        let path = temp_path("length.scgrid");
+        write(&grid, &path).unwrap();
+        let length = fs::metadata(&path).unwrap().len() as usize;
+        fs::remove_file(path).unwrap();
+        assert_eq!(length, MAGIC.len() + 1 + HEADER_BYTES + 63 * 3 + 8);
+    }
+
+    // Reads `bytes` as a dump, giving the error it's refused with
+    fn read_error(bytes: &[u8], name: &str) -> String {
+        let path = temp_path(name);
+        fs::write(&path, bytes).unwrap();
+        let result = read(&path);
+        fs::remove_file(path).unwrap();
+        match result {
+            Err(StateError::Invalid(message)) => message,
+            other => panic!("expected an invalid dump, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn damaged_dumps_are_refused() {
+        let path = temp_path("damaged.scgrid");
+        write(&grid(9, 7), &path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[..MAGIC.len()].copy_from_slice(b"PNGRID");
+        assert!(read_error(&wrong_magic, "magic.scgrid").contains("doesn't start like"));
+        let mut wrong_version = bytes.clone();
+        wrong_version[MAGIC.len()] = FORMAT_VERSION + 1;
+        assert!(read_error(&wrong_version, "version.scgrid").contains("format version"));
+        assert!(read_error(&bytes[..MAGIC.len() + 5], "header.scgrid").contains("cut short"));
+        let truncated = &bytes[..bytes.len() - 1];
+        assert!(read_error(truncated, "truncated.scgrid").contains("doesn't match its size"));
+    }
+}
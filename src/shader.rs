@@ -0,0 +1,505 @@
+// Post-processing exported images with a fragment shader, Shadertoy style: the finished image is
+// drawn through the user's shader on the GPU (offscreen, through wgpu) and the result is what gets
+// saved, for effects like chromatic aberration or CRT curvature. Needs the `shaders` feature.
+//
+// Shaders are written in WGSL (`.wgsl`) or GLSL (`.frag` or `.glsl`) and define `mainImage`, like on
+// Shadertoy. Its `fragCoord` is in pixels from the bottom-left corner, and the image is available
+// as `iChannel0` with its size in `iResolution`:
+//
+//     // WGSL
+//     fn mainImage(fragCoord: vec2<f32>) -> vec4<f32> {
+//         return textureSample(iChannel0, iChannel0Sampler, fragCoord / iResolution.xy);
+//     }
+//
+//     // GLSL, without a #version line
+//     void mainImage(out vec4 fragColor, in vec2 fragCoord) {
+//         fragColor = texture(iChannel0, fragCoord / iResolution.xy);
+//     }
+//
+// The output has the same size as the image, and its alpha channel is dropped.
+// Examples are in examples/shaders.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use image::RgbImage;
+
+use crate::i18n::t;
+
+// What's put around a WGSL shader: the inputs, and the entry point calling its `mainImage`.
+// They go after the user's code, so lines in error messages are numbered as in their file.
+const WGSL_WRAPPER: &str = "
+@group(0) @binding(0) var iChannel0: texture_2d<f32>;
+@group(0) @binding(1) var iChannel0Sampler: sampler;
+@group(0) @binding(2) var<uniform> iResolution: vec3<f32>;
+
+@fragment
+fn spreading_colors_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return mainImage(vec2<f32>(position.x, iResolution.y - position.y));
+}
+";
+
+// The same for GLSL, where the inputs have to be declared first. They're kept to one line, which
+// is taken off line numbers in error messages.
+const GLSL_PRELUDE: &str = "layout(set = 0, binding = 0) uniform texture2D iChannel0Texture; \
+layout(set = 0, binding = 1) uniform sampler iChannel0Sampler; \
+layout(set = 0, binding = 2) uniform Inputs { vec3 iResolution; }; \
+layout(location = 0) out vec4 spreadingColorsFragColor;
+";
+const GLSL_ENTRY_POINT: &str = "
+void main() {
+    mainImage(spreadingColorsFragColor, vec2(gl_FragCoord.x, iResolution.y - gl_FragCoord.y));
+}
+";
+// Shadertoy's `iChannel0` is a combined texture and sampler, which has to be put together in wgpu
+#[cfg_attr(not(feature = "shaders"), allow(dead_code))]
+const GLSL_DEFINES: [(&str, &str); 1] =
+    [("iChannel0", "sampler2D(iChannel0Texture, iChannel0Sampler)")];
+
+/// A fragment shader read from a file, ready to post-process images with
+#[derive(Debug, Clone)]
+pub struct PostShader {
+    #[cfg_attr(not(feature = "shaders"), allow(dead_code))]
+    language: ShaderLanguage,
+    // The user's code with the inputs and entry point put around it
+    #[cfg_attr(not(feature = "shaders"), allow(dead_code))]
+    source: String,
+}
+
+/// Why a shader couldn't be loaded
+#[derive(Debug)]
+pub enum ShaderError {
+    /// The program was built without the `shaders` feature
+    Unsupported,
+    /// The shader's extension doesn't say which language it's written in
+    UnknownLanguage(PathBuf),
+    /// The shader file couldn't be read
+    Io(PathBuf, io::Error),
+    /// The shader doesn't compile, with the compiler's messages
+    Invalid(String),
+}
+
+impl ShaderError {
+    /// The error as a message from the catalog, in the user's language
+    pub fn message(&self) -> String {
+        match self {
+            ShaderError::Unsupported => t!("export.shader_unsupported"),
+            ShaderError::UnknownLanguage(path) => {
+                t!("export.shader_unknown_language", path = path.display())
+            }
+            ShaderError::Io(path, e) => {
+                t!("export.shader_unreadable", path = path.display(), error = e)
+            }
+            ShaderError::Invalid(message) => t!("export.shader_failed", error = message),
+        }
+    }
+}
+
+impl Display for ShaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Unsupported => write!(
+                f,
+                "this build can't run shaders, rebuild it with `--features shaders`"
+            ),
+            ShaderError::UnknownLanguage(path) => write!(
+                f,
+                "can't tell what language {} is written in, name it .wgsl, .frag or .glsl",
+                path.display()
+            ),
+            ShaderError::Io(path, e) => {
+                write!(f, "couldn't read the shader {}: {e}", path.display())
+            }
+            ShaderError::Invalid(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+impl PostShader {
+    /// Reads the shader at `path`, going by its extension for the language it's written in, and
+    /// checks it for mistakes. Fails in builds without the `shaders` feature.
+    /// Either way, problems are known before anything is rendered.
+    pub fn load(path: &Path) -> Result<Self, ShaderError> {
+        if !cfg!(feature = "shaders") {
+            return Err(ShaderError::Unsupported);
+        }
+        let language = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("wgsl") => ShaderLanguage::Wgsl,
+            Some("frag" | "glsl") => ShaderLanguage::Glsl,
+            _ => return Err(ShaderError::UnknownLanguage(path.to_path_buf())),
+        };
+        let code = fs::read_to_string(path).map_err(|e| ShaderError::Io(path.to_path_buf(), e))?;
+        let shader = Self {
+            language,
+            source: match language {
+                ShaderLanguage::Wgsl => format!("{code}\n{WGSL_WRAPPER}"),
+                ShaderLanguage::Glsl => format!("{GLSL_PRELUDE}{code}\n{GLSL_ENTRY_POINT}"),
+            },
+        };
+        #[cfg(feature = "shaders")]
+        shader.check(path, &code).map_err(ShaderError::Invalid)?;
+        Ok(shader)
+    }
+
+    /// Draws `image` through the shader, returning the shaded image
+    #[cfg(feature = "shaders")]
+    pub fn apply(&self, image: &RgbImage) -> Result<RgbImage, String> {
+        pollster::block_on(gpu::render(image, self.language, &self.source))
+    }
+
+    #[cfg(not(feature = "shaders"))]
+    pub fn apply(&self, _image: &RgbImage) -> Result<RgbImage, String> {
+        unreachable!("shaders can't be loaded without the `shaders` feature")
+    }
+
+    // Parses and validates the shader without a GPU. Mistakes are reported like a compiler would,
+    // as "path:line:column: message" pointing into the user's `code`.
+    #[cfg(feature = "shaders")]
+    fn check(&self, path: &Path, code: &str) -> Result<(), String> {
+        use std::error::Error;
+
+        use wgpu::naga::{
+            front::{glsl, wgsl},
+            valid::{Capabilities, ValidationFlags, Validator},
+            ShaderStage, SourceLocation,
+        };
+
+        let prelude_lines = match self.language {
+            ShaderLanguage::Wgsl => 0,
+            ShaderLanguage::Glsl => GLSL_PRELUDE.lines().count() as u32,
+        };
+        let user_lines = prelude_lines + 1..=prelude_lines + code.lines().count() as u32;
+        // Mistakes found in the wrapper (e.g. `mainImage` missing) don't point at a line
+        let report = |message: String, location: Option<SourceLocation>| match location {
+            Some(location) if user_lines.contains(&location.line_number) => format!(
+                "{}:{}:{}: {message}",
+                path.display(),
+                location.line_number - prelude_lines,
+                location.line_position
+            ),
+            _ => format!("{}: {message}", path.display()),
+        };
+
+        let module = match self.language {
+            ShaderLanguage::Wgsl => wgsl::parse_str(&self.source)
+                .map_err(|e| report(e.message().to_string(), e.location(&self.source)))?,
+            ShaderLanguage::Glsl => {
+                let mut options = glsl::Options::from(ShaderStage::Fragment);
+                for (name, value) in GLSL_DEFINES {
+                    options.defines.insert(name.to_string(), value.to_string());
+                }
+                glsl::Frontend::default()
+                    .parse(&options, &self.source)
+                    .map_err(|e| {
+                        let errors: Vec<String> = e
+                            .errors
+                            .iter()
+                            .map(|e| report(e.kind.to_string(), e.location(&self.source)))
+                            .collect();
+                        errors.join("\n")
+                    })?
+            }
+        };
+        Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .map_err(|e| {
+                // The outermost error only names the function, the reason is further in
+                let mut message = e.as_inner().to_string();
+                let mut cause = e.as_inner().source();
+                while let Some(error) = cause {
+                    message = format!("{message}: {error}");
+                    cause = error.source();
+                }
+                report(message, e.location(&self.source))
+            })?;
+        Ok(())
+    }
+}
+
+/// The name of the GPU (or software renderer) shaders run on, or why none can be used
+#[cfg(feature = "shaders")]
+pub fn gpu_name() -> Result<String, String> {
+    pollster::block_on(gpu::adapter()).map(|adapter| adapter.get_info().name)
+}
+
+// What a shader is written in
+#[derive(Debug, Clone, Copy)]
+enum ShaderLanguage {
+    Wgsl,
+    Glsl,
+}
+
+#[cfg(feature = "shaders")]
+mod gpu {
+    use std::borrow::Cow;
+
+    use image::RgbImage;
+    use wgpu::util::DeviceExt;
+
+    use super::{ShaderLanguage, GLSL_DEFINES};
+
+    // Covers the whole image with one triangle, so every pixel runs the fragment shader
+    const VERTEX_SHADER: &str = "
+        @vertex
+        fn main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+            let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+            return vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+        }
+    ";
+
+    // Both textures hold the colors as they are, without any sRGB conversion, as on Shadertoy
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+    /// The first GPU (or software renderer) that can be found
+    pub async fn adapter() -> Result<wgpu::Adapter, String> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::from_env_or_default());
+        instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .map_err(|e| format!("no GPU was found to run the shader on: {e}"))
+    }
+
+    /// Renders `image` through the wrapped fragment shader `source` on the first GPU (or software
+    /// renderer) that can be found
+    pub async fn render(
+        image: &RgbImage,
+        language: ShaderLanguage,
+        source: &str,
+    ) -> Result<RgbImage, String> {
+        let adapter = adapter().await?;
+        // The adapter's own limits, so images bigger than the defaults allow can be shaded
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                required_limits: adapter.limits(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| format!("couldn't use the GPU: {e}"))?;
+
+        let (width, height) = image.dimensions();
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+
+        // Uploaded bottom row first, so texture coordinates start at the bottom-left like Shadertoy's
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+        for row in image.rows().rev() {
+            for pixel in row {
+                pixels.extend_from_slice(&[pixel[0], pixel[1], pixel[2], u8::MAX]);
+            }
+        }
+        let input = device.create_texture_with_data(
+            &queue,
+            &wgpu::TextureDescriptor {
+                label: Some("image"),
+                size,
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: FORMAT,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &pixels,
+        );
+        let output = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("shaded image"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let resolution = [width as f32, height as f32, 1.0, 0.0];
+        let resolution = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("iResolution"),
+            contents: &resolution.map(f32::to_ne_bytes).concat(),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        // Laid out by hand rather than from the shader, which doesn't have to use all of them
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let input = input.create_view(&Default::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&input),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: resolution.as_entire_binding(),
+                },
+            ],
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        // Mistakes in the shader are reported here instead of panicking
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let vertex = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("full-screen triangle"),
+            source: wgpu::ShaderSource::Wgsl(VERTEX_SHADER.into()),
+        });
+        let fragment = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("post shader"),
+            source: match language {
+                ShaderLanguage::Wgsl => wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+                ShaderLanguage::Glsl => wgpu::ShaderSource::Glsl {
+                    shader: Cow::Borrowed(source),
+                    stage: wgpu::naga::ShaderStage::Fragment,
+                    defines: &GLSL_DEFINES,
+                },
+            },
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("post shader"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &vertex,
+                entry_point: Some("main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &fragment,
+                entry_point: None,
+                compilation_options: Default::default(),
+                targets: &[Some(FORMAT.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        if let Some(error) = device.pop_error_scope().await {
+            return Err(format!("the shader didn't compile: {error}"));
+        }
+
+        // Rows copied out of a texture have to be padded to a multiple of 256 bytes
+        let padded_row = (width * 4).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("readback"),
+            size: u64::from(padded_row) * u64::from(height),
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        {
+            let output = output.create_view(&Default::default());
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("post shader"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &output,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations::default(),
+                })],
+                ..Default::default()
+            });
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+        encoder.copy_texture_to_buffer(
+            output.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &readback,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row),
+                    rows_per_image: None,
+                },
+            },
+            size,
+        );
+        queue.submit([encoder.finish()]);
+
+        let slice = readback.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .map_err(|e| format!("the GPU didn't finish running the shader: {e}"))?;
+        let data = slice.get_mapped_range();
+        let shaded = RgbImage::from_fn(width, height, |x, y| {
+            let start = (y * padded_row + x * 4) as usize;
+            image::Rgb([data[start], data[start + 1], data[start + 2]])
+        });
+        Ok(shaded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_errors_say_what_went_wrong() {
+        let error = PostShader::load(Path::new("effect.txt")).unwrap_err();
+        let missing = PostShader::load(Path::new("no_such_shader.wgsl")).unwrap_err();
+        if cfg!(feature = "shaders") {
+            assert!(matches!(error, ShaderError::UnknownLanguage(_)), "{error}");
+            assert!(matches!(missing, ShaderError::Io(..)), "{missing}");
+            assert!(!error.message().contains("shader can't be used"));
+        } else {
+            assert!(matches!(error, ShaderError::Unsupported), "{error}");
+            assert!(matches!(missing, ShaderError::Unsupported), "{missing}");
+        }
+    }
+}
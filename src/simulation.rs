@@ -0,0 +1,993 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    },
+    execute, terminal,
+};
+use rand::rngs::StdRng;
+
+use crate::{
+    brush::Brush,
+    cast::Cast,
+    config::{Boundary, SimulationConfig},
+    export::{save_image, with_filename_suffix, ImageOptions, Overwrite},
+    grid::Grid,
+    history::History,
+    i18n::t,
+    progress::ProgressBar,
+    terminal::{enter_alternate_screen, leave_alternate_screen, renderer, screen_size, Renderer},
+    timelapse::Timelapse,
+    tui::{self, Controls, Tui},
+    verbosity::status,
+    viewport::{shrink_to_fit, visible_cells, Viewport},
+};
+
+/// Number of generations `U` rewinds at once
+pub const BIG_REWIND: u64 = 100;
+
+// Longest an automatic --draw-every lets an animation go without a frame, so it never drops below
+// a few frames a second on terminals that draw very slowly
+const MAX_FRAME_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often an animation draws a frame, given with `--draw-every`. Either way the simulation
+/// advances at the framerate, skipped generations only aren't drawn, so a terminal that draws
+/// slowly doesn't slow the simulation down with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawEvery {
+    /// Every this many generations
+    Generations(u64),
+    /// As often as the terminal keeps up with, going by how long the frames so far took to draw
+    Auto,
+}
+
+impl FromStr for DrawEvery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().eq_ignore_ascii_case("auto") {
+            return Ok(DrawEvery::Auto);
+        }
+        match s.trim().parse() {
+            Ok(0) | Err(_) => Err(format!(
+                "'{s}' isn't a number of generations, expected a whole number from 1 up or auto"
+            )),
+            Ok(generations) => Ok(DrawEvery::Generations(generations)),
+        }
+    }
+}
+
+/// How often a background run prints a preview of the grid, for keeping an eye on long runs
+/// without animating them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreviewEvery {
+    /// Every this many generations
+    Generations(u64),
+    /// Every this long, written in seconds like `30s`
+    Interval(Duration),
+}
+
+impl FromStr for PreviewEvery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(seconds) = s.strip_suffix('s') {
+            return match seconds.trim().parse::<f64>() {
+                Ok(seconds) if seconds > 0.0 && seconds.is_finite() => {
+                    Ok(PreviewEvery::Interval(Duration::from_secs_f64(seconds)))
+                }
+                _ => Err(format!("'{s}' isn't a number of seconds above 0, like 30s")),
+            };
+        }
+        match s.parse() {
+            Ok(0) | Err(_) => Err(format!(
+                "'{s}' isn't a number of generations or seconds, expected a whole number from 1 \
+                 up or a number of seconds like 30s"
+            )),
+            Ok(generations) => Ok(PreviewEvery::Generations(generations)),
+        }
+    }
+}
+
+// How many generations to simulate before the next frame: as many as `draw_every` asks for,
+// or for `DrawEvery::Auto` as many as the framerate lets go by while a frame takes `draw_time`
+// to draw, when each generation takes `step_time` to simulate
+fn generations_per_frame(
+    draw_every: DrawEvery,
+    frametime: Duration,
+    draw_time: Duration,
+    step_time: Duration,
+) -> u64 {
+    match draw_every {
+        DrawEvery::Generations(generations) => generations,
+        DrawEvery::Auto => {
+            let most = (MAX_FRAME_INTERVAL.as_secs_f64() / frametime.as_secs_f64()).max(1.0);
+            // Simulating has to leave time to draw in, or the skipped generations never catch up
+            let spare = frametime.saturating_sub(step_time).as_secs_f64();
+            let needed = if spare > 0.0 {
+                (draw_time.as_secs_f64() / spare).ceil()
+            } else {
+                most
+            };
+            needed.clamp(1.0, most) as u64
+        }
+    }
+}
+
+/// Lines plain frames leave free under them: the status, the settings, the key and mouse help,
+/// and the line the cursor ends up on
+pub const STATUS_LINES: usize = 5;
+// Lines a background run's previews leave free: the one saying which generation it is,
+// and the line the cursor ends up on
+const PREVIEW_STATUS_LINES: usize = 2;
+// How far back the framerate shown in the status is measured
+const FPS_WINDOW: Duration = Duration::from_secs(1);
+
+// Set by Ctrl+C once `stop_on_interrupt` is called, asking a background run to stop early
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Makes Ctrl+C stop background runs after the current generation (so their state can be saved)
+/// instead of killing the program
+pub fn stop_on_interrupt() {
+    let handled = ctrlc::set_handler(|| {
+        status!("{}", t!("run.interrupted"));
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    });
+    if let Err(e) = handled {
+        let error = format!("{e:?}");
+        eprintln!("{}", t!("run.interrupt_unavailable", error = error));
+    }
+}
+
+/// Creates a grid from `config` with its starting cells already placed
+pub fn starting_grid(config: &SimulationConfig, rng: &mut StdRng) -> Grid {
+    let mut grid = Grid::new(config);
+
+    // =======================
+    //  PLACE STARTING CELLS
+    // =======================
+    for _ in 0..config.starting_live_cells {
+        grid.spawn_orphan_at_random_position(rng);
+    }
+    grid
+}
+
+/*
+ ____ ___ __  __ _   _ _        _  _____ ___ ___  _   _
+/ ___|_ _|  \/  | | | | |      / \|_   _|_ _/ _ \| \ | |
+\___ \| || |\/| | | | | |     / _ \ | |  | | | | |  \| |
+ ___) | || |  | | |_| | |___ / ___ \| |  | | |_| | |\  |
+|____/___|_|  |_|\___/|_____/_/   \_\_| |___\___/|_| \_|
+*/
+
+/// Produces all the indices of a Vec<Vec<_>> with some width and height,
+/// skipping the outermost rows and columns if `boundary` keeps them as a border that's never simulated.
+/// Height is the .len() of the outer vec
+/// Width is the .len() of the inner vec
+/// In theory this would improve performance. In practice it does not.
+pub fn yx_coordinate_pairs(width: usize, height: usize, boundary: Boundary) -> Vec<[usize; 2]> {
+    let mut yx_coordinate_pairs = Vec::with_capacity(width * height);
+    let border = boundary.border();
+    for y in border..(height - border) {
+        for x in border..(width - border) {
+            yx_coordinate_pairs.push([y, x]);
+        }
+    }
+    // Make immutable, since it will never be modified again.
+    yx_coordinate_pairs.shrink_to_fit();
+    yx_coordinate_pairs
+}
+
+/// Runs a whole simulation from `config` in the background and returns the finished grid,
+/// keeping track of when each cell was born if `track_births` is set
+pub fn render(config: &SimulationConfig, rng: &mut StdRng, track_births: bool) -> Grid {
+    let mut grid = starting_grid(config, rng);
+    if track_births {
+        grid.track_births();
+    }
+    let yx_coordinate_pairs = yx_coordinate_pairs(config.width, config.height, config.boundary);
+    simulation_in_background(
+        grid,
+        rng,
+        &yx_coordinate_pairs,
+        None,
+        Progress::Silent,
+        None,
+        None,
+    )
+}
+
+/// How a background run shows how far along it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Progress {
+    /// Not at all
+    Silent,
+    /// A status line each time another tenth of the grid fills up, for screen readers and logs
+    Lines,
+    /// A progress bar with the time left, when printing to a terminal
+    Bar,
+}
+
+/// Where and how a background run saves snapshot images on its way
+pub struct Snapshots<'a> {
+    /// Generations to save a snapshot on
+    pub at: &'a [u64],
+    /// Path of the final image, which the snapshots are named after
+    pub output: &'a Path,
+    pub options: &'a ImageOptions,
+    pub overwrite: Overwrite,
+}
+
+// Names the snapshot taken at `generation`, e.g. "image.png" -> "image_gen100.png"
+fn snapshot_path(output: &Path, generation: u64) -> PathBuf {
+    with_filename_suffix(output, &format!("_gen{generation}"))
+}
+
+// Waits up to `timeout` for a key press or mouse event while the terminal is in raw mode.
+// Without raw mode input can't be read one event at a time, so this just sleeps.
+fn read_event(timeout: Duration, raw_mode: bool) -> Option<Event> {
+    if !raw_mode {
+        thread::sleep(timeout);
+        return None;
+    }
+    if !event::poll(timeout).unwrap_or(false) {
+        return None;
+    }
+    match event::read() {
+        Ok(Event::Key(key)) if key.kind != KeyEventKind::Press => None,
+        Ok(event) => Some(event),
+        Err(_) => None,
+    }
+}
+
+// Blocks until the user presses a key (or Enter, when the terminal isn't in raw mode)
+fn wait_for_key(message: &str, raw_mode: bool) {
+    print!("{message}\r\n");
+    io::stdout().flush().unwrap();
+
+    if raw_mode {
+        tui::wait_for_key_press();
+    } else {
+        // EOF or a read error just means there's nobody to wait for
+        let _ = io::stdin().read_line(&mut String::new());
+    }
+}
+
+// Whether a key press asks the animation to stop (q, Esc, or Ctrl+C since raw mode swallows SIGINT)
+fn is_quit_key(key: &KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => true,
+        KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
+
+// Counts the frames drawn over the last `FPS_WINDOW`, for the framerate the animation actually
+// reaches, which falls short of the one asked for when drawing or simulating takes too long
+struct FrameCounter {
+    started: Instant,
+    frames: VecDeque<Instant>,
+}
+
+impl FrameCounter {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            frames: VecDeque::new(),
+        }
+    }
+
+    fn tick(&mut self) {
+        let now = Instant::now();
+        self.frames.push_back(now);
+        while self
+            .frames
+            .front()
+            .is_some_and(|&frame| now - frame > FPS_WINDOW)
+        {
+            self.frames.pop_front();
+        }
+    }
+
+    // Frames per second, measured over less than the window at the start
+    fn fps(&self) -> f64 {
+        let measured = self.started.elapsed().min(FPS_WINDOW).as_secs_f64();
+        if measured == 0.0 {
+            return 0.0;
+        }
+        self.frames.len() as f64 / measured
+    }
+}
+
+// The characters of the last plain frame on screen, by row, so the next one only has to redraw
+// the ones that changed. Empty when the screen has to be drawn from scratch.
+type ShownFrame = Vec<Vec<String>>;
+
+// Draws the grid with status lines underneath, giving its statistics, the settings and the
+// controls. On terminals that can move the cursor, frames are drawn over the last one, only
+// redrawing the characters that changed since it (`shown`). Images are drawn over the last one whole.
+fn draw_frame(grid: &Grid, config: &SimulationConfig, controls: &Controls, shown: &mut ShownFrame) {
+    let in_place = renderer().redraws_in_place();
+    print!("{}", plain_frame(grid, config, controls, shown, in_place));
+    io::stdout().flush().unwrap();
+}
+
+// The output drawing a plain frame, either over the last one (`in_place`) or after it
+fn plain_frame(
+    grid: &Grid,
+    config: &SimulationConfig,
+    controls: &Controls,
+    shown: &mut ShownFrame,
+    in_place: bool,
+) -> String {
+    let state = if controls.paused {
+        t!("animation.paused")
+    } else {
+        t!("animation.running")
+    };
+    // Grids bigger than the terminal are drawn through the viewport, zoomed out or cut down to
+    // the part that fits above the status lines, instead of wrapping and scrolling
+    let view = controls.viewport.view(grid);
+    let (columns, rows) = view.drawn_size(renderer());
+    let image = renderer().draws_image();
+    let mut output = String::new();
+    if in_place && !image {
+        output += &draw_changes(&view, (columns, rows), shown);
+    } else {
+        if in_place {
+            output.push_str("\x1B[1;1H");
+        }
+        output += &view.frame_within(renderer(), (columns, rows));
+    }
+
+    // Lines drawn in place are cleared to their end, since a longer one may have been there
+    let end_of_line = if in_place { "\x1B[K\r\n" } else { "\r\n" };
+    if !controls.viewport.is_whole(grid) {
+        output += &format!("{}{end_of_line}", controls.viewport.describe(grid));
+    }
+    let status = t!(
+        "animation.status",
+        generation = grid.generation,
+        state = state,
+        percent = format!("{:.1}", grid.fill_percent()),
+        fps = format!("{:.1}", controls.fps),
+        rule = grid.rule()
+    );
+    output += &format!("{status}{end_of_line}");
+    let settings = t!(
+        "animation.settings",
+        colorshift = config.colorshift,
+        spread_chance = config.spread_chance,
+        temperature = config.temperature,
+        brush = controls.brush,
+        rewindable = controls.rewindable
+    );
+    output += &format!("{settings}{end_of_line}");
+    output += &format!(
+        "{}{end_of_line}",
+        t!("animation.keys", big_rewind = BIG_REWIND)
+    );
+    output += &format!("{}{end_of_line}", t!("animation.mouse"));
+    // Whatever's left under the status, like a breakpoint's notice, is cleared
+    if in_place {
+        output.push_str("\x1B[J");
+    }
+    output
+}
+
+// The output drawing the characters of the grid that fit in (columns, rows) and differ from the
+// ones `shown`, moving the cursor to each run of them, then under the frame. When the frame's size
+// changed (or it's the first) the screen is cleared and the whole frame drawn instead.
+fn draw_changes(grid: &Grid, (columns, rows): (usize, usize), shown: &mut ShownFrame) -> String {
+    let renderer = renderer();
+    let (drawn_columns, drawn_rows) = grid.drawn_size(renderer);
+    let frame: ShownFrame = (0..rows.min(drawn_rows))
+        .map(|row| {
+            (0..columns.min(drawn_columns))
+                .map(|column| grid.draw_char(renderer, row, column))
+                .collect()
+        })
+        .collect();
+
+    let mut output = String::new();
+    let same_size =
+        frame.len() == shown.len() && frame.first().map(Vec::len) == shown.first().map(Vec::len);
+    // Rows and columns of the terminal count from 1, and the grid starts inside its frame
+    let offset = renderer.frame_width();
+    if !same_size {
+        output.push_str("\x1B[2J");
+        shown.clear();
+        if offset > 0 {
+            output +=
+                &draw_frame_around(renderer, (frame.first().map_or(0, Vec::len), frame.len()));
+        }
+    }
+    for (row, chars) in frame.iter().enumerate() {
+        let mut column = 0;
+        while column < chars.len() {
+            let changed = |column: usize| {
+                shown
+                    .get(row)
+                    .is_none_or(|old| old[column] != chars[column])
+            };
+            if !changed(column) {
+                column += 1;
+                continue;
+            }
+            let (row, column_start) = (
+                row + offset + 1,
+                column * renderer.cell_width() + offset + 1,
+            );
+            output += &format!("\x1B[{row};{column_start}H");
+            while column < chars.len() && changed(column) {
+                output.push_str(&chars[column]);
+                column += 1;
+            }
+        }
+    }
+    output += &format!("\x1B[{};1H", frame.len() + 2 * offset + 1);
+    *shown = frame;
+    output
+}
+
+// The frame around a grid of (columns, rows) characters drawn from the top-left corner,
+// as escape sequences moving the cursor along it
+fn draw_frame_around(renderer: &Renderer, (columns, rows): (usize, usize)) -> String {
+    let mut output = format!("\x1B[1;1H{}", renderer.frame_line(columns, true));
+    let right = columns * renderer.cell_width() + 2;
+    for row in 2..rows + 2 {
+        let side = renderer.frame_side();
+        output += &format!("\x1B[{row};1H{side}\x1B[{row};{right}H{side}");
+    }
+    output += &format!(
+        "\x1B[{};1H{}",
+        rows + 2,
+        renderer.frame_line(columns, false)
+    );
+    output
+}
+
+// How many columns and rows of the terminal the grid can be drawn in, if it's drawn to one: the
+// interface's grid pane, or for plain frames the screen less the status lines, and less a line for
+// the note saying which part is shown when the grid doesn't fit whole
+fn grid_area(tui: &Option<Tui>, grid: &Grid) -> Option<(usize, usize)> {
+    if let Some(tui) = tui {
+        return Some(tui.grid_area());
+    }
+    let (columns, rows) = screen_size()?;
+    let frame = 2 * renderer().frame_width();
+    let (columns, rows) = (
+        columns.saturating_sub(frame),
+        rows.saturating_sub(STATUS_LINES + frame),
+    );
+    let (width, height) = visible_cells(renderer(), (columns, rows));
+    let (simulated_width, simulated_height) = grid.simulated_size();
+    if width < simulated_width || height < simulated_height {
+        Some((columns, rows.saturating_sub(1).max(1)))
+    } else {
+        Some((columns, rows))
+    }
+}
+
+// The cell a mouse event happened on in a plain frame, which starts at the top-left corner,
+// inside the frame around it if there is one
+fn plain_cell_at(grid: &Grid, mouse: &MouseEvent) -> Option<(usize, usize)> {
+    let frame = renderer().frame_width();
+    let row = usize::from(mouse.row).checked_sub(frame)?;
+    let column = usize::from(mouse.column).checked_sub(frame)?;
+    let (drawn_columns, drawn_rows) = grid.drawn_size(renderer());
+    if row >= drawn_rows || column / renderer().cell_width() >= drawn_columns {
+        return None;
+    }
+    grid.cell_under(renderer(), row, column)
+}
+
+// Draws the current frame in the full-screen interface if it's open, with `notice` in its sidebar,
+// or else as plain text
+fn draw(
+    tui: &mut Option<Tui>,
+    grid: &Grid,
+    config: &SimulationConfig,
+    controls: &Controls,
+    notice: Option<&str>,
+    shown: &mut ShownFrame,
+) {
+    match tui {
+        Some(tui) => tui.draw(grid, Some(controls), notice),
+        None => draw_frame(grid, config, controls, shown),
+    }
+}
+
+// Applies the brush to the cell at (y, x) when the user clicked or dragged there,
+// or picks up its color on right click
+fn handle_mouse(grid: &mut Grid, brush: &mut Brush, mouse: MouseEvent, (y, x): (usize, usize)) {
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Drag(MouseButton::Left) => {
+            brush.apply(grid, y, x);
+        }
+        MouseEventKind::Down(MouseButton::Right) => brush.color = grid.get_color(y, x),
+        _ => {}
+    }
+}
+
+// Undoes up to `steps` generations, or as many as the history still holds
+fn rewind(grid: &mut Grid, history: &mut History, steps: u64) {
+    for _ in 0..steps {
+        let Some(changes) = history.pop() else {
+            break;
+        };
+        grid.revert(&changes);
+        grid.generation -= 1;
+    }
+}
+
+/// How an animated run is shown and controlled
+pub struct Animation<'a> {
+    /// Generations to pause on until a key is pressed
+    pub break_at: &'a [u64],
+    /// How many recent generations can be rewound
+    pub history_size: usize,
+    pub draw_every: DrawEvery,
+    /// Where to record the animation as an asciinema cast, if anywhere
+    pub cast: Option<&'a Path>,
+    /// Captures frames for a timelapse, if one is being made
+    pub timelapse: Option<&'a mut Timelapse>,
+}
+
+// Starts recording the animation of `grid` to `path`, sized like the terminal, or if it isn't one
+// like the plain frames. Recording is skipped with a warning when the file can't be written.
+fn start_cast(path: &Path, grid: &Grid) -> Option<Cast> {
+    let size = screen_size().unwrap_or_else(|| {
+        let (columns, rows) = grid.drawn_size(renderer());
+        let frame = 2 * renderer().frame_width();
+        (
+            columns * renderer().cell_width() + frame,
+            rows + frame + STATUS_LINES + 1,
+        )
+    });
+    match Cast::create(path, size) {
+        Ok(cast) => Some(cast),
+        Err(e) => {
+            eprintln!("{}", t!("run.cast_failed", error = e));
+            None
+        }
+    }
+}
+
+// Runs the simulation while animating it in the terminal, drawing one frame per generation, in the
+// full-screen interface when the terminal supports it (showing the settings from `config`).
+// The animation pauses on each generation listed in `break_at` until the user presses a key.
+// While running, `r` switches to the next spreading rule, space pauses,
+// `u`/`U` rewind through the last `history_size` generations, and `q` stops the simulation early.
+// The mouse can erase or repaint regions with a brush, and the simulation grows around the edits.
+// Frames are drawn as often as `draw_every` says, with the generations in between simulated
+// without being drawn. With a `cast`, each frame is also recorded there as a plain frame.
+// Returns the final state of the grid in-case the user wants to save it as an image.
+pub fn simulation_animated(
+    mut grid: Grid,
+    config: &SimulationConfig,
+    rng: &mut StdRng,
+    yx_coordinate_pairs: &[[usize; 2]],
+    animation: Animation,
+) -> Grid {
+    let Animation {
+        break_at,
+        history_size,
+        draw_every,
+        cast: cast_path,
+        mut timelapse,
+    } = animation;
+    let mut paused = false;
+    let mut cast = cast_path.and_then(|path| start_cast(path, &grid));
+    // Frames are recorded as drawn over the last one, whatever the terminal itself can do
+    let mut cast_shown = ShownFrame::new();
+    let mut cast_error = None;
+
+    let mut history = History::new(history_size);
+    grid.start_recording();
+    // The starting cells aren't part of any generation, so they can't be rewound
+    grid.take_changes();
+    if renderer().draws_new_cells() {
+        grid.track_births();
+    }
+
+    // Raw mode lets hotkeys be read without waiting for Enter.
+    // It isn't available when stdin isn't a terminal, in which case hotkeys are disabled.
+    let raw_mode = terminal::enable_raw_mode().is_ok();
+    if raw_mode {
+        execute!(io::stdout(), EnableMouseCapture).unwrap();
+    }
+    let mut brush = Brush::default();
+    // Without the full-screen interface, frames are printed as plain text
+    let mut tui = if raw_mode && tui::supported() {
+        Tui::start(config).ok()
+    } else {
+        None
+    };
+    // The interface has its own screen. Plain frames drawn in place get the alternate screen,
+    // instead of piling up in the scrollback.
+    let alternate_screen = tui.is_none() && renderer().redraws_in_place();
+    if alternate_screen {
+        enter_alternate_screen();
+    }
+
+    // Set when the frame only has to be drawn again, because the terminal was resized
+    let mut redraw = false;
+    let mut shown = ShownFrame::new();
+    let mut frames = FrameCounter::new();
+    let mut viewport = Viewport::default();
+    let mut per_frame =
+        generations_per_frame(draw_every, grid.frametime, Duration::ZERO, Duration::ZERO);
+    loop {
+        let frame_start = Instant::now();
+        let mut unfinished = true;
+        let stepped = !paused && !redraw;
+        // Skipped generations stop at breakpoints and the end, so those get drawn
+        let mut generations = 0;
+        if stepped {
+            while generations < per_frame {
+                unfinished = grid.step(yx_coordinate_pairs, rng);
+                history.push(grid.take_changes());
+                if let Some(timelapse) = timelapse.as_deref_mut() {
+                    timelapse.capture(&grid);
+                }
+                generations += 1;
+                if !unfinished || break_at.contains(&grid.generation) {
+                    break;
+                }
+            }
+        }
+        let step_time = frame_start.elapsed();
+
+        // Redrawing the same frame for a resize doesn't count towards the framerate
+        if !redraw {
+            frames.tick();
+        }
+        redraw = false;
+        // The viewport is fitted to the terminal each frame, in case it was resized
+        let visible = grid_area(&tui, &grid).map_or((usize::MAX, usize::MAX), |area| {
+            visible_cells(renderer(), area)
+        });
+        viewport.place(&grid, visible);
+        let controls = Controls {
+            paused,
+            brush: &brush,
+            rewindable: history.len(),
+            fps: frames.fps(),
+            viewport,
+        };
+        draw(&mut tui, &grid, config, &controls, None, &mut shown);
+        if let Some(recording) = &mut cast {
+            let output = plain_frame(&grid, config, &controls, &mut cast_shown, true);
+            // Errors can't be shown while the animation is on screen, so they wait until the end
+            if let Err(e) = recording.record(&output) {
+                cast_error = Some(e);
+                cast = None;
+            }
+        }
+        if stepped {
+            per_frame = generations_per_frame(
+                draw_every,
+                grid.frametime,
+                frame_start.elapsed() - step_time,
+                step_time / generations as u32,
+            );
+        }
+
+        if stepped && break_at.contains(&grid.generation) {
+            let notice = t!("animation.breakpoint", generation = grid.generation);
+            if tui.is_some() {
+                draw(
+                    &mut tui,
+                    &grid,
+                    config,
+                    &controls,
+                    Some(&notice),
+                    &mut shown,
+                );
+                tui::wait_for_key_press();
+            } else {
+                wait_for_key(&notice, raw_mode);
+            }
+        }
+        if !unfinished {
+            // The interface takes the finished grid off the screen when it closes,
+            // so it stays up until the user has had a look
+            if tui.is_some() {
+                let notice = t!("tui.finished");
+                draw(
+                    &mut tui,
+                    &grid,
+                    config,
+                    &controls,
+                    Some(&notice),
+                    &mut shown,
+                );
+                tui::wait_for_key_press();
+            }
+            break;
+        }
+
+        // Sleep between frames while listening for hotkeys and brush strokes, for as long as the
+        // generations simulated for this frame take at the framerate, less the time it took
+        let interval = grid.frametime * generations.max(1) as u32;
+        let timeout = interval.saturating_sub(frame_start.elapsed());
+        let key = match read_event(timeout, raw_mode) {
+            Some(Event::Key(key)) => key,
+            // The interface fits itself to the new size when it's drawn. Plain frames are fitted
+            // too, but images are only drawn over, so anything they leave on screen is cleared.
+            Some(Event::Resize(..)) => {
+                if tui.is_none() && renderer().redraws_in_place() {
+                    print!("\x1B[2J");
+                }
+                shown.clear();
+                redraw = true;
+                continue;
+            }
+            Some(Event::Mouse(mouse)) => {
+                let view = viewport.view(&grid);
+                let cell = match &tui {
+                    Some(tui) => tui.cell_at(&view, mouse.column, mouse.row),
+                    None => plain_cell_at(&view, &mouse),
+                };
+                if let Some(cell) = cell {
+                    let cell = viewport.grid_cell(&grid, cell);
+                    handle_mouse(&mut grid, &mut brush, mouse, cell);
+                }
+                // Brush strokes are undone together with the generation they were made after
+                history.append_to_latest(grid.take_changes());
+                continue;
+            }
+            _ => continue,
+        };
+        if is_quit_key(&key) {
+            break;
+        }
+        match key.code {
+            KeyCode::Char('r') => grid.set_rule(grid.rule().next()),
+            KeyCode::Char(' ') => paused = !paused,
+            // Rewinding pauses, so the user can tweak the rule before continuing
+            KeyCode::Char('u') | KeyCode::Left => {
+                rewind(&mut grid, &mut history, 1);
+                paused = true;
+            }
+            KeyCode::Char('U') => {
+                rewind(&mut grid, &mut history, BIG_REWIND);
+                paused = true;
+            }
+            KeyCode::Char('b') => brush.toggle_tool(),
+            KeyCode::Char(']') => brush.grow(),
+            KeyCode::Char('[') => brush.shrink(),
+            KeyCode::Char('+' | '=') => viewport.zoom_in(),
+            KeyCode::Char('-') => viewport.zoom_out(),
+            KeyCode::Char('w') => viewport.pan(-1, 0),
+            KeyCode::Char('a') => viewport.pan(0, -1),
+            KeyCode::Char('s') => viewport.pan(1, 0),
+            KeyCode::Char('d') => viewport.pan(0, 1),
+            _ => {}
+        }
+    }
+
+    if raw_mode {
+        execute!(io::stdout(), DisableMouseCapture).unwrap();
+        if let Some(tui) = tui {
+            tui.finish();
+        }
+        terminal::disable_raw_mode().unwrap();
+    }
+    // The last frame is left on the normal screen, where it's kept
+    if alternate_screen {
+        leave_alternate_screen();
+        print!("{}", grid.frame());
+    }
+    if let (Some(path), Some(cast)) = (cast_path, cast) {
+        match cast.finish() {
+            Ok(()) => status!("{}", t!("run.cast_saved", path = path.display())),
+            Err(e) => cast_error = Some(e),
+        }
+    }
+    if let Some(e) = cast_error {
+        eprintln!("{}", t!("run.cast_failed", error = e));
+    }
+    grid
+}
+
+// Runs the simulation without visualizing it in the terminal.
+// This is faster, and helpful if you only want the final output image.
+// `snapshots` says which generations get a snapshot image saved, if any.
+// `progress` says how it shows how far along it is.
+// `preview_every` says how often the grid is printed, shrunk to fit the terminal, if ever.
+// A `timelapse` gets a frame captured on each generation it asks for.
+// Returns the final state of the grid in-case the user wants to save it as an image.
+pub fn simulation_in_background(
+    mut grid: Grid,
+    rng: &mut StdRng,
+    yx_coordinate_pairs: &[[usize; 2]],
+    snapshots: Option<&Snapshots>,
+    progress: Progress,
+    preview_every: Option<PreviewEvery>,
+    mut timelapse: Option<&mut Timelapse>,
+) -> Grid {
+    // Only show the resulting art after its finished rendering (much faster!)
+    status!("{}", t!("run.background"));
+    let mut reported_tenths = 0;
+    let mut last_preview = Instant::now();
+    let mut bar = (progress == Progress::Bar).then(ProgressBar::new);
+
+    loop {
+        let unfinished = grid.step(yx_coordinate_pairs, rng);
+        if let Some(timelapse) = timelapse.as_deref_mut() {
+            timelapse.capture(&grid);
+        }
+
+        if progress == Progress::Lines {
+            let percent = grid.fill_percent();
+            let tenths = (percent / 10.0) as u32;
+            if tenths > reported_tenths || !unfinished {
+                reported_tenths = tenths;
+                let percent = format!("{percent:.0}");
+                let generation = grid.generation;
+                status!(
+                    "{}",
+                    t!("run.progress", generation = generation, percent = percent)
+                );
+            }
+        }
+
+        let preview_due = match preview_every {
+            Some(PreviewEvery::Generations(generations)) => {
+                grid.generation.is_multiple_of(generations)
+            }
+            Some(PreviewEvery::Interval(interval)) => last_preview.elapsed() >= interval,
+            None => false,
+        };
+        // Anything else printed goes on the bar's line, which the bar then moves under
+        let printing = preview_due || snapshots.is_some_and(|s| s.at.contains(&grid.generation));
+        if let Some(bar) = bar.as_mut().filter(|_| printing) {
+            bar.clear();
+        }
+        if preview_due {
+            print_preview(&grid);
+            last_preview = Instant::now();
+        }
+
+        if let Some(snapshots) = snapshots.filter(|s| s.at.contains(&grid.generation)) {
+            status!("{}", t!("run.snapshot", generation = grid.generation));
+            save_image(
+                &grid,
+                &snapshot_path(snapshots.output, grid.generation),
+                snapshots.options,
+                snapshots.overwrite,
+                false,
+            );
+        }
+        let stopping = !unfinished || INTERRUPTED.load(Ordering::SeqCst);
+        if let Some(bar) = &mut bar {
+            bar.update(&grid, printing || stopping);
+            if stopping {
+                bar.finish();
+            }
+        }
+        if stopping {
+            return grid;
+        }
+    }
+}
+
+// Prints `grid` under a line saying how far along it is, shrunk to fit the terminal by averaging
+// blocks of cells when it's bigger
+fn print_preview(grid: &Grid) {
+    let percent = format!("{:.0}", grid.fill_percent());
+    status!(
+        "{}",
+        t!(
+            "run.preview",
+            generation = grid.generation,
+            percent = percent
+        )
+    );
+    let small = screen_size().and_then(|(columns, rows)| {
+        let area = (columns, rows.saturating_sub(PREVIEW_STATUS_LINES));
+        shrink_to_fit(grid, renderer(), area)
+    });
+    status!("{}", small.as_ref().unwrap_or(grid).frame().trim_end());
+}
+
+// fn save_vec_as_image(v: &Vec<Vec<[u8; 3]>>, filename: &str) {
+//     let height = v.len();
+//     let width = v[0].len();
+
+//     // Try using the Image crate to save the image at the desired location
+//     let img = image::ImageBuffer::from_fn(
+//         width.try_into().unwrap(),
+//         height.try_into().unwrap(),
+//         |y, x| {
+//             let y: usize = y.try_into().unwrap();
+//             let x: usize = x.try_into().unwrap();
+//             image::Rgb(v[y][x])
+//         },
+//     );
+
+//     // Print whether saving the image succeeded or not.
+//     if let Err(e) = img.save(&format!("output_images/{filename}")) {
+//         println!("Sorry, the file wasn't able to because of this error -> {e:?}")
+//     } else {
+//         println!("{filename} was saved in the output_images directory")
+//     }
+// }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draw_every_takes_generations_or_auto() {
+        for (s, expected) in [
+            ("auto", DrawEvery::Auto),
+            (" AUTO ", DrawEvery::Auto),
+            ("1", DrawEvery::Generations(1)),
+            (" 12 ", DrawEvery::Generations(12)),
+        ] {
+            assert_eq!(s.parse::<DrawEvery>(), Ok(expected), "{s}");
+        }
+        for s in ["0", "-1", "", "1.5", "2s", "automatic"] {
+            assert!(s.parse::<DrawEvery>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn preview_every_takes_generations_or_seconds() {
+        for (s, expected) in [
+            ("100", PreviewEvery::Generations(100)),
+            ("30s", PreviewEvery::Interval(Duration::from_secs(30))),
+            (" 30 s ", PreviewEvery::Interval(Duration::from_secs(30))),
+            ("0.5s", PreviewEvery::Interval(Duration::from_millis(500))),
+        ] {
+            assert_eq!(s.parse::<PreviewEvery>(), Ok(expected), "{s}");
+        }
+        for s in [
+            "0", "0s", "-1s", "infs", "NaNs", "s", "", "10m", "1.5", "-3",
+        ] {
+            assert!(s.parse::<PreviewEvery>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn auto_drawing_skips_what_the_terminal_cant_keep_up_with() {
+        let ms = Duration::from_millis;
+        let frametime = ms(10);
+        assert_eq!(
+            generations_per_frame(DrawEvery::Generations(7), frametime, ms(500), ms(0)),
+            7
+        );
+        let auto = |draw_time, step_time| {
+            generations_per_frame(DrawEvery::Auto, frametime, draw_time, step_time)
+        };
+        // Fast terminals draw every generation
+        assert_eq!(auto(ms(0), ms(0)), 1);
+        assert_eq!(auto(ms(10), ms(0)), 1);
+        // A frame taking 30ms to draw lets 3 generations go by at 100 fps
+        assert_eq!(auto(ms(30), ms(0)), 3);
+        // Half of each generation's time goes to simulating, leaving half to draw in
+        assert_eq!(auto(ms(30), ms(5)), 6);
+        // Never more than MAX_FRAME_INTERVAL (250ms) apart, even when nothing is left to draw in
+        assert_eq!(auto(ms(1000), ms(0)), 25);
+        assert_eq!(auto(ms(1), ms(10)), 25);
+        // Slow framerates draw every generation, however long drawing takes
+        assert_eq!(
+            generations_per_frame(DrawEvery::Auto, ms(1000), ms(5000), ms(0)),
+            1
+        );
+    }
+}
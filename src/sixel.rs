@@ -0,0 +1,170 @@
+// Sixel rendering: the grid drawn as actual pixels on terminals that show Sixel graphics, laid out
+// as `graphics.rs` describes. Sixel images pick their colors from a palette of at most 256, so grids with more colors than that are
+// drawn with the 256 that come closest, which is rarely visible.
+
+use std::collections::HashMap;
+
+use crate::{graphics, grid::Grid};
+
+// Most colors a Sixel image can have, as terminals support it
+const MAX_COLORS: usize = 256;
+// Sixel characters stand for a column of 6 pixels, bit 0 being the top one
+const SIXEL_HEIGHT: usize = 6;
+const SIXEL_OFFSET: u8 = 0x3F;
+
+/// The simulated cells of `grid` as a Sixel image, scaled up to fit the terminal,
+/// followed by a line break so text printed after it starts underneath
+pub fn image(grid: &Grid) -> String {
+    let (columns, rows) = grid.simulated_size();
+    let scale = graphics::scale(grid);
+    let (palette, indices) = quantize(grid);
+
+    let mut sixel = format!("\x1BPq\"1;1;{};{}", columns * scale, rows * scale);
+    for (index, color) in palette.iter().enumerate() {
+        // Sixel colors are given in percent
+        let [red, green, blue] = color.map(|c| u32::from(c) * 100 / 255);
+        sixel += &format!("#{index};2;{red};{green};{blue}");
+    }
+
+    // Each band of 6 pixel rows is drawn one color at a time, the cursor going back to the start
+    // of the band ($) between colors and down to the next band (-) after them
+    let height = rows * scale;
+    for top in (0..height).step_by(SIXEL_HEIGHT) {
+        // The sixel of every color in the band, for each cell across
+        let mut bands: HashMap<usize, Vec<u8>> = HashMap::new();
+        for y in top..(top + SIXEL_HEIGHT).min(height) {
+            let bit = 1 << (y - top);
+            for (x, &index) in indices[y / scale].iter().enumerate() {
+                bands.entry(index).or_insert_with(|| vec![0; columns])[x] |= bit;
+            }
+        }
+        let mut colors: Vec<_> = bands.into_iter().collect();
+        colors.sort_unstable_by_key(|(index, _)| *index);
+        for (i, (index, sixels)) in colors.iter().enumerate() {
+            if i > 0 {
+                sixel.push('$');
+            }
+            sixel += &format!("#{index}");
+            push_runs(&mut sixel, sixels, scale);
+        }
+        sixel.push('-');
+    }
+    sixel += "\x1B\\\r\n";
+    sixel
+}
+
+// The palette the simulated cells are drawn with, and the palette index of each of them, by row.
+// Grids with too many colors get a palette by median cut: the colors are split into boxes, the
+// box spanning the widest range of a channel being split in two along it where half its cells
+// fall on either side, until there are enough boxes. Each box is drawn as its average color.
+fn quantize(grid: &Grid) -> (Vec<[u8; 3]>, Vec<Vec<usize>>) {
+    let border = grid.boundary().border();
+    let cells: Vec<Vec<[u8; 3]>> = (border..grid.height - border)
+        .map(|y| {
+            (border..grid.width - border)
+                .map(|x| grid.get_color(y, x).as_slice())
+                .collect()
+        })
+        .collect();
+    let mut counts: HashMap<[u8; 3], usize> = HashMap::new();
+    for &color in cells.iter().flatten() {
+        *counts.entry(color).or_default() += 1;
+    }
+
+    let mut boxes = vec![ColorBox::new(counts.into_iter().collect())];
+    while boxes.len() < MAX_COLORS {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.colors.len() > 1)
+            .max_by_key(|(_, b)| b.range);
+        let Some((index, _)) = widest else { break };
+        let mut colors = boxes.swap_remove(index).colors;
+        let channel = ColorBox::widest_channel(&colors);
+        colors.sort_unstable_by_key(|(color, _)| color[channel]);
+        let total: usize = colors.iter().map(|(_, count)| count).sum();
+        let mut seen = 0;
+        let median = colors
+            .iter()
+            .position(|(_, count)| {
+                seen += count;
+                seen * 2 >= total
+            })
+            .unwrap_or(0);
+        // Both halves need a color of their own
+        let rest = colors.split_off((median + 1).min(colors.len() - 1));
+        boxes.push(ColorBox::new(colors));
+        boxes.push(ColorBox::new(rest));
+    }
+
+    let mut lookup = HashMap::new();
+    let palette = boxes
+        .iter()
+        .enumerate()
+        .map(|(index, color_box)| {
+            let mut sum = [0; 3];
+            let mut total = 0;
+            for &(color, count) in &color_box.colors {
+                lookup.insert(color, index);
+                for (sum, channel) in sum.iter_mut().zip(color) {
+                    *sum += usize::from(channel) * count;
+                }
+                total += count;
+            }
+            sum.map(|sum| (sum / total) as u8)
+        })
+        .collect();
+    let indices = cells
+        .iter()
+        .map(|row| row.iter().map(|color| lookup[color]).collect())
+        .collect();
+    (palette, indices)
+}
+
+// Colors that share a palette entry, with how many cells have each,
+// and how wide a range of values they span in the channel that varies most
+struct ColorBox {
+    colors: Vec<([u8; 3], usize)>,
+    range: u8,
+}
+
+impl ColorBox {
+    fn new(colors: Vec<([u8; 3], usize)>) -> Self {
+        let channel = Self::widest_channel(&colors);
+        let range = Self::channel_range(&colors, channel);
+        Self { colors, range }
+    }
+
+    // The channel whose values vary the most between `colors`
+    fn widest_channel(colors: &[([u8; 3], usize)]) -> usize {
+        (0..3)
+            .max_by_key(|&channel| Self::channel_range(colors, channel))
+            .unwrap()
+    }
+
+    fn channel_range(colors: &[([u8; 3], usize)], channel: usize) -> u8 {
+        let values = colors.iter().map(|(color, _)| color[channel]);
+        values.clone().max().unwrap_or(0) - values.min().unwrap_or(0)
+    }
+}
+
+// Appends the sixels of one color in a band, each repeated `scale` times, with runs of the same
+// sixel compressed (!count). Pixels the color doesn't cover at the end of the band are left out.
+fn push_runs(sixel: &mut String, sixels: &[u8], scale: usize) {
+    let end = sixels.iter().rposition(|&s| s != 0).map_or(0, |i| i + 1);
+    let mut i = 0;
+    while i < end {
+        let run = sixels[i..end]
+            .iter()
+            .take_while(|&&s| s == sixels[i])
+            .count();
+        let character = char::from(SIXEL_OFFSET + sixels[i]);
+        let count = run * scale;
+        if count > 3 {
+            *sixel += &format!("!{count}{character}");
+        } else {
+            sixel.extend(std::iter::repeat_n(character, count));
+        }
+        i += run;
+    }
+}
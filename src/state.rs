@@ -0,0 +1,314 @@
+// Saved runs, so a simulation of a huge canvas can be stopped and picked up again later,
+// even after a reboot. A state file holds the settings and every cell of the grid:
+//
+//   "SCCASTATE" and a format version byte
+//   the header (settings, generation and colony count) as JSON, after its length as a u32
+//   every cell row by row: alive (1 byte), red, green, blue (1 byte each), colony (u32)
+//
+// Numbers are little-endian.
+//
+// Runs can be resumed from grid dumps too (see `scgrid.rs`), which are quicker to write but don't
+// hold the settings.
+
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::SimulationConfig,
+    grid::{Grid, RgbColor, NO_COLONY},
+    scgrid::{self, GridDump},
+};
+
+const MAGIC: &[u8] = b"SCCASTATE";
+const FORMAT_VERSION: u8 = 1;
+
+// Bytes each cell takes up: alive, red, green, blue and a 4 byte colony
+const CELL_BYTES: usize = 8;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Header {
+    config: SimulationConfig,
+    generation: u64,
+    colonies: u32,
+}
+
+/// A run loaded from a state file, ready to be continued
+#[derive(Debug)]
+pub struct SavedRun {
+    /// The settings the run was started with, including its seed
+    pub config: SimulationConfig,
+    generation: u64,
+    colonies: u32,
+    cells: Vec<(bool, RgbColor, u32)>,
+}
+
+impl SavedRun {
+    /// The saved grid, continuing with `config` (the saved settings, possibly with some changed).
+    /// Grids can't be resized, so `config` has to keep the saved width and height.
+    pub fn into_grid(self, config: &SimulationConfig) -> Result<Grid, StateError> {
+        if (config.width, config.height) != (self.config.width, self.config.height) {
+            return Err(StateError::Incompatible(format!(
+                "the saved grid is {}x{}, and a resumed run can't change its size",
+                self.config.width, self.config.height
+            )));
+        }
+        Grid::restore(config, self.generation, self.colonies, self.cells)
+            .ok_or_else(|| StateError::Invalid("the file ends partway through the grid".into()))
+    }
+}
+
+// Dumps don't hold the settings or colonies, so the run goes on with the default settings (and
+// any given as flags), and all of its live cells make up one colony
+impl From<GridDump> for SavedRun {
+    fn from(dump: GridDump) -> Self {
+        let any_alive = dump.alive.contains(&true);
+        // Colonies are numbered from 1, since 0 is for dead cells
+        let cells = dump
+            .alive
+            .into_iter()
+            .zip(dump.colors)
+            .map(|(alive, color)| (alive, color, if alive { 1 } else { NO_COLONY }))
+            .collect();
+        Self {
+            config: SimulationConfig {
+                width: dump.width,
+                height: dump.height,
+                ..SimulationConfig::default()
+            },
+            generation: dump.generation,
+            colonies: u32::from(any_alive),
+            cells,
+        }
+    }
+}
+
+/// Why a state file couldn't be saved or loaded
+#[derive(Debug)]
+pub enum StateError {
+    Io(io::Error),
+    /// The file isn't a state file, or is damaged
+    Invalid(String),
+    /// The saved run can't be continued with the given settings
+    Incompatible(String),
+}
+
+impl Display for StateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::Io(e) => write!(f, "{e}"),
+            StateError::Invalid(message) => write!(f, "not a valid state file, {message}"),
+            StateError::Incompatible(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl From<io::Error> for StateError {
+    fn from(e: io::Error) -> Self {
+        StateError::Io(e)
+    }
+}
+
+/// Saves `grid` and the settings it runs with to `path`, so the run can be resumed later
+pub fn save(grid: &Grid, config: &SimulationConfig, path: &Path) -> Result<(), StateError> {
+    let header = Header {
+        config: config.clone(),
+        generation: grid.generation,
+        colonies: grid.colony_count(),
+    };
+    let header = serde_json::to_vec(&header).map_err(io::Error::from)?;
+    let header_len = u32::try_from(header.len())
+        .map_err(|_| StateError::Invalid("the settings are too big to save".into()))?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = BufWriter::new(fs::File::create(path)?);
+    file.write_all(MAGIC)?;
+    file.write_all(&[FORMAT_VERSION])?;
+    file.write_all(&header_len.to_le_bytes())?;
+    file.write_all(&header)?;
+    for (alive, color, colony) in grid.cells() {
+        file.write_all(&[u8::from(alive)])?;
+        file.write_all(&color.as_slice())?;
+        file.write_all(&colony.to_le_bytes())?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+/// Loads a run saved with `save`, or dumped as a .scgrid file with live cells in it
+pub fn load(path: &Path) -> Result<SavedRun, StateError> {
+    if path.extension().is_some_and(|ext| ext == "scgrid") {
+        let dump = scgrid::read(path)?;
+        // Nothing could ever spread in it, so the run would never finish
+        if !dump.alive.contains(&true) {
+            return Err(StateError::Invalid("no cell in it is alive".to_string()));
+        }
+        return Ok(SavedRun::from(dump));
+    }
+    let mut contents = Vec::new();
+    fs::File::open(path)?.read_to_end(&mut contents)?;
+    let invalid = |message: &str| StateError::Invalid(message.to_string());
+
+    let rest = contents
+        .strip_prefix(MAGIC)
+        .ok_or_else(|| invalid("it doesn't start like one"))?;
+    let (&version, rest) = rest
+        .split_first()
+        .ok_or_else(|| invalid("the file is cut short"))?;
+    if version != FORMAT_VERSION {
+        return Err(StateError::Invalid(format!(
+            "it was saved in format version {version}, but only version {FORMAT_VERSION} can be read"
+        )));
+    }
+    let (header_len, rest) = rest
+        .split_first_chunk::<4>()
+        .ok_or_else(|| invalid("the file is cut short"))?;
+    let header_len = u32::from_le_bytes(*header_len) as usize;
+    if rest.len() < header_len {
+        return Err(invalid("the file is cut short"));
+    }
+    let (header, cells) = rest.split_at(header_len);
+    let header: Header = serde_json::from_slice(header)
+        .map_err(|e| StateError::Invalid(format!("its settings can't be read: {e}")))?;
+
+    // A cut short file, or one with anything after the grid, isn't the run that was saved
+    let expected = header
+        .config
+        .width
+        .checked_mul(header.config.height)
+        .and_then(|cells| cells.checked_mul(CELL_BYTES))
+        .ok_or_else(|| invalid("its size is impossible"))?;
+    if cells.len() != expected {
+        return Err(invalid("its length doesn't match its size"));
+    }
+    let cells = cells
+        .chunks_exact(CELL_BYTES)
+        .map(|cell| {
+            let colony = u32::from_le_bytes([cell[4], cell[5], cell[6], cell[7]]);
+            (
+                cell[0] != 0,
+                RgbColor::from([cell[1], cell[2], cell[3]]),
+                colony,
+            )
+        })
+        .collect();
+    Ok(SavedRun {
+        config: header.config,
+        generation: header.generation,
+        colonies: header.colonies,
+        cells,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{grown_grid, temp_path};
+
+    fn config() -> SimulationConfig {
+        SimulationConfig {
+            width: 12,
+            height: 9,
+            starting_live_cells: 4,
+            seed: Some(7),
+            ..SimulationConfig::default()
+        }
+    }
+
+    // Saves a grid grown with `config` and loads it back
+    fn saved_run(config: &SimulationConfig, name: &str) -> (Grid, SavedRun) {
+        let grid = grown_grid(config, 5);
+        let path = temp_path(name);
+        save(&grid, config, &path).unwrap();
+        let run = load(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        (grid, run)
+    }
+
+    #[test]
+    fn saved_runs_restore_the_same_grid() {
+        let config = config();
+        let (grid, run) = saved_run(&config, "restore.state");
+        assert_eq!(run.config, config);
+        let restored = run.into_grid(&config).unwrap();
+
+        assert_eq!(restored.generation, grid.generation);
+        assert_eq!(restored.colony_count(), grid.colony_count());
+        assert!(grid.colony_count() > 1);
+        let cells = |grid: &Grid| -> Vec<(bool, [u8; 3], u32)> {
+            grid.cells()
+                .map(|(alive, color, colony)| (alive, color.as_slice(), colony))
+                .collect()
+        };
+        assert_eq!(cells(&restored), cells(&grid));
+    }
+
+    #[test]
+    fn resized_runs_are_incompatible() {
+        let config = config();
+        let (_, run) = saved_run(&config, "resize.state");
+        let wider = SimulationConfig {
+            width: config.width + 1,
+            ..config
+        };
+        assert!(matches!(
+            run.into_grid(&wider),
+            Err(StateError::Incompatible(_))
+        ));
+    }
+
+    #[test]
+    fn files_of_the_wrong_length_are_refused() {
+        let config = config();
+        let grid = grown_grid(&config, 5);
+        let path = temp_path("length.state");
+        save(&grid, &config, &path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+
+        let mut extra_byte = bytes.clone();
+        extra_byte.push(0);
+        let mut extra_cell = bytes.clone();
+        extra_cell.extend(&bytes[bytes.len() - CELL_BYTES..]);
+        let two_runs = [bytes.as_slice(), bytes.as_slice()].concat();
+        let cut_short = bytes[..bytes.len() - 1].to_vec();
+        let missing_cell = bytes[..bytes.len() - CELL_BYTES].to_vec();
+        for (damaged, name) in [
+            (extra_byte, "extra byte"),
+            (extra_cell, "extra cell"),
+            (two_runs, "two runs"),
+            (cut_short, "cut short"),
+            (missing_cell, "missing cell"),
+        ] {
+            fs::write(&path, damaged).unwrap();
+            assert!(matches!(load(&path), Err(StateError::Invalid(_))), "{name}");
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn grid_dumps_without_live_cells_are_refused() {
+        let mut grid = grown_grid(&config(), 0);
+        for y in 0..grid.height {
+            for x in 0..grid.width {
+                grid.kill_cell(y, x);
+            }
+        }
+        let path = temp_path("dead.scgrid");
+        scgrid::write(&grid, &path).unwrap();
+        assert!(matches!(load(&path), Err(StateError::Invalid(_))));
+
+        // One live cell is enough
+        grid.paint_cell(1, 1, RgbColor::from([1, 2, 3]));
+        scgrid::write(&grid, &path).unwrap();
+        let loaded = load(&path);
+        fs::remove_file(path).unwrap();
+        assert!(loaded.is_ok());
+    }
+}
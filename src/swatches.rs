@@ -0,0 +1,158 @@
+// Palettes of the colors a run ended up with, saved for reuse in other art: GIMP palettes (.gpl),
+// which Inkscape, Krita and Aseprite read too, or Adobe Swatch Exchange files (.ase) for Adobe's
+// apps. A finished grid holds far more colors than a palette is useful with, so they're boiled down
+// to a few representative ones by median cut: the colors are split in two at the median of the
+// channel they vary most in, again and again, and each group becomes its average color.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::grid::{Grid, RgbColor};
+
+/// How many colors saved palettes have by default
+pub const PALETTE_COLORS_DEFAULT: u16 = 16;
+
+// How many colors a line of a GIMP palette shows
+const GPL_COLUMNS: usize = 8;
+
+/// Checks that `s` names a file palettes can be saved as
+pub fn parse_palette_path(s: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(s);
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("gpl") || ext.eq_ignore_ascii_case("ase") => Ok(path),
+        _ => Err("palettes can only be saved as .gpl or .ase files".to_string()),
+    }
+}
+
+/// Up to `count` colors representing the live cells of `grid`, the most common first.
+/// If there are no more than `count` different colors, they're all there as they are.
+pub fn representative_colors(grid: &Grid, count: usize) -> Vec<RgbColor> {
+    let mut counts: HashMap<[u8; 3], u64> = HashMap::new();
+    for (_, color, _) in grid.cells().filter(|(alive, _, _)| *alive) {
+        *counts.entry(color.as_slice()).or_default() += 1;
+    }
+    let mut boxes = vec![counts.into_iter().collect::<Vec<_>>()];
+    // Splits the box spanning the widest range of a channel until there are enough, or every box
+    // is a single color
+    while boxes.len() < count {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| (i, widest_channel(colors)))
+            .max_by_key(|(_, (_, range))| *range);
+        let Some((i, (channel, _))) = widest else {
+            break;
+        };
+        let mut colors = boxes.swap_remove(i);
+        colors.sort_unstable_by_key(|(color, _)| color[channel]);
+        // Split where half the cells are on each side, keeping a color on both
+        let total: u64 = colors.iter().map(|(_, n)| n).sum();
+        let mut seen = 0;
+        let split = colors
+            .iter()
+            .position(|(_, n)| {
+                seen += n;
+                seen * 2 >= total
+            })
+            .map_or(1, |i| i + 1)
+            .clamp(1, colors.len() - 1);
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    let mut averaged: Vec<(RgbColor, u64)> = boxes
+        .iter()
+        .filter(|colors| !colors.is_empty())
+        .map(|colors| average(colors))
+        .collect();
+    averaged.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+    averaged.into_iter().map(|(color, _)| color).collect()
+}
+
+// The channel `colors` vary most in, and by how much
+fn widest_channel(colors: &[([u8; 3], u64)]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let values = colors.iter().map(|(color, _)| color[channel]);
+            let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+            (channel, range)
+        })
+        .max_by_key(|(_, range)| *range)
+        .expect("there are three channels")
+}
+
+// The average of `colors`, weighed by how many cells have each, and how many cells that is
+fn average(colors: &[([u8; 3], u64)]) -> (RgbColor, u64) {
+    let total: u64 = colors.iter().map(|(_, n)| n).sum();
+    let [red, green, blue] = [0, 1, 2].map(|channel| {
+        let sum: u64 = colors.iter().map(|(c, n)| u64::from(c[channel]) * n).sum();
+        ((sum + total / 2) / total) as u8
+    });
+    (RgbColor { red, green, blue }, total)
+}
+
+/// Saves `colors` at `path` as a palette called `name`, a GIMP palette for `.gpl` files and an
+/// Adobe Swatch Exchange file for `.ase` files, creating any missing directories on the way
+pub fn write(colors: &[RgbColor], name: &str, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut contents = Vec::new();
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ase"))
+    {
+        write_ase(colors, &mut contents)?;
+    } else {
+        write_gpl(colors, name, &mut contents)?;
+    }
+    fs::write(path, contents)
+}
+
+// A GIMP palette: a header, then a line per color with its red, green and blue and a name
+fn write_gpl(colors: &[RgbColor], name: &str, mut file: impl Write) -> io::Result<()> {
+    writeln!(file, "GIMP Palette")?;
+    writeln!(file, "Name: {name}")?;
+    writeln!(file, "Columns: {GPL_COLUMNS}")?;
+    writeln!(file, "#")?;
+    for color in colors {
+        let [red, green, blue] = color.as_slice();
+        writeln!(
+            file,
+            "{red:3} {green:3} {blue:3}\t#{red:02x}{green:02x}{blue:02x}"
+        )?;
+    }
+    Ok(())
+}
+
+// An Adobe Swatch Exchange file: a header, then a block per color with its name (UTF-16 with a
+// terminating zero) and its red, green and blue as floats from 0 to 1. Everything is big-endian.
+fn write_ase(colors: &[RgbColor], mut file: impl Write) -> io::Result<()> {
+    file.write_all(b"ASEF")?;
+    file.write_all(&[0, 1, 0, 0])?; // Version 1.0
+    file.write_all(&(colors.len() as u32).to_be_bytes())?;
+    for color in colors {
+        let [red, green, blue] = color.as_slice();
+        let name: Vec<u16> = format!("#{red:02x}{green:02x}{blue:02x}\0")
+            .encode_utf16()
+            .collect();
+        let mut block = Vec::new();
+        block.extend((name.len() as u16).to_be_bytes());
+        block.extend(name.iter().flat_map(|unit| unit.to_be_bytes()));
+        block.extend(b"RGB ");
+        for channel in [red, green, blue] {
+            block.extend((f32::from(channel) / 255.0).to_be_bytes());
+        }
+        block.extend(2u16.to_be_bytes()); // A normal color, not a global or spot one
+        file.write_all(&1u16.to_be_bytes())?; // A color entry
+        file.write_all(&(block.len() as u32).to_be_bytes())?;
+        file.write_all(&block)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,341 @@
+// Parameter sweeps: renders one image for every combination of the given setting ranges,
+// so a region of the parameter space can be explored systematically.
+// Every combination uses the same seed, so the images differ only by their settings.
+
+use std::{path::Path, process, str::FromStr, time::Instant};
+
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+use crate::{
+    cli::SweepArgs,
+    config::{SimulationConfig, OUTPUT_FILENAME_DEFAULT},
+    estimate::{confirm_export_size, image_bytes},
+    export::{save_image, with_filename_suffix, ImageOptions},
+    i18n::t,
+    manifest::save_manifest,
+    simulation::render,
+    verbosity::status,
+};
+
+/// Settings that can be swept (or compared), in the order they appear in filenames
+pub const SWEEPABLE_SETTINGS: [&str; 6] = [
+    "width",
+    "height",
+    "starting_live_cells",
+    "colorshift",
+    "spread_chance",
+    "temperature",
+];
+
+/// Most values a single range can give, so a typo'd range can't try to allocate them all
+pub const MAX_SWEEP_VALUES: usize = 10_000;
+
+/// Most combinations a whole sweep can have, checked before any of them are built
+pub const MAX_SWEEP_COMBINATIONS: usize = 100_000;
+
+/// The values one setting takes during a sweep, parsed from e.g. "colorshift=1..16"
+/// or "spread_chance=0.1..0.9 step 0.1". Both ends of the range are included.
+#[derive(Debug, Clone)]
+pub struct SweepRange {
+    pub key: String,
+    pub values: Vec<f64>,
+}
+
+impl FromStr for SweepRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, range) = s
+            .split_once('=')
+            .ok_or(format!("expected SETTING=START..END, got '{s}'"))?;
+        let key = key.trim().to_string();
+        if !SWEEPABLE_SETTINGS.contains(&key.as_str()) {
+            return Err(format!(
+                "'{key}' can't be swept, expected one of {}",
+                SWEEPABLE_SETTINGS.join(", ")
+            ));
+        }
+
+        let (range, step) = match range.split_once(" step ") {
+            Some((range, step)) => (range, Some(step)),
+            None => (range, None),
+        };
+        let (start, end) = range
+            .split_once("..")
+            .ok_or(format!("expected a range like 1..16, got '{range}'"))?;
+        let number = |s: &str| match s.trim().parse::<f64>() {
+            Ok(number) if number.is_finite() => Ok(number),
+            _ => Err(format!("'{}' isn't a finite number", s.trim())),
+        };
+        let start = number(start)?;
+        let end = number(end)?;
+        let step = step.map(number).transpose()?.unwrap_or(1.0);
+        if step <= 0.0 {
+            return Err(format!("the step must be above 0, got {step}"));
+        }
+        if end < start {
+            return Err(format!("the range {start}..{end} is empty"));
+        }
+
+        // Computing each value from the start (instead of adding up steps) keeps rounding errors
+        // from piling up, and rounding drops the ones left so filenames read "0.3" not "0.30000000000000004"
+        let steps = ((end - start) / step + 1e-9).floor();
+        if steps >= MAX_SWEEP_VALUES as f64 {
+            return Err(format!(
+                "the range {start}..{end} in steps of {step} has more than {MAX_SWEEP_VALUES} values"
+            ));
+        }
+        let steps = steps as usize;
+        let values = (0..=steps)
+            .map(|i| ((start + i as f64 * step) * 1e6).round() / 1e6)
+            .collect();
+        Ok(Self { key, values })
+    }
+}
+
+/// Sets the setting named `key` to `value`, which must be a whole number for integer settings.
+/// `key` has to be one of `SWEEPABLE_SETTINGS`.
+pub fn set_setting(config: &mut SimulationConfig, key: &str, value: f64) -> Result<(), String> {
+    let whole = || {
+        if value.fract() == 0.0 && value >= 0.0 {
+            Ok(value)
+        } else {
+            Err(format!("{key} must be a whole number, got {value}"))
+        }
+    };
+    match key {
+        "width" => config.width = whole()? as usize,
+        "height" => config.height = whole()? as usize,
+        "starting_live_cells" => config.starting_live_cells = whole()? as u32,
+        "colorshift" => {
+            config.colorshift = u8::try_from(whole()? as u64)
+                .map_err(|_| format!("colorshift must be at most 255, got {value}"))?
+        }
+        "spread_chance" => config.spread_chance = value,
+        "temperature" => config.temperature = value,
+        _ => unreachable!("sweep ranges only accept sweepable settings"),
+    }
+    Ok(())
+}
+
+/// How many combinations `ranges` make, or an error if that's more than `MAX_SWEEP_COMBINATIONS`
+pub fn combination_count(ranges: &[SweepRange]) -> Result<usize, String> {
+    ranges
+        .iter()
+        .try_fold(1usize, |count, range| count.checked_mul(range.values.len()))
+        .filter(|count| *count <= MAX_SWEEP_COMBINATIONS)
+        .ok_or(format!(
+            "the ranges make more than {MAX_SWEEP_COMBINATIONS} combinations"
+        ))
+}
+
+// Every combination of the ranges' values, e.g. [[1, 0.1], [1, 0.2], [2, 0.1], [2, 0.2]]
+fn cross_product(ranges: &[SweepRange]) -> Vec<Vec<f64>> {
+    ranges.iter().fold(vec![vec![]], |combinations, range| {
+        combinations
+            .iter()
+            .flat_map(|combination| {
+                range.values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.push(*value);
+                    combination
+                })
+            })
+            .collect()
+    })
+}
+
+// Encodes a combination's settings into a filename suffix, e.g. "_colorshift4_spread_chance0.3"
+fn settings_suffix(ranges: &[SweepRange], combination: &[f64]) -> String {
+    ranges
+        .iter()
+        .zip(combination)
+        .map(|(range, value)| format!("_{}{value}", range.key))
+        .collect()
+}
+
+pub fn run(args: &SweepArgs) {
+    let mut base = match &args.config {
+        Some(path) => {
+            SimulationConfig::from_file(path, args.profile.as_deref()).unwrap_or_else(|e| {
+                eprintln!("{}", t!("run.invalid_settings", error = e));
+                process::exit(1);
+            })
+        }
+        None => SimulationConfig::default(),
+    };
+    // Every image should have the same theme and palette, even if the system's changes midway
+    base.resolve_auto();
+
+    // Sweeping the same setting twice would make every other combination a duplicate
+    let mut ranges: Vec<SweepRange> = Vec::new();
+    for range in &args.vary {
+        if ranges.iter().any(|r| r.key == range.key) {
+            eprintln!("{}", t!("sweep.swept_twice", setting = range.key));
+            process::exit(1);
+        }
+        ranges.push(range.clone());
+    }
+    ranges.sort_by_key(|r| SWEEPABLE_SETTINGS.iter().position(|key| *key == r.key));
+    if let Err(message) = combination_count(&ranges) {
+        eprintln!("{}", t!("sweep.invalid", error = message));
+        process::exit(1);
+    }
+
+    // Build and check every config up front, so a bad combination doesn't stop the sweep halfway
+    let mut configs: Vec<(SimulationConfig, String)> = Vec::new();
+    for combination in cross_product(&ranges) {
+        let mut config = base.clone();
+        config.show_while_running = false;
+        for (range, value) in ranges.iter().zip(&combination) {
+            if let Err(message) = set_setting(&mut config, &range.key, *value) {
+                eprintln!("{}", t!("sweep.invalid", error = message));
+                process::exit(1);
+            }
+        }
+        if let Err(e) = config.validate() {
+            eprintln!("{}", t!("sweep.invalid", error = e));
+            process::exit(1);
+        }
+        configs.push((config, settings_suffix(&ranges, &combination)));
+    }
+
+    let image_options = ImageOptions::new(
+        args.shader.as_deref(),
+        args.scale,
+        args.orientation,
+        args.color_space,
+        &args.format,
+        &args.metadata,
+        args.hash_names,
+    )
+    .unwrap_or_else(|error| {
+        eprintln!("{}", error.message());
+        process::exit(1);
+    })
+    .with_layers(&args.layers);
+
+    let estimated_bytes = configs
+        .iter()
+        .map(|(config, _)| image_bytes(config, args.scale))
+        .fold(0, u64::saturating_add);
+    let what = t!("sweep.size", count = configs.len());
+    if !confirm_export_size(&what, estimated_bytes, args.max_export_size, !args.yes) {
+        return;
+    }
+
+    let seed = args
+        .seed
+        .or(base.seed)
+        .unwrap_or_else(|| thread_rng().gen());
+    status!("{}", t!("sweep.start", count = configs.len(), seed = seed));
+
+    let sweep_timer = Instant::now();
+    let filename = match &args.output {
+        Some(output) => args.format.filename(output).unwrap_or_else(|error| {
+            eprintln!("{}", t!("export.invalid_name", error = error));
+            process::exit(1);
+        }),
+        None => Path::new(OUTPUT_FILENAME_DEFAULT)
+            .with_extension(args.format.extension())
+            .display()
+            .to_string(),
+    };
+    let output = args.out_dir.join(filename);
+    for (index, (config, suffix)) in configs.iter().enumerate() {
+        let count = configs.len();
+        status!(
+            "{}",
+            t!("sweep.combination", index = index + 1, count = count)
+        );
+        let config = SimulationConfig {
+            seed: Some(seed),
+            ..config.clone()
+        };
+        let path = image_options.settings_path(&with_filename_suffix(&output, suffix), &config);
+        if image_options.already_saved(&path) {
+            continue;
+        }
+
+        let timer = Instant::now();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let grid = render(&config, &mut rng, image_options.needs_births());
+        let runtime = timer.elapsed();
+        if let Some(path) = save_image(&grid, &path, &image_options, args.overwrite, false) {
+            save_manifest(
+                &path,
+                &config,
+                grid.generation,
+                runtime,
+                &[],
+                args.overwrite,
+                false,
+            );
+        }
+    }
+    let elapsed = format!("{:?}", sweep_timer.elapsed());
+    status!(
+        "{}",
+        t!("sweep.finished", count = configs.len(), elapsed = elapsed)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_include_both_ends() {
+        let range: SweepRange = "spread_chance=0.1..0.3 step 0.1".parse().unwrap();
+        assert_eq!(range.key, "spread_chance");
+        assert_eq!(range.values, [0.1, 0.2, 0.3]);
+        let range: SweepRange = "colorshift=1..4".parse().unwrap();
+        assert_eq!(range.values, [1.0, 2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn bad_ranges_are_rejected() {
+        for range in [
+            "colorshift",
+            "seed=1..2",
+            "colorshift=1",
+            "colorshift=4..1",
+            "colorshift=1..4 step 0",
+            "colorshift=x..4",
+            "spread_chance=0..inf",
+            "spread_chance=NaN..1",
+            "spread_chance=0..1 step inf",
+            "spread_chance=0..1e12 step 0.1",
+        ] {
+            assert!(range.parse::<SweepRange>().is_err(), "{range}");
+        }
+    }
+
+    #[test]
+    fn ranges_are_capped() {
+        let most = format!("width=1..{MAX_SWEEP_VALUES}");
+        assert_eq!(
+            most.parse::<SweepRange>().unwrap().values.len(),
+            MAX_SWEEP_VALUES
+        );
+        let too_many = format!("width=0..{MAX_SWEEP_VALUES}");
+        assert!(too_many.parse::<SweepRange>().is_err());
+    }
+
+    #[test]
+    fn combinations_are_capped() {
+        let range = |key: &str, count: usize| SweepRange {
+            key: key.to_string(),
+            values: vec![1.0; count],
+        };
+        let fits = [range("width", 100), range("height", 1_000)];
+        assert_eq!(combination_count(&fits), Ok(MAX_SWEEP_COMBINATIONS));
+        let too_many = [range("width", 100), range("height", 1_001)];
+        assert!(combination_count(&too_many).is_err());
+        // Three ranges at the per-range cap make 10^12 combinations, and all six overflow a usize
+        let huge = ["width", "height", "colorshift"].map(|key| range(key, MAX_SWEEP_VALUES));
+        assert!(combination_count(&huge).is_err());
+        let overflowing = SWEEPABLE_SETTINGS.map(|key| range(key, MAX_SWEEP_VALUES));
+        assert!(combination_count(&overflowing).is_err());
+    }
+}
@@ -0,0 +1,824 @@
+// Works out what the terminal can display, so frames are drawn with the best renderer it supports
+// instead of escape sequences it would print as garbage. Terminals can't be asked directly without
+// waiting on a reply, so this goes by the environment variables terminals are known to set.
+
+use std::{
+    env,
+    fmt::{self, Display, Formatter},
+    io::{self, IsTerminal},
+    panic,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Once, OnceLock,
+    },
+};
+
+use clap::ValueEnum;
+use crossterm::{
+    cursor::{Hide, Show},
+    event::DisableMouseCapture,
+    execute,
+    terminal::{disable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use terminfo::capability as cap;
+
+use crate::{
+    ansi::AnsiColor,
+    braille::BRAILLE_CELLS,
+    glyphs::{display_width, pad_to_width, Palette, ASCII_RAMP, EMOJI_SQUARES},
+    grid::{RgbColor, NO_COLONY},
+    i18n::t,
+    verbosity::status,
+    vision::ColorBlindness,
+};
+
+// Characters cells are drawn with in the block style, on terminals with and without unicode
+const ASCII_CELL_CHAR: &str = "#";
+const UNICODE_CELL_CHAR: &str = "█";
+
+// Characters colonies are drawn with in the colony style, reused in order once they run out,
+// and the character for dead cells
+const COLONY_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+const NO_COLONY_CHAR: char = '.';
+
+// Characters the frame around the grid is drawn with, on terminals with and without unicode:
+// the top-left, top-right, bottom-left and bottom-right corners, then the top and bottom edges and
+// the sides
+const UNICODE_FRAME_CHARS: [&str; 6] = ["┌", "┐", "└", "┘", "─", "│"];
+const ASCII_FRAME_CHARS: [&str; 6] = ["+", "+", "+", "+", "-", "|"];
+
+static RENDERER: OnceLock<Renderer> = OnceLock::new();
+// Whether frames are being drawn on the alternate screen, which a panic has to switch back from
+static ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+static RESTORE_ON_PANIC: Once = Once::new();
+
+/// What the terminal the program runs in supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Any color at all. Off on dumb terminals and when `NO_COLOR` is set.
+    pub color: bool,
+    /// 24-bit color, needed to show every cell in its exact color
+    pub truecolor: bool,
+    /// The 256-color palette, which cells are drawn in the closest colors of without 24-bit color.
+    /// Terminals with neither get the 16 basic colors.
+    pub ansi256: bool,
+    /// Characters outside ASCII, like the block used for cells
+    pub unicode: bool,
+    /// Sixel pixel graphics
+    pub sixel: bool,
+    /// The kitty graphics protocol, for showing images at their exact colors
+    pub kitty: bool,
+    /// iTerm2's inline image protocol, which also shows images at their exact colors
+    pub iterm: bool,
+    /// Moving the cursor and switching to the alternate screen, needed to redraw frames in place
+    pub alt_screen: bool,
+}
+
+impl Capabilities {
+    /// Detects the capabilities of the current terminal from the environment
+    pub fn detect() -> Self {
+        let terminfo = terminfo::Database::from_env().ok();
+        Self::from_vars(
+            |name| env::var_os(name).map(|value| value.to_string_lossy().into_owned()),
+            terminfo.as_ref(),
+        )
+    }
+
+    // The capabilities a terminal with the environment variables `var` gives and the terminfo
+    // entry `terminfo` has
+    fn from_vars(
+        var: impl Fn(&str) -> Option<String>,
+        terminfo: Option<&terminfo::Database>,
+    ) -> Self {
+        let is_set = |name: &str| var(name).is_some();
+        let var = |name: &str| var(name).unwrap_or_default();
+        let term = var("TERM").to_lowercase();
+        let term_program = var("TERM_PROGRAM");
+        let dumb = term == "dumb";
+        // Windows terminals don't set TERM, but all of them understand escape sequences nowadays
+        let no_term = term.is_empty() && !cfg!(windows);
+
+        // The terminal's terminfo entry, which says how many colors it has and,
+        // on newer systems, whether it takes 24-bit colors (the Tc or RGB flags)
+        let max_colors: i32 = terminfo
+            .and_then(|info| info.get::<cap::MaxColors>())
+            .map_or(0, Into::into);
+        let terminfo_truecolor =
+            terminfo.is_some_and(|info| info.raw("Tc").is_some() || info.raw("RGB").is_some());
+
+        let color = !dumb && !no_term && !is_set("NO_COLOR");
+        let truecolor = color
+            && (matches!(var("COLORTERM").as_str(), "truecolor" | "24bit")
+                || ["truecolor", "24bit", "direct"]
+                    .iter()
+                    .any(|t| term.contains(t))
+                || matches!(
+                    term_program.as_str(),
+                    "iTerm.app" | "WezTerm" | "vscode" | "Hyper"
+                )
+                || is_set("WT_SESSION")
+                || is_set("KONSOLE_VERSION")
+                || terminfo_truecolor);
+        let ansi256 = color
+            && (truecolor
+                || term.contains("256")
+                || max_colors >= 256
+                || term_program == "Apple_Terminal");
+
+        // The first locale variable that's set decides the encoding, like it does for C programs
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .map(|name| var(name))
+            .find(|value| !value.is_empty())
+            .unwrap_or_default()
+            .to_lowercase();
+        let unicode = cfg!(windows) || locale.contains("utf-8") || locale.contains("utf8");
+
+        let sixel = !dumb
+            && (term.contains("sixel")
+                || ["mlterm", "foot", "contour", "yaft"]
+                    .iter()
+                    .any(|t| term.starts_with(t))
+                || term_program == "WezTerm");
+        let kitty = !dumb
+            && (is_set("KITTY_WINDOW_ID")
+                || term.contains("kitty")
+                || term.contains("ghostty")
+                || matches!(term_program.as_str(), "WezTerm" | "ghostty"));
+        // iTerm2 sets LC_TERMINAL too, which ssh passes on where TERM_PROGRAM doesn't get through
+        let iterm = !dumb
+            && (matches!(term_program.as_str(), "iTerm.app" | "WezTerm" | "mintty")
+                || var("LC_TERMINAL") == "iTerm2");
+
+        Self {
+            color,
+            truecolor,
+            ansi256,
+            unicode,
+            sixel,
+            kitty,
+            iterm,
+            alt_screen: !dumb && !no_term,
+        }
+    }
+}
+
+impl Display for Capabilities {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let yes_no = |supported: bool| if supported { "yes" } else { "no" };
+        write!(
+            f,
+            "color: {}, truecolor: {}, 256 colors: {}, unicode: {}, sixel: {}, kitty graphics: {}, iterm images: {}, alt screen: {}",
+            yes_no(self.color),
+            yes_no(self.truecolor),
+            yes_no(self.ansi256),
+            yes_no(self.unicode),
+            yes_no(self.sixel),
+            yes_no(self.kitty),
+            yes_no(self.iterm),
+            yes_no(self.alt_screen)
+        )
+    }
+}
+
+/// How each cell is drawn in the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum CellStyle {
+    /// A block in the cell's exact color, or the closest one terminals without truecolor have
+    #[default]
+    Block,
+    /// A space on a background of the cell's color, so cells join up into solid rectangles
+    /// without the gaps some fonts leave around blocks, much like the saved image. Colored like
+    /// the block style, which it falls back to without color support
+    Background,
+    /// The colored square emoji closest to the cell's color, which works without color support
+    Emoji,
+    /// A character for the cell's brightness, from ' ' to '@', without any color. Watchable on
+    /// any terminal, even over ssh or with color turned off
+    Ascii,
+    /// A letter or digit for each colony, so colonies can be told apart even without color
+    Colony,
+    /// Blocks of 2x4 cells packed into each character as Braille dots, for previewing grids far
+    /// bigger than the terminal. Each character takes the color of its block's biggest colony
+    Braille,
+    /// Actual pixels, on terminals that show Sixel graphics, with the grid scaled up to fill the
+    /// terminal. Animations are drawn without the statistics sidebar
+    Sixel,
+    /// Actual pixels in their exact colors, on terminals with the kitty graphics protocol
+    /// (kitty, WezTerm, Ghostty), scaled up like Sixel's
+    Kitty,
+    /// Actual pixels in their exact colors, on terminals with iTerm2's inline images
+    /// (iTerm2, WezTerm, mintty), scaled up like Sixel's
+    Iterm,
+}
+
+/// Draws cells in the best way the terminal supports
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    capabilities: Capabilities,
+    // Glyphs to draw cells with instead of colored blocks
+    palette: Option<Palette>,
+    // Characters the block style draws live cells with instead of blocks,
+    // and the one it draws cells born in the latest generation with
+    cell_char: Option<String>,
+    new_cell_char: Option<String>,
+    // Draw a frame around the grid, where the canvas ends
+    frame: bool,
+    // Draw each cell as a space on a background of its color
+    background: bool,
+    // Draw each cell as the character of its colony, instead of by its color
+    colonies: bool,
+    // Pack blocks of cells into Braille characters
+    braille: bool,
+    // Draw the grid as a Sixel image
+    sixel: bool,
+    // Draw the grid as an image through the kitty graphics protocol
+    kitty: bool,
+    // Draw the grid as an iTerm2 inline image
+    iterm: bool,
+    // Describe colors in text instead of showing them, for screen readers and log files
+    accessible: bool,
+    // Show colors as they look with this kind of color blindness
+    color_blindness: Option<ColorBlindness>,
+}
+
+impl Renderer {
+    /// Picks the best renderer for a terminal with the given capabilities
+    pub fn new(capabilities: Capabilities) -> Self {
+        Self {
+            capabilities,
+            palette: None,
+            cell_char: None,
+            new_cell_char: None,
+            frame: false,
+            background: false,
+            colonies: false,
+            braille: false,
+            sixel: false,
+            kitty: false,
+            iterm: false,
+            accessible: false,
+            color_blindness: None,
+        }
+    }
+
+    /// Draws cells in `style`, if the terminal can show it
+    pub fn with_style(self, style: CellStyle) -> Self {
+        let palette = match style {
+            CellStyle::Block
+            | CellStyle::Background
+            | CellStyle::Colony
+            | CellStyle::Braille
+            | CellStyle::Sixel
+            | CellStyle::Kitty
+            | CellStyle::Iterm => None,
+            CellStyle::Emoji => Some(Palette::Nearest(&EMOJI_SQUARES)),
+            CellStyle::Ascii => Some(Palette::Ramp(ASCII_RAMP)),
+        };
+        Self {
+            palette,
+            background: style == CellStyle::Background,
+            colonies: style == CellStyle::Colony,
+            braille: style == CellStyle::Braille,
+            sixel: style == CellStyle::Sixel,
+            kitty: style == CellStyle::Kitty,
+            iterm: style == CellStyle::Iterm,
+            ..self
+        }
+    }
+
+    /// Draws cells with the glyphs in `palette`, if the terminal can show them
+    pub fn with_palette(self, palette: Palette) -> Self {
+        Self {
+            palette: Some(palette),
+            ..self
+        }
+    }
+
+    /// Draws live cells as `cell_char` instead of blocks, and cells born in the latest generation
+    /// as `new_cell_char`, in the block style, if the terminal can show them
+    pub fn with_cell_chars(self, cell_char: Option<String>, new_cell_char: Option<String>) -> Self {
+        Self {
+            cell_char,
+            new_cell_char,
+            ..self
+        }
+    }
+
+    /// Draws a frame around the grid if `frame` is set, showing where the canvas ends
+    pub fn with_frame(self, frame: bool) -> Self {
+        Self { frame, ..self }
+    }
+
+    /// Shows cells in the colors they have for viewers with `color_blindness`, if any
+    pub fn with_color_blindness(self, color_blindness: Option<ColorBlindness>) -> Self {
+        Self {
+            color_blindness,
+            ..self
+        }
+    }
+
+    /// The kind of color blindness cells are shown as seen with, if any
+    pub fn color_blindness(&self) -> Option<ColorBlindness> {
+        self.color_blindness
+    }
+
+    // `color` as it's shown, after any simulated color blindness
+    fn seen(&self, color: RgbColor) -> RgbColor {
+        match self.color_blindness {
+            Some(color_blindness) => color_blindness.simulate(color),
+            None => color,
+        }
+    }
+
+    /// A renderer that writes plain text only: no colors, no cursor movement, and cells are
+    /// described by their hex color code
+    pub fn accessible(capabilities: Capabilities) -> Self {
+        Self {
+            accessible: true,
+            ..Self::new(capabilities)
+        }
+    }
+
+    /// A renderer for saving grids as text files: the same glyphs as this one,
+    /// or emoji if this one draws colored blocks (which text files can't hold)
+    pub fn for_text_export(&self) -> Self {
+        let capabilities = Capabilities {
+            unicode: true,
+            ..self.capabilities
+        };
+        if self.colonies || self.braille {
+            // Colony characters and Braille dots stand on their own,
+            // and color codes would clutter the file
+            let capabilities = Capabilities {
+                color: false,
+                truecolor: false,
+                ansi256: false,
+                ..capabilities
+            };
+            let style = if self.colonies {
+                CellStyle::Colony
+            } else {
+                CellStyle::Braille
+            };
+            return Self::new(capabilities).with_style(style);
+        }
+        let palette = self
+            .palette
+            .clone()
+            .unwrap_or(Palette::Nearest(&EMOJI_SQUARES));
+        Self::new(capabilities).with_palette(palette)
+    }
+
+    /// A renderer for saving grids as ANSI art, which any truecolor terminal shows by printing
+    /// the file: this one's blocks (or spaces, in the background style), characters and frame in
+    /// the cells' exact colors, whatever the terminal the program runs in can show
+    pub fn for_ansi_export(&self) -> Self {
+        let capabilities = Capabilities {
+            color: true,
+            truecolor: true,
+            ansi256: true,
+            unicode: true,
+            sixel: false,
+            kitty: false,
+            iterm: false,
+            alt_screen: false,
+        };
+        let style = if self.background {
+            CellStyle::Background
+        } else {
+            CellStyle::Block
+        };
+        Self::new(capabilities)
+            .with_style(style)
+            .with_cell_chars(self.cell_char.clone(), None)
+            .with_frame(self.frame)
+    }
+
+    // The glyphs cells are actually drawn with. Glyphs beyond ASCII need unicode,
+    // otherwise cells are drawn as blocks.
+    fn usable_palette(&self) -> Option<&Palette> {
+        self.palette
+            .as_ref()
+            .filter(|palette| self.capabilities.unicode || palette.is_ascii())
+    }
+
+    // A character the block style was given, if the terminal can show it.
+    // Characters beyond ASCII need unicode, otherwise cells are drawn as '#'.
+    // The background style draws spaces instead.
+    fn usable_char<'a>(&self, glyph: &'a Option<String>) -> Option<&'a str> {
+        glyph
+            .as_deref()
+            .filter(|glyph| !self.background && (self.capabilities.unicode || glyph.is_ascii()))
+    }
+
+    /// How many terminal columns each cell takes up. Every cell is padded to the same width,
+    /// so rows line up even when some glyphs are wider than others.
+    pub fn cell_width(&self) -> usize {
+        if self.draws_colonies() || self.draws_braille() {
+            return 1;
+        }
+        match self.usable_palette() {
+            Some(palette) => palette.width(),
+            None => [&self.cell_char, &self.new_cell_char]
+                .into_iter()
+                .filter_map(|glyph| self.usable_char(glyph))
+                .map(display_width)
+                .max()
+                .unwrap_or(1)
+                .max(1),
+        }
+    }
+
+    /// A cell drawn in its color, or just the cell's character if the terminal can't show it
+    pub fn cell(&self, color: RgbColor) -> String {
+        if self.accessible {
+            let [red, green, blue] = color.as_slice();
+            return format!("#{red:02x}{green:02x}{blue:02x}");
+        }
+        if let Some(palette) = self.usable_palette() {
+            return pad_to_width(palette.glyph(self.seen(color)), palette.width());
+        }
+
+        if self.colors_background() {
+            let color = self.terminal_color(color).expect("cells are colored");
+            return color.paint_background(&" ".repeat(self.cell_width()));
+        }
+        let cell = match self.usable_char(&self.cell_char) {
+            Some(glyph) => glyph,
+            None if self.capabilities.unicode => UNICODE_CELL_CHAR,
+            None => ASCII_CELL_CHAR,
+        };
+        self.paint(&pad_to_width(cell, self.cell_width()), color)
+    }
+
+    /// Whether cells born in the latest generation are drawn differently (with `new_cell`)
+    pub fn draws_new_cells(&self) -> bool {
+        self.usable_char(&self.new_cell_char).is_some()
+            && self.usable_palette().is_none()
+            && !(self.accessible
+                || self.draws_colonies()
+                || self.draws_braille()
+                || self.draws_image())
+    }
+
+    /// A cell born in the latest generation, drawn like `cell` but with its own character
+    pub fn new_cell(&self, color: RgbColor) -> String {
+        match self.usable_char(&self.new_cell_char) {
+            Some(glyph) if self.draws_new_cells() => {
+                self.paint(&pad_to_width(glyph, self.cell_width()), color)
+            }
+            _ => self.cell(color),
+        }
+    }
+
+    /// A cell that isn't alive, drawn in the background `color`. Glyph palettes that bucket colors
+    /// by hue leave it blank, since the background has no hue of its own.
+    pub fn dead_cell(&self, color: RgbColor) -> String {
+        match self.usable_palette() {
+            Some(palette @ (Palette::Hues(_) | Palette::Ramp(_))) if !self.accessible => {
+                " ".repeat(palette.width())
+            }
+            _ => self.cell(color),
+        }
+    }
+
+    /// Whether cells are drawn as the character of their colony (with `colony_cell`)
+    pub fn draws_colonies(&self) -> bool {
+        self.colonies && !self.accessible
+    }
+
+    /// A cell drawn as the character of its colony, in the cell's color if the terminal can show it
+    pub fn colony_cell(&self, colony: u32, color: RgbColor) -> String {
+        if colony == NO_COLONY {
+            return NO_COLONY_CHAR.to_string();
+        }
+        let index = (colony - 1) as usize % COLONY_CHARS.len();
+        let cell = char::from(COLONY_CHARS[index]).to_string();
+        self.paint(&cell, color)
+    }
+
+    /// Whether blocks of cells are packed into Braille characters (with `braille_cell`).
+    /// They need unicode, otherwise cells are drawn one by one.
+    pub fn draws_braille(&self) -> bool {
+        self.braille && self.capabilities.unicode && !self.accessible
+    }
+
+    /// How many cells each character stands for, across and down
+    pub fn cells_per_char(&self) -> (usize, usize) {
+        if self.draws_braille() {
+            BRAILLE_CELLS
+        } else {
+            (1, 1)
+        }
+    }
+
+    /// A Braille character standing for a block of cells, in `color` if the terminal can show it
+    pub fn braille_cell(&self, braille: char, color: RgbColor) -> String {
+        self.paint(&braille.to_string(), color)
+    }
+
+    /// Whether the grid is drawn as a Sixel image (with `sixel::image`) instead of characters.
+    /// Terminals without Sixel support get blocks.
+    pub fn draws_sixel(&self) -> bool {
+        self.sixel && self.capabilities.sixel && !self.accessible
+    }
+
+    /// Whether the grid is drawn as an image through the kitty graphics protocol (with
+    /// `kitty::image`) instead of characters. Terminals without it get blocks.
+    pub fn draws_kitty(&self) -> bool {
+        self.kitty && self.capabilities.kitty && !self.accessible
+    }
+
+    /// Whether the grid is drawn as an iTerm2 inline image (with `iterm::image`) instead of
+    /// characters. Terminals without them get blocks.
+    pub fn draws_iterm(&self) -> bool {
+        self.iterm && self.capabilities.iterm && !self.accessible
+    }
+
+    /// Whether the grid is drawn as an image, in any of the graphics styles
+    pub fn draws_image(&self) -> bool {
+        self.draws_sixel() || self.draws_kitty() || self.draws_iterm()
+    }
+
+    /// The same renderer without any colors, for interfaces that color cells themselves
+    pub fn uncolored(&self) -> Self {
+        Self {
+            capabilities: Capabilities {
+                color: false,
+                truecolor: false,
+                ansi256: false,
+                ..self.capabilities
+            },
+            ..self.clone()
+        }
+    }
+
+    /// The color the terminal is told to draw `color` in: the color itself (as seen with any
+    /// simulated color blindness), the closest one the terminal has, or None if it has no colors
+    pub fn terminal_color(&self, color: RgbColor) -> Option<AnsiColor> {
+        let color = self.seen(color);
+        let [red, green, blue] = color.as_slice();
+        match self.capabilities {
+            Capabilities {
+                truecolor: true, ..
+            } => Some(AnsiColor::Rgb(red, green, blue)),
+            Capabilities { ansi256: true, .. } => Some(AnsiColor::nearest_256(color)),
+            Capabilities { color: true, .. } => Some(AnsiColor::nearest_16(color)),
+            _ => None,
+        }
+    }
+
+    // `text` in the terminal's version of `color`
+    fn paint(&self, text: &str, color: RgbColor) -> String {
+        match self.terminal_color(color) {
+            Some(color) => color.paint(text),
+            None => text.to_string(),
+        }
+    }
+
+    /// How many characters the frame around the grid takes up on each side: 1 if one is drawn.
+    /// Images and plain text fill the space themselves.
+    pub fn frame_width(&self) -> usize {
+        usize::from(self.frame && !self.accessible && !self.draws_image())
+    }
+
+    /// The frame's line above the grid (`top`) or below it, for a grid `columns` characters across
+    pub fn frame_line(&self, columns: usize, top: bool) -> String {
+        let [top_left, top_right, bottom_left, bottom_right, edge, _] = self.frame_chars();
+        let (left, right) = if top {
+            (top_left, top_right)
+        } else {
+            (bottom_left, bottom_right)
+        };
+        format!("{left}{}{right}", edge.repeat(columns * self.cell_width()))
+    }
+
+    /// The frame's character on either side of each row of the grid
+    pub fn frame_side(&self) -> &'static str {
+        self.frame_chars()[5]
+    }
+
+    fn frame_chars(&self) -> [&'static str; 6] {
+        if self.capabilities.unicode {
+            UNICODE_FRAME_CHARS
+        } else {
+            ASCII_FRAME_CHARS
+        }
+    }
+
+    /// Whether cells are drawn as spaces on a background of their color (with `cell`),
+    /// rather than as characters in it
+    pub fn colors_background(&self) -> bool {
+        self.background && self.colors_cells()
+    }
+
+    /// Whether cells are drawn in their color, rather than as glyphs standing for it
+    pub fn colors_cells(&self) -> bool {
+        self.capabilities.color
+            && !self.accessible
+            && (self.draws_colonies() || self.draws_braille() || self.usable_palette().is_none())
+    }
+
+    /// What the terminal this renderer draws for supports
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// Whether frames can be redrawn in place. Otherwise they're printed one after another.
+    pub fn redraws_in_place(&self) -> bool {
+        self.capabilities.alt_screen && !self.accessible
+    }
+
+    /// Explains every way drawing falls short of the full experience on this terminal
+    pub fn downgrades(&self) -> Vec<String> {
+        let mut downgrades = Vec::new();
+        // Nothing is drawn in accessible mode, so there's nothing to downgrade
+        if self.accessible {
+            return downgrades;
+        }
+        // Glyphs stand for colors themselves, and colony characters for colonies,
+        // so neither needs color support. Images bring their own colors.
+        if !self.capabilities.truecolor
+            && self.usable_palette().is_none()
+            && !self.colonies
+            && !self.draws_image()
+        {
+            downgrades.push(match self.capabilities {
+                Capabilities { ansi256: true, .. } => t!("terminal.no_truecolor"),
+                Capabilities { color: true, .. } => t!("terminal.no_256_colors"),
+                _ => t!("terminal.no_color"),
+            });
+        }
+        if self.sixel && !self.capabilities.sixel {
+            downgrades.push(t!("terminal.no_sixel"));
+        }
+        if self.kitty && !self.capabilities.kitty {
+            downgrades.push(t!("terminal.no_kitty"));
+        }
+        if self.iterm && !self.capabilities.iterm {
+            downgrades.push(t!("terminal.no_iterm"));
+        }
+        if !self.capabilities.unicode {
+            downgrades.push(match self.palette {
+                _ if self.braille => t!("terminal.no_braille"),
+                None => t!("terminal.no_unicode"),
+                Some(_) => t!("terminal.no_glyphs"),
+            });
+        }
+        if !self.capabilities.alt_screen {
+            downgrades.push(t!("terminal.no_cursor"));
+        }
+        downgrades
+    }
+}
+
+/// The renderer for the terminal the program runs in, detected on first use
+pub fn renderer() -> &'static Renderer {
+    RENDERER.get_or_init(|| Renderer::new(Capabilities::detect()))
+}
+
+/// Picks the renderer used from now on, instead of the one detected on first use.
+/// Has to be called before anything is drawn, since the renderer can't change afterwards.
+pub fn init(renderer: Renderer) {
+    let _ = RENDERER.set(renderer);
+}
+
+/// The size of the terminal in columns and rows, if the output goes to one
+pub fn screen_size() -> Option<(usize, usize)> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    let (columns, rows) = crossterm::terminal::size().ok()?;
+    Some((usize::from(columns), usize::from(rows)))
+}
+
+/// Switches to the alternate screen with the cursor hidden, so frames drawn in place don't end up
+/// in the scrollback, until `leave_alternate_screen` is called. A panic before then switches back
+/// and turns raw mode and mouse capture off first, so its message can be read.
+pub fn enter_alternate_screen() {
+    RESTORE_ON_PANIC.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if ALTERNATE_SCREEN.load(Ordering::SeqCst) {
+                let _ = execute!(io::stdout(), DisableMouseCapture);
+                let _ = disable_raw_mode();
+                leave_alternate_screen();
+            }
+            default_hook(info);
+        }));
+    });
+    ALTERNATE_SCREEN.store(true, Ordering::SeqCst);
+    let _ = execute!(io::stdout(), EnterAlternateScreen, Hide);
+}
+
+/// Shows the screen and cursor the way they were before `enter_alternate_screen`
+pub fn leave_alternate_screen() {
+    if ALTERNATE_SCREEN.swap(false, Ordering::SeqCst) {
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+    }
+}
+
+/// Tells the user how drawing was downgraded to suit their terminal, if it was.
+/// Nothing is printed when the output isn't going to a terminal, since nothing is drawn then.
+pub fn report_downgrades() {
+    if !io::stdout().is_terminal() {
+        return;
+    }
+    for downgrade in renderer().downgrades() {
+        status!("{}", t!("terminal.note", downgrade = downgrade));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The capabilities a terminal setting only `vars` has, without a terminfo entry
+    fn capabilities(vars: &[(&str, &str)]) -> Capabilities {
+        Capabilities::from_vars(
+            |name| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            },
+            None,
+        )
+    }
+
+    #[test]
+    fn color_depth_follows_colorterm_and_term() {
+        // (variables, color, 256 colors, truecolor)
+        for (vars, color, ansi256, truecolor) in [
+            (vec![("TERM", "xterm")], true, false, false),
+            (vec![("TERM", "xterm-256color")], true, true, false),
+            (
+                vec![("TERM", "xterm-256color"), ("COLORTERM", "truecolor")],
+                true,
+                true,
+                true,
+            ),
+            (
+                vec![("TERM", "xterm"), ("COLORTERM", "24bit")],
+                true,
+                true,
+                true,
+            ),
+            (vec![("TERM", "xterm-direct")], true, true, true),
+            // COLORTERM values other than the two meaning 24-bit color don't count
+            (
+                vec![("TERM", "xterm"), ("COLORTERM", "yes")],
+                true,
+                false,
+                false,
+            ),
+            (
+                vec![("TERM", "dumb"), ("COLORTERM", "truecolor")],
+                false,
+                false,
+                false,
+            ),
+        ] {
+            let detected = capabilities(&vars);
+            assert_eq!(
+                (detected.color, detected.ansi256, detected.truecolor),
+                (color, ansi256, truecolor),
+                "{vars:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn no_color_turns_off_every_color() {
+        // Set to anything, even nothing
+        for value in ["1", ""] {
+            let detected = capabilities(&[
+                ("TERM", "xterm-256color"),
+                ("COLORTERM", "truecolor"),
+                ("NO_COLOR", value),
+            ]);
+            assert!(!detected.color && !detected.ansi256 && !detected.truecolor);
+            // Redrawing in place doesn't need color
+            assert!(detected.alt_screen);
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn missing_and_dumb_terms_get_nothing() {
+        for vars in [vec![], vec![("TERM", "dumb")]] {
+            let detected = capabilities(&vars);
+            assert!(!detected.color && !detected.alt_screen, "{vars:?}");
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn unicode_follows_the_first_locale_variable_set() {
+        let unicode = |vars: &[(&str, &str)]| capabilities(vars).unicode;
+        assert!(unicode(&[("LANG", "en_US.UTF-8")]));
+        assert!(unicode(&[("LC_ALL", "de_DE.utf8"), ("LANG", "C")]));
+        assert!(!unicode(&[("LC_ALL", "C"), ("LANG", "en_US.UTF-8")]));
+        assert!(!unicode(&[]));
+    }
+}
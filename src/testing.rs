@@ -0,0 +1,32 @@
+// Helpers shared by the unit tests
+
+use std::path::PathBuf;
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::{
+    config::SimulationConfig,
+    grid::Grid,
+    simulation::{starting_grid, yx_coordinate_pairs},
+};
+
+/// A path for a test to write a file at, unique to the test run
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("spreading_colors_{}_{name}", std::process::id()))
+}
+
+/// A grid with `config`'s settings, seeded with 7 and grown for `generations` generations,
+/// so it has live and dead cells and more than one colony
+pub fn grown_grid(config: &SimulationConfig, generations: u64) -> Grid {
+    let config = SimulationConfig {
+        seed: Some(7),
+        ..config.clone()
+    };
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut grid = starting_grid(&config, &mut rng);
+    let cells = yx_coordinate_pairs(config.width, config.height, config.boundary);
+    for _ in 0..generations {
+        grid.step(&cells, &mut rng);
+    }
+    grid
+}
@@ -0,0 +1,497 @@
+// Timelapses of whole runs: a frame of the grid is captured every so many generations and written
+// out as an animation at the end, so the growth itself can be shared and not just the final
+// frame. Frames are kept in memory until then, which `--max-export-size` guards against.
+// They can be streamed into a video as they're captured too (see `video.rs`), each saved as a
+// numbered PNG for putting together with other tools, written to stdout as a stream of PPM
+// images for piping into them, or compressed into a single archive (see `archive.rs`).
+//
+// GIFs play everywhere but only have 256 colors per frame, so smooth gradients come out banded and
+// dithered. APNGs (animated PNGs) keep every color exactly, and play in browsers. Web pages
+// (see `replay.rs`) keep every color too, and can be paused and stepped through.
+//
+// Growth starts fast and slows down as the grid fills, so frames can be captured logarithmically:
+// close together early on and further and further apart later, for timelapses that don't drag.
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Stdout, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    error::{EncodingError, ImageFormatHint},
+    Delay, DynamicImage, Frame, ImageBuffer, ImageError, ImageFormat, ImageResult, Rgb, RgbImage,
+};
+
+use crate::{archive::ArchiveWriter, export::write_netpbm, grid::Grid, replay, video::Video};
+
+// How hard GIF encoding works at picking each frame's 256 colors, from 1 (best) to 30 (fastest)
+const GIF_SPEED: i32 = 10;
+
+/// How many frames timelapses sampled logarithmically or by frame count aim for by default
+pub const TIMELAPSE_FRAMES_DEFAULT: u64 = 100;
+
+/// How the generations a timelapse's frames are captured on are picked, with --timelapse-sampling
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum SamplingMode {
+    /// Every --timelapse-every generations, the default
+    #[default]
+    Every,
+    /// Close together early on, where growth is fast, and further apart as it slows down
+    Log,
+    /// About --timelapse-frames frames, evenly spread over how long the run is expected to take
+    Frames,
+}
+
+/// Which generations a timelapse captures frames on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampling {
+    /// Every this many generations
+    Every(u64),
+    /// Each frame this many times as many generations from the start as the one before
+    Logarithmic(f64),
+}
+
+impl Sampling {
+    /// The sampling `mode` picks, `every` generations apart or aiming for `frames` frames over a
+    /// run expected to take `expected` generations. Runs that aren't expected to end are sampled
+    /// every `every` generations whatever the mode.
+    pub fn new(mode: SamplingMode, every: u64, frames: u64, expected: Option<u64>) -> Self {
+        let frames = frames.max(2);
+        match (mode, expected) {
+            (SamplingMode::Every, _) | (_, None) => Sampling::Every(every.max(1)),
+            (SamplingMode::Frames, Some(expected)) => {
+                Sampling::Every((expected / (frames - 1)).max(1))
+            }
+            (SamplingMode::Log, Some(expected)) => {
+                let ratio = ((expected + 1) as f64).powf(1.0 / (frames - 1) as f64);
+                Sampling::Logarithmic(ratio.max(1.0 + f64::EPSILON))
+            }
+        }
+    }
+
+    /// About how many frames a run of `generations` generations is captured in, counting the
+    /// first and last
+    pub fn frames_over(self, generations: u64) -> u64 {
+        match self {
+            Sampling::Every(every) => (generations / every).saturating_add(2),
+            Sampling::Logarithmic(ratio) => {
+                ((generations as f64 + 1.0).ln() / ratio.ln()).ceil() as u64 + 2
+            }
+        }
+    }
+}
+
+/// Frames of a run captured on the generations its sampling picks, to be saved as an animation,
+/// streamed into a video or saved one by one
+pub struct Timelapse {
+    sampling: Sampling,
+    // The generation the next frame is due on when sampling logarithmically, kept fractional so
+    // rounding doesn't add up
+    next_due: f64,
+    // The frames so far, if they're kept for saving as an animation
+    frames: Option<Vec<RgbImage>>,
+    video: Option<Video>,
+    // The directory each frame is saved in as a numbered PNG, and the first error saving one,
+    // after which the rest aren't saved
+    frame_dir: Option<PathBuf>,
+    frame_error: Option<ImageError>,
+    // Stdout, if each frame is written to it as a PPM image, and the first error writing one,
+    // after which the rest aren't written
+    stdout: Option<BufWriter<Stdout>>,
+    stdout_error: Option<io::Error>,
+    // The archive each frame is compressed into, and the first error writing one, after which
+    // the rest aren't written
+    archive: Option<ArchiveWriter>,
+    archive_error: Option<io::Error>,
+    captured: usize,
+    // The generation the last frame was captured on
+    last_captured: Option<u64>,
+}
+
+impl Timelapse {
+    /// A timelapse capturing frames on the generations `sampling` picks, which goes nowhere until
+    /// it's told to keep its frames or given a video or directory
+    pub fn new(sampling: Sampling) -> Self {
+        Self {
+            sampling,
+            next_due: 0.0,
+            frames: None,
+            video: None,
+            frame_dir: None,
+            frame_error: None,
+            stdout: None,
+            stdout_error: None,
+            archive: None,
+            archive_error: None,
+            captured: 0,
+            last_captured: None,
+        }
+    }
+
+    /// Keeps the frames in memory, so they can be saved as an animation with `save`
+    pub fn keep_frames(self) -> Self {
+        Self {
+            frames: Some(Vec::new()),
+            ..self
+        }
+    }
+
+    /// Streams each frame into `video` as it's captured
+    pub fn with_video(self, video: Video) -> Self {
+        Self {
+            video: Some(video),
+            ..self
+        }
+    }
+
+    /// Saves each frame in `dir` as it's captured, as frame_000001.png, frame_000002.png, ...
+    /// The directory has to exist.
+    pub fn with_frame_dir(self, dir: PathBuf) -> Self {
+        Self {
+            frame_dir: Some(dir),
+            ..self
+        }
+    }
+
+    /// Writes each frame to stdout as a binary PPM image as it's captured, one after another
+    pub fn with_stdout(self) -> Self {
+        Self {
+            stdout: Some(BufWriter::new(io::stdout())),
+            ..self
+        }
+    }
+
+    /// Compresses each frame into `archive` as it's captured
+    pub fn with_archive(self, archive: ArchiveWriter) -> Self {
+        Self {
+            archive: Some(archive),
+            ..self
+        }
+    }
+
+    /// Whether the frames go anywhere
+    pub fn is_recording(&self) -> bool {
+        self.frames.is_some()
+            || self.video.is_some()
+            || self.frame_dir.is_some()
+            || self.stdout.is_some()
+            || self.archive.is_some()
+    }
+
+    /// Captures `grid` if it's on a generation frames are taken on
+    pub fn capture(&mut self, grid: &Grid) {
+        let due = match self.sampling {
+            Sampling::Every(every) => grid.generation.is_multiple_of(every),
+            Sampling::Logarithmic(ratio) => {
+                let due = grid.generation as f64 >= self.next_due;
+                if due {
+                    // Counted from generation 1, since the gaps grow from there
+                    let from = self.next_due.max(grid.generation as f64) + 1.0;
+                    self.next_due = from * ratio - 1.0;
+                }
+                due
+            }
+        };
+        if due {
+            self.capture_now(grid);
+        }
+    }
+
+    /// Captures `grid` whatever generation it's on, unless it was just captured.
+    /// For the last frame, so the timelapse ends on the finished grid.
+    pub fn capture_now(&mut self, grid: &Grid) {
+        if self.last_captured == Some(grid.generation) {
+            return;
+        }
+        self.last_captured = Some(grid.generation);
+        self.captured += 1;
+        let frame = grid_image(grid);
+        if let Some(video) = &mut self.video {
+            video.write_frame(&frame);
+        }
+        if let Some(dir) = self
+            .frame_dir
+            .as_ref()
+            .filter(|_| self.frame_error.is_none())
+        {
+            let path = dir.join(format!("frame_{:06}.png", self.captured));
+            self.frame_error = frame.save(path).err();
+        }
+        // Frames are flushed one by one, so whatever reads them keeps up with the run
+        if let Some(stdout) = self.stdout.as_mut().filter(|_| self.stdout_error.is_none()) {
+            self.stdout_error = write_netpbm(&frame, &mut *stdout, false)
+                .and_then(|()| stdout.flush())
+                .err();
+        }
+        if let Some(archive) = self
+            .archive
+            .as_mut()
+            .filter(|_| self.archive_error.is_none())
+        {
+            self.archive_error = archive.write_frame(&frame, grid.generation).err();
+        }
+        if let Some(frames) = &mut self.frames {
+            frames.push(frame);
+        }
+    }
+
+    /// How many frames were captured
+    pub fn len(&self) -> usize {
+        self.captured
+    }
+
+    /// Whether no frames were captured yet
+    pub fn is_empty(&self) -> bool {
+        self.captured == 0
+    }
+
+    /// Whether every frame was saved in the directory given for them, if one was
+    pub fn finish_frame_dir(&mut self) -> Option<ImageResult<()>> {
+        self.frame_dir
+            .take()
+            .map(|_| self.frame_error.take().map_or(Ok(()), Err))
+    }
+
+    /// Whether every frame was written to stdout, if they were
+    pub fn finish_stdout(&mut self) -> Option<io::Result<()>> {
+        self.stdout
+            .take()
+            .map(|_| self.stdout_error.take().map_or(Ok(()), Err))
+    }
+
+    /// Finishes the archive the frames were compressed into, if there is one
+    pub fn finish_archive(&mut self) -> Option<io::Result<()>> {
+        let archive = self.archive.take()?;
+        Some(match self.archive_error.take() {
+            Some(e) => Err(e),
+            None => archive.finish(),
+        })
+    }
+
+    /// Finishes the video the frames were streamed into, if there is one
+    pub fn finish_video(&mut self) -> Option<io::Result<()>> {
+        self.video.take().map(Video::finish)
+    }
+
+    /// Writes the kept frames to `path` as an animation, see `save_animation`
+    pub fn save(&self, path: &Path, frame_time: Duration) -> ImageResult<()> {
+        save_animation(self.frames.as_deref().unwrap_or_default(), path, frame_time)
+    }
+}
+
+/// Writes `frames` to `path` as an animation that loops forever, each frame shown for
+/// `frame_time`, creating any missing directories on the way. `.png` and `.apng` files are
+/// saved as APNGs, `.html` and `.htm` files as a web page playing them, anything else as a GIF.
+pub fn save_animation(frames: &[RgbImage], path: &Path, frame_time: Duration) -> ImageResult<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let file = BufWriter::new(File::create(path)?);
+    let extension = path.extension().and_then(|ext| ext.to_str());
+    if extension
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png") || ext.eq_ignore_ascii_case("apng"))
+    {
+        return write_apng(frames, file, frame_time);
+    }
+    if extension
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"))
+    {
+        return replay::write_html(frames, file, frame_time);
+    }
+    let mut encoder = GifEncoder::new_with_speed(file, GIF_SPEED);
+    encoder.set_repeat(Repeat::Infinite)?;
+    let delay = Delay::from_saturating_duration(frame_time);
+    for frame in frames {
+        let frame = DynamicImage::ImageRgb8(frame.clone()).into_rgba8();
+        encoder.encode_frame(Frame::from_parts(frame, 0, 0, delay))?;
+    }
+    Ok(())
+}
+
+// Writes `frames` as an APNG in full 24-bit color, looping forever
+fn write_apng(frames: &[RgbImage], file: BufWriter<File>, frame_time: Duration) -> ImageResult<()> {
+    let png_error = |e| {
+        ImageError::Encoding(EncodingError::new(
+            ImageFormatHint::Exact(ImageFormat::Png),
+            e,
+        ))
+    };
+    let Some(first) = frames.first() else {
+        return Ok(());
+    };
+    let mut encoder = png::Encoder::new(file, first.width(), first.height());
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(frames.len() as u32, 0)
+        .map_err(png_error)?;
+    let millis = frame_time.as_millis().clamp(1, u16::MAX.into()) as u16;
+    encoder.set_frame_delay(millis, 1000).map_err(png_error)?;
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    for frame in frames {
+        writer.write_image_data(frame.as_raw()).map_err(png_error)?;
+    }
+    writer.finish().map_err(png_error)
+}
+
+// The whole grid as an image, a pixel per cell
+fn grid_image(grid: &Grid) -> RgbImage {
+    ImageBuffer::from_fn(grid.width as u32, grid.height as u32, |x, y| {
+        Rgb(grid.get_color(y as usize, x as usize).as_slice())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::SimulationConfig, testing::temp_path};
+
+    // The generations a timelapse sampled by `sampling` captures over a run ending on `last`
+    fn captured_generations(sampling: Sampling, last: u64) -> Vec<u64> {
+        let mut grid = Grid::new(&SimulationConfig {
+            width: 4,
+            height: 3,
+            ..SimulationConfig::default()
+        });
+        let mut timelapse = Timelapse::new(sampling);
+        let mut captured = Vec::new();
+        for generation in 0..=last {
+            grid.generation = generation;
+            if generation == last {
+                timelapse.capture_now(&grid);
+            } else {
+                timelapse.capture(&grid);
+            }
+            if timelapse.last_captured == Some(generation) {
+                captured.push(generation);
+            }
+        }
+        assert_eq!(timelapse.len(), captured.len());
+        captured
+    }
+
+    #[test]
+    fn every_mode_keeps_the_first_and_last_frames() {
+        let sampling = Sampling::new(SamplingMode::Every, 10, 100, Some(1000));
+        assert_eq!(sampling, Sampling::Every(10));
+        assert_eq!(captured_generations(sampling, 35), [0, 10, 20, 30, 35]);
+        // A last generation that's due anyway isn't captured twice
+        assert_eq!(captured_generations(sampling, 30), [0, 10, 20, 30]);
+        assert_eq!(
+            Sampling::new(SamplingMode::Every, 0, 100, None),
+            Sampling::Every(1)
+        );
+    }
+
+    #[test]
+    fn frames_mode_spreads_frames_evenly() {
+        let sampling = Sampling::new(SamplingMode::Frames, 5, 11, Some(1000));
+        assert_eq!(sampling, Sampling::Every(100));
+        let captured = captured_generations(sampling, 1000);
+        assert_eq!(captured, (0..=1000).step_by(100).collect::<Vec<_>>());
+        // Runs going on longer than expected get a last frame on top
+        let captured = captured_generations(sampling, 1042);
+        assert_eq!(captured.len(), 12);
+        assert_eq!(captured.last(), Some(&1042));
+        assert!(sampling.frames_over(1042) >= 12);
+
+        // Short runs are captured every generation, not every 0
+        let sampling = Sampling::new(SamplingMode::Frames, 5, 100, Some(10));
+        assert_eq!(sampling, Sampling::Every(1));
+        // Without an expected length there's nothing to spread over
+        assert_eq!(
+            Sampling::new(SamplingMode::Frames, 5, 100, None),
+            Sampling::Every(5)
+        );
+    }
+
+    #[test]
+    fn log_mode_spaces_frames_further_apart() {
+        let sampling = Sampling::new(SamplingMode::Log, 5, 11, Some(1023));
+        let Sampling::Logarithmic(ratio) = sampling else {
+            panic!("{sampling:?} isn't logarithmic");
+        };
+        assert!((ratio - 2.0).abs() < 1e-9, "{ratio}");
+
+        let captured = captured_generations(sampling, 1023);
+        assert_eq!(captured.first(), Some(&0));
+        assert_eq!(captured.last(), Some(&1023));
+        let gaps: Vec<u64> = captured.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        assert!(
+            gaps.windows(2).all(|pair| pair[0] <= pair[1]),
+            "{captured:?}"
+        );
+        // About the 11 frames asked for, which frames_over doesn't undercount
+        assert!((10..=13).contains(&captured.len()), "{captured:?}");
+        assert!(sampling.frames_over(1023) >= captured.len() as u64);
+    }
+
+    // Frames whose pixels depend on their number, so they all differ
+    fn frames() -> Vec<RgbImage> {
+        (0..3)
+            .map(|n| RgbImage::from_fn(4, 3, |x, y| Rgb([n * 100, x as u8 * 60, y as u8 * 80])))
+            .collect()
+    }
+
+    #[test]
+    fn gifs_hold_every_frame() {
+        use image::{codecs::gif::GifDecoder, AnimationDecoder};
+
+        let path = temp_path("timelapse.gif");
+        save_animation(&frames(), &path, Duration::from_millis(50)).unwrap();
+        let decoder = GifDecoder::new(File::open(&path).unwrap()).unwrap();
+        let decoded = decoder.into_frames().collect_frames().unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[0].delay(), Delay::from_numer_denom_ms(50, 1));
+        assert_eq!(decoded[0].buffer().dimensions(), (4, 3));
+    }
+
+    #[test]
+    fn apngs_keep_every_color() {
+        let path = temp_path("timelapse.apng");
+        save_animation(&frames(), &path, Duration::from_millis(40)).unwrap();
+        let decoder = png::Decoder::new(File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!((control.num_frames, control.num_plays), (3, 0));
+
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        for frame in frames() {
+            reader.next_frame(&mut buffer).unwrap();
+            assert_eq!(buffer, frame.into_raw());
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn web_pages_embed_every_frame() {
+        let path = temp_path("timelapse.html");
+        save_animation(&frames(), &path, Duration::from_millis(40)).unwrap();
+        let page = fs::read_to_string(&path).unwrap();
+        fs::remove_file(path).unwrap();
+        // Every frame is a base64 PNG, which starts the same way
+        assert_eq!(page.matches("\"iVBORw0KGgo").count(), 3);
+        assert!(!page.contains("__FRAMES__"));
+    }
+
+    #[test]
+    fn streamed_frames_are_back_to_back_ppms() {
+        let mut stream = Vec::new();
+        for frame in frames() {
+            write_netpbm(&frame, &mut stream, false).unwrap();
+        }
+        let header = b"P6\n4 3\n255\n";
+        let frame_bytes = header.len() + 4 * 3 * 3;
+        assert_eq!(stream.len(), 3 * frame_bytes);
+        for (chunk, frame) in stream.chunks(frame_bytes).zip(frames()) {
+            assert_eq!(&chunk[..header.len()], header);
+            let decoded = image::load_from_memory(chunk).unwrap().into_rgb8();
+            assert_eq!(decoded, frame);
+        }
+    }
+}
@@ -0,0 +1,318 @@
+// The full-screen interface for animations and previews: the grid in one pane and live statistics
+// (generation, fill, spread rate, elapsed time and the settings) in a sidebar, drawn with ratatui.
+// Terminals that can't redraw in place, and accessible output, get plain frames printed one after
+// another instead.
+
+use std::{
+    io::{self, IsTerminal},
+    time::Instant,
+};
+
+use crossterm::event::{self, Event, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Layout, Position},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{
+    ansi::AnsiColor,
+    brush::Brush,
+    config::SimulationConfig,
+    grid::Grid,
+    i18n::t,
+    simulation::BIG_REWIND,
+    terminal::{renderer, Renderer},
+    viewport::{shrink_to_fit, Viewport},
+};
+
+// Width of the statistics sidebar, in columns
+const SIDEBAR_WIDTH: u16 = 38;
+
+/// What the animation's controls are set to, shown in the sidebar under the statistics
+pub struct Controls<'a> {
+    pub paused: bool,
+    pub brush: &'a Brush,
+    /// How many generations can be rewound
+    pub rewindable: usize,
+    /// Frames drawn per second, as measured
+    pub fps: f64,
+    /// The part of the grid that's drawn
+    pub viewport: Viewport,
+}
+
+/// Whether the interface can be shown: it needs a terminal that can redraw in place,
+/// for both input and output. Images can't be placed in its panes, so they're drawn without it.
+pub fn supported() -> bool {
+    renderer().redraws_in_place()
+        && !renderer().draws_image()
+        && io::stdin().is_terminal()
+        && io::stdout().is_terminal()
+}
+
+/// The interface while it's on screen. It takes over the terminal until `finish` is called.
+pub struct Tui {
+    terminal: DefaultTerminal,
+    config: SimulationConfig,
+    started: Instant,
+    // Where the first simulated cell was drawn and how many characters of the grid fit across
+    // and down, for mapping mouse clicks onto cells
+    grid_origin: Position,
+    visible_chars: (usize, usize),
+    // The generation last drawn and how many cells were alive in it, to work out the spread rate
+    last_drawn: Option<(u64, usize)>,
+    spread_rate: usize,
+}
+
+impl Tui {
+    /// Switches the terminal over to the interface, showing the settings from `config`
+    pub fn start(config: &SimulationConfig) -> io::Result<Self> {
+        Ok(Self {
+            terminal: ratatui::try_init()?,
+            config: config.clone(),
+            started: Instant::now(),
+            grid_origin: Position::ORIGIN,
+            visible_chars: (0, 0),
+            last_drawn: None,
+            spread_rate: 0,
+        })
+    }
+
+    /// Draws `grid` with its statistics, the animation's `controls` if there are any,
+    /// and a `notice` the user has to act on (e.g. that a breakpoint was reached)
+    pub fn draw(&mut self, grid: &Grid, controls: Option<&Controls>, notice: Option<&str>) {
+        let alive = grid.alive_count();
+        match self.last_drawn {
+            Some((generation, last_alive)) if grid.generation == generation + 1 => {
+                self.spread_rate = alive.saturating_sub(last_alive);
+            }
+            Some((generation, _)) if grid.generation == generation => {}
+            _ => self.spread_rate = 0,
+        }
+        self.last_drawn = Some((grid.generation, alive));
+
+        let view = controls.map(|controls| controls.viewport.view(grid));
+        self.draw_as(grid, view.as_ref().unwrap_or(grid), controls, notice);
+    }
+
+    // Draws `drawn` in place of `grid`, with the statistics of `grid`
+    fn draw_as(
+        &mut self,
+        grid: &Grid,
+        drawn: &Grid,
+        controls: Option<&Controls>,
+        notice: Option<&str>,
+    ) {
+        let sidebar = self.sidebar_lines(grid, controls, notice);
+        let mut grid_placement = (self.grid_origin, self.visible_chars);
+        self.terminal
+            .draw(|frame| grid_placement = draw_frame(frame, drawn, sidebar))
+            .expect("couldn't draw the interface");
+        (self.grid_origin, self.visible_chars) = grid_placement;
+    }
+
+    /// The cell (y, x) of `grid` drawn at a screen position, if one is drawn there
+    pub fn cell_at(&self, grid: &Grid, column: u16, row: u16) -> Option<(usize, usize)> {
+        let column = usize::from(column.checked_sub(self.grid_origin.x)?);
+        let row = usize::from(row.checked_sub(self.grid_origin.y)?);
+        let (columns, rows) = self.visible_chars;
+        if column / renderer().cell_width() >= columns || row >= rows {
+            return None;
+        }
+        grid.cell_under(renderer(), row, column)
+    }
+
+    /// How many columns and rows the grid's pane has room for inside its border
+    pub fn grid_area(&self) -> (usize, usize) {
+        let size = self.terminal.size().unwrap_or_default();
+        (
+            usize::from(size.width.saturating_sub(SIDEBAR_WIDTH + 2)),
+            usize::from(size.height.saturating_sub(2)),
+        )
+    }
+
+    /// Gives the terminal back, the way it was before `start`
+    pub fn finish(self) {
+        ratatui::restore();
+    }
+
+    // The statistics, settings and controls shown beside the grid
+    fn sidebar_lines(
+        &self,
+        grid: &Grid,
+        controls: Option<&Controls>,
+        notice: Option<&str>,
+    ) -> Vec<Line<'static>> {
+        let config = &self.config;
+        let mut lines = Vec::new();
+        if let Some(notice) = notice {
+            lines.push(Line::from(notice.to_string()).bold());
+            lines.push(Line::default());
+        }
+
+        let percent = format!("{:.1}", grid.fill_percent());
+        let elapsed = format!("{:.1}", self.started.elapsed().as_secs_f64());
+        let seed = config.seed.map_or("-".to_string(), |seed| seed.to_string());
+        lines.extend(
+            [
+                t!("tui.generation", generation = grid.generation),
+                t!("tui.alive", percent = percent),
+                t!("tui.spread_rate", cells = self.spread_rate),
+                t!("tui.elapsed", seconds = elapsed),
+                String::new(),
+                t!("tui.size", width = grid.width, height = grid.height),
+                t!("tui.colorshift", colorshift = config.colorshift),
+                t!("tui.spread_chance", spread_chance = config.spread_chance),
+                t!("tui.temperature", temperature = config.temperature),
+                t!("tui.seed", seed = seed),
+                t!("tui.palette", palette = config.current_palette()),
+                t!("tui.theme", theme = config.theme),
+                t!("tui.rule", rule = grid.rule()),
+            ]
+            .map(Line::from),
+        );
+
+        if let Some(controls) = controls {
+            let state = if controls.paused {
+                t!("animation.paused")
+            } else {
+                t!("animation.running")
+            };
+            let mut control_lines = vec![
+                t!("tui.state", state = state),
+                t!("tui.fps", fps = format!("{:.1}", controls.fps)),
+                t!("tui.brush", brush = controls.brush),
+                t!("tui.rewindable", generations = controls.rewindable),
+            ];
+            if !controls.viewport.is_whole(grid) {
+                control_lines.push(controls.viewport.describe(grid));
+            }
+            control_lines.extend([
+                String::new(),
+                t!("animation.keys", big_rewind = BIG_REWIND),
+                t!("animation.mouse"),
+            ]);
+            lines.extend(control_lines.into_iter().map(Line::from));
+        }
+        lines
+    }
+}
+
+// Draws the grid in one pane and the `sidebar` beside it. Returns where the first simulated cell
+// was drawn and how many characters of the grid fit across and down.
+fn draw_frame(
+    frame: &mut Frame,
+    grid: &Grid,
+    sidebar: Vec<Line<'static>>,
+) -> (Position, (usize, usize)) {
+    let [grid_area, sidebar_area] =
+        Layout::horizontal([Constraint::Min(0), Constraint::Length(SIDEBAR_WIDTH)])
+            .areas(frame.area());
+
+    let block = Block::bordered().title(t!("tui.grid_title"));
+    let inner = block.inner(grid_area);
+    let renderer = renderer();
+    let (columns, rows) = grid.drawn_size(renderer);
+    let visible_chars = (
+        (usize::from(inner.width) / renderer.cell_width()).min(columns),
+        usize::from(inner.height).min(rows),
+    );
+    let lines = grid_lines(grid, renderer, visible_chars);
+    frame.render_widget(Paragraph::new(lines).block(block), grid_area);
+
+    let sidebar = Paragraph::new(sidebar)
+        .wrap(Wrap { trim: true })
+        .block(Block::bordered().title(t!("tui.stats_title")));
+    frame.render_widget(sidebar, sidebar_area);
+    (inner.as_position(), visible_chars)
+}
+
+// The characters of the grid that fit in (columns, rows), one line per row.
+// They're colored with ratatui styles instead of escape codes, which it can't measure.
+fn grid_lines(
+    grid: &Grid,
+    renderer: &Renderer,
+    (columns, rows): (usize, usize),
+) -> Vec<Line<'static>> {
+    let uncolored = renderer.uncolored();
+    (0..rows)
+        .map(|row| {
+            let spans: Vec<Span> = (0..columns)
+                .map(|column| {
+                    let cell = grid.draw_char(&uncolored, row, column);
+                    if !renderer.colors_cells() {
+                        return Span::raw(cell);
+                    }
+                    let color = grid.char_color(renderer, row, column);
+                    match renderer.terminal_color(color) {
+                        Some(color) if renderer.colors_background() => Span::styled(
+                            " ".repeat(renderer.cell_width()),
+                            Style::new().bg(tui_color(color)),
+                        ),
+                        Some(color) => Span::styled(cell, Style::new().fg(tui_color(color))),
+                        None => Span::raw(cell),
+                    }
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+// The ratatui color for a terminal color
+fn tui_color(color: AnsiColor) -> Color {
+    // The basic colors in the order terminals number them
+    const BASIC: [Color; 16] = [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::Gray,
+        Color::DarkGray,
+        Color::LightRed,
+        Color::LightGreen,
+        Color::LightYellow,
+        Color::LightBlue,
+        Color::LightMagenta,
+        Color::LightCyan,
+        Color::White,
+    ];
+    match color {
+        AnsiColor::Rgb(red, green, blue) => Color::Rgb(red, green, blue),
+        AnsiColor::Indexed(index) => Color::Indexed(index),
+        AnsiColor::Basic(index) => BASIC[usize::from(index)],
+    }
+}
+
+/// Shows the finished grid with its statistics until a key is pressed
+pub fn preview(grid: &Grid, config: &SimulationConfig) -> io::Result<()> {
+    let mut tui = Tui::start(config)?;
+    // Grids bigger than the pane are shrunk to fit it, instead of being cut off
+    let small = shrink_to_fit(grid, renderer(), tui.grid_area());
+    tui.draw_as(
+        grid,
+        small.as_ref().unwrap_or(grid),
+        None,
+        Some(&t!("tui.close")),
+    );
+    wait_for_key_press();
+    tui.finish();
+    Ok(())
+}
+
+/// Blocks until a key is pressed. The terminal has to be in raw mode, as it is while the
+/// interface is on screen.
+pub fn wait_for_key_press() {
+    loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => return,
+            Err(_) => return,
+            _ => {}
+        }
+    }
+}
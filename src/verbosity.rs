@@ -0,0 +1,64 @@
+// How much the program tells the user while it runs. Errors always go to stderr, while status
+// messages go through the macros here, so `-q` can silence them and `-v`/`-vv` can add detail.
+// Output the user asked for (prompts, animations, dry runs) is printed regardless.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How much gets printed, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Only errors
+    Quiet,
+    /// Progress and results
+    Normal,
+    /// Every orphan that's spawned too
+    Verbose,
+    /// Every cell that spreads too
+    VeryVerbose,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+impl Verbosity {
+    /// The verbosity picked with `-q` or a number of `-v`s
+    pub fn from_flags(quiet: bool, verbose: u8) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Verbosity::Quiet,
+            (false, 0) => Verbosity::Normal,
+            (false, 1) => Verbosity::Verbose,
+            (false, _) => Verbosity::VeryVerbose,
+        }
+    }
+}
+
+/// Sets how much gets printed from now on
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+/// Whether messages at `level` are printed
+pub fn enabled(level: Verbosity) -> bool {
+    VERBOSITY.load(Ordering::Relaxed) >= level as u8
+}
+
+/// Prints a status message, unless `-q` was given
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if $crate::verbosity::enabled($crate::verbosity::Verbosity::Normal) {
+            println!($($arg)*);
+        }
+    };
+}
+pub use crate::status;
+
+/// Prints a detail only wanted with `-v` or more
+#[macro_export]
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if $crate::verbosity::enabled($crate::verbosity::Verbosity::Verbose) {
+            println!($($arg)*);
+        }
+    };
+}
+pub use crate::verbose;
@@ -0,0 +1,126 @@
+// Video exports: frames are streamed as raw RGB into an ffmpeg process as they're captured, which
+// encodes them into whatever format the output's extension asks for (MP4, WebM, MKV, ...). Unlike
+// GIF and APNG timelapses nothing is kept in memory, so videos of huge runs are fine too.
+// ffmpeg has to be installed and on the PATH.
+
+use std::{
+    io::{self, BufWriter, Read, Write},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+    thread::{self, JoinHandle},
+};
+
+use image::RgbImage;
+
+/// A video being encoded by ffmpeg, which frames are written to as they're captured
+pub struct Video {
+    ffmpeg: Child,
+    input: Option<BufWriter<ChildStdin>>,
+    // Everything ffmpeg printed as errors. It's read as it comes, since ffmpeg would stop once
+    // the pipe filled up, while this waits for it to take the next frame.
+    errors: Option<JoinHandle<String>>,
+    // The first error writing a frame, after which the rest are dropped
+    error: Option<io::Error>,
+}
+
+impl Video {
+    /// Starts encoding a video to `path` from frames of (width, height) pixels, played at `fps`
+    /// frames a second and each pixel scaled up to a square of `scale` pixels
+    pub fn start(
+        path: &Path,
+        (width, height): (usize, usize),
+        fps: f64,
+        scale: u32,
+    ) -> io::Result<Self> {
+        // Most encoders need even sizes, so odd ones get a pixel more. Scaling by nearest neighbor
+        // keeps the cells crisp instead of blurring them together.
+        let filter =
+            format!("scale=trunc(iw*{scale}/2+0.5)*2:trunc(ih*{scale}/2+0.5)*2:flags=neighbor");
+        let mut ffmpeg = Command::new("ffmpeg")
+            .args([
+                "-y",
+                "-loglevel",
+                "error",
+                "-f",
+                "rawvideo",
+                "-pix_fmt",
+                "rgb24",
+            ])
+            .args(["-s", &format!("{width}x{height}"), "-r", &fps.to_string()])
+            .args(["-i", "-", "-vf", &filter, "-pix_fmt", "yuv420p"])
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| match e.kind() {
+                io::ErrorKind::NotFound => io::Error::new(
+                    e.kind(),
+                    "ffmpeg wasn't found, it has to be installed to export videos",
+                ),
+                _ => e,
+            })?;
+        let input = ffmpeg.stdin.take().map(BufWriter::new);
+        let errors = ffmpeg.stderr.take().map(|mut stderr| {
+            thread::spawn(move || {
+                let mut errors = String::new();
+                let _ = stderr.read_to_string(&mut errors);
+                errors
+            })
+        });
+        Ok(Self {
+            ffmpeg,
+            input,
+            errors,
+            error: None,
+        })
+    }
+
+    /// Sends `frame` to be encoded
+    pub fn write_frame(&mut self, frame: &RgbImage) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Some(input) = &mut self.input {
+            if let Err(e) = input.write_all(frame.as_raw()) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// Waits for ffmpeg to encode the last frames and finish the file, returning what went wrong
+    /// if anything did
+    pub fn finish(mut self) -> io::Result<()> {
+        let flushed = match self.input.take() {
+            Some(mut input) => input.flush(),
+            None => Ok(()),
+        };
+        // ffmpeg finishes once its input is closed, which dropping it above did
+        let status = self.ffmpeg.wait()?;
+        let errors = self
+            .errors
+            .take()
+            .and_then(|errors| errors.join().ok())
+            .unwrap_or_default();
+        if !status.success() {
+            return Err(io::Error::other(format!(
+                "ffmpeg failed ({status}): {}",
+                errors.trim()
+            )));
+        }
+        match self.error {
+            Some(e) => Err(e),
+            None => flushed,
+        }
+    }
+}
+
+/// Parses a number of frames per second, which has to be above 0
+pub fn parse_fps(s: &str) -> Result<f64, String> {
+    match s.trim().parse::<f64>() {
+        Ok(fps) if fps > 0.0 && fps.is_finite() => Ok(fps),
+        _ => Err(format!(
+            "'{s}' isn't a number of frames per second above 0, like 12"
+        )),
+    }
+}
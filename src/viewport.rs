@@ -0,0 +1,179 @@
+// The part of the grid an animation shows, so grids bigger than the terminal can still be watched
+// live: the view can be zoomed out, each drawn cell then standing for a square of cells, and panned
+// around. Zoomed out views show one cell of each square (the one in its middle) rather than mixing
+// their colors, so they look as sharp as the grid itself.
+
+use crate::{graphics, grid::Grid, i18n::t, terminal::Renderer};
+
+// How much of the view a pan moves it by, as a fraction of its size
+const PAN_FRACTION: usize = 4;
+
+/// Which part of the grid an animation shows, and how far it's zoomed out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    /// How many cells across and down each drawn cell stands for
+    pub zoom: usize,
+    /// The simulated cell in the top-left corner of the view, counting from 0
+    pub top: usize,
+    pub left: usize,
+    // How many drawn cells fit across and down, as of the last `place`
+    visible: (usize, usize),
+    // Whether the zoom still has to be picked so the whole grid fits
+    fit: bool,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            zoom: 1,
+            top: 0,
+            left: 0,
+            visible: (usize::MAX, usize::MAX),
+            fit: true,
+        }
+    }
+}
+
+impl Viewport {
+    /// Fits the view to `visible` drawn cells across and down. The first time, it zooms out as far
+    /// as it takes to show the whole grid. Afterwards it's only moved back over the grid if
+    /// it went past its edges, or the zoom is lowered if it's further out than that.
+    pub fn place(&mut self, grid: &Grid, visible: (usize, usize)) {
+        self.visible = (visible.0.max(1), visible.1.max(1));
+        let whole_grid = self.whole_grid_zoom(grid);
+        if self.fit {
+            self.zoom = whole_grid;
+            self.fit = false;
+        }
+        self.zoom = self.zoom.clamp(1, whole_grid);
+        let (width, height) = grid.simulated_size();
+        self.left = self
+            .left
+            .min(width.saturating_sub(self.visible.0.saturating_mul(self.zoom)));
+        self.top = self
+            .top
+            .min(height.saturating_sub(self.visible.1.saturating_mul(self.zoom)));
+    }
+
+    /// The part of `grid` in view as a grid of its own, zoomed out
+    pub fn view(&self, grid: &Grid) -> Grid {
+        let (width, height) = grid.simulated_size();
+        let size = (
+            (height - self.top).div_ceil(self.zoom).min(self.visible.1),
+            (width - self.left).div_ceil(self.zoom).min(self.visible.0),
+        );
+        grid.sample(size, |y, x| {
+            let (y, x) = self.cell_in_grid(y, x);
+            (y.min(height - 1), x.min(width - 1))
+        })
+    }
+
+    /// The cell of the grid shown at (y, x) of the view's grid, counting the border of both
+    /// (the view always has one, the grid only if it excludes its edges)
+    pub fn grid_cell(&self, grid: &Grid, (y, x): (usize, usize)) -> (usize, usize) {
+        let (width, height) = grid.simulated_size();
+        let border = grid.boundary().border();
+        let (y, x) = self.cell_in_grid(y - 1, x - 1);
+        (y.min(height - 1) + border, x.min(width - 1) + border)
+    }
+
+    /// Whether every cell of `grid` is in view, as it is
+    pub fn is_whole(&self, grid: &Grid) -> bool {
+        let (width, height) = grid.simulated_size();
+        self.zoom == 1
+            && self.top == 0
+            && self.left == 0
+            && self.visible.0 >= width
+            && self.visible.1 >= height
+    }
+
+    /// Which cells of `grid` are in view and how far it's zoomed out
+    pub fn describe(&self, grid: &Grid) -> String {
+        let (width, height) = grid.simulated_size();
+        let span = |start: usize, visible: usize, total: usize| {
+            let end = start
+                .saturating_add(visible.saturating_mul(self.zoom))
+                .min(total);
+            format!("{}-{end}", start + 1)
+        };
+        t!(
+            "animation.view",
+            across = span(self.left, self.visible.0, width),
+            down = span(self.top, self.visible.1, height),
+            total = format!("{width}x{height}"),
+            zoom = self.zoom
+        )
+    }
+
+    /// Zooms in a step, keeping the middle of the view where it is
+    pub fn zoom_in(&mut self) {
+        self.rezoom(self.zoom.saturating_sub(1).max(1));
+    }
+
+    /// Zooms out a step, keeping the middle of the view where it is
+    pub fn zoom_out(&mut self) {
+        self.rezoom(self.zoom + 1);
+    }
+
+    /// Moves the view by a fraction of its size, `down` and `right` being -1, 0 or 1
+    pub fn pan(&mut self, down: isize, right: isize) {
+        let step = |visible: usize| (visible * self.zoom / PAN_FRACTION).max(1) as isize;
+        self.top = self.top.saturating_add_signed(down * step(self.visible.1));
+        self.left = self
+            .left
+            .saturating_add_signed(right * step(self.visible.0));
+    }
+
+    // The simulated cell at the middle of the square the drawn cell (y, x) stands for
+    fn cell_in_grid(&self, y: usize, x: usize) -> (usize, usize) {
+        let middle = self.zoom / 2;
+        (
+            self.top + y * self.zoom + middle,
+            self.left + x * self.zoom + middle,
+        )
+    }
+
+    fn rezoom(&mut self, zoom: usize) {
+        let half = |visible: usize, zoom: usize| visible.saturating_mul(zoom) / 2;
+        let middle = (
+            self.top + half(self.visible.1, self.zoom),
+            self.left + half(self.visible.0, self.zoom),
+        );
+        self.top = middle.0.saturating_sub(half(self.visible.1, zoom));
+        self.left = middle.1.saturating_sub(half(self.visible.0, zoom));
+        self.zoom = zoom;
+    }
+
+    // The lowest zoom showing all of the grid
+    fn whole_grid_zoom(&self, grid: &Grid) -> usize {
+        zoom_to_fit(grid, self.visible)
+    }
+}
+
+/// `grid` shrunk to fit in (columns, rows) characters as `renderer` draws them, each block of
+/// cells averaged into one, or nothing if it fits already. For still previews, where averaging
+/// shows more of the grid than picking a cell of each block would.
+pub fn shrink_to_fit(grid: &Grid, renderer: &Renderer, area: (usize, usize)) -> Option<Grid> {
+    let zoom = zoom_to_fit(grid, visible_cells(renderer, area));
+    (zoom > 1).then(|| grid.downsample(zoom))
+}
+
+/// How many drawn cells across and down fit in (columns, rows) characters with the given
+/// renderer. Images get a pixel for each, the smallest they're drawn at.
+pub fn visible_cells(renderer: &Renderer, (columns, rows): (usize, usize)) -> (usize, usize) {
+    if renderer.draws_image() {
+        return graphics::pixels_within((columns, rows));
+    }
+    let (across, down) = renderer.cells_per_char();
+    (
+        (columns / renderer.cell_width()).saturating_mul(across),
+        rows.saturating_mul(down),
+    )
+}
+
+// The lowest zoom showing all of `grid` in `visible` drawn cells across and down
+fn zoom_to_fit(grid: &Grid, visible: (usize, usize)) -> usize {
+    let (width, height) = grid.simulated_size();
+    let (across, down) = (visible.0.max(1), visible.1.max(1));
+    width.div_ceil(across).max(height.div_ceil(down)).max(1)
+}
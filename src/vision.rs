@@ -0,0 +1,70 @@
+// Simulated color blindness for the terminal preview, so a palette can be checked for how it reads
+// to color-blind viewers before it's exported. Exports always keep the real colors.
+//
+// Colors are converted to linear RGB and run through the matrices of Machado, Oliveira and
+// Fernandes (2009) for a complete lack of each kind of cone, then converted back to sRGB.
+
+use clap::ValueEnum;
+
+use crate::grid::RgbColor;
+
+/// A kind of color blindness the terminal preview can be shown as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorBlindness {
+    /// No red cones, so reds look dark and get confused with greens
+    Protanopia,
+    /// No green cones, the most common kind, so reds and greens get confused
+    Deuteranopia,
+    /// No blue cones, so blues get confused with greens and yellows with pinks
+    Tritanopia,
+}
+
+impl ColorBlindness {
+    /// `color` as it looks with this kind of color blindness
+    pub fn simulate(self, color: RgbColor) -> RgbColor {
+        let matrix = match self {
+            ColorBlindness::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            ColorBlindness::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            ColorBlindness::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        };
+        let linear = color.as_slice().map(to_linear);
+        let [red, green, blue] = matrix.map(|row| {
+            let mixed: f64 = row.iter().zip(linear).map(|(weight, c)| weight * c).sum();
+            to_srgb(mixed)
+        });
+        RgbColor { red, green, blue }
+    }
+}
+
+/// An sRGB channel as linear light, from 0 to 1
+pub fn to_linear(channel: u8) -> f64 {
+    let c = f64::from(channel) / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// Linear light back as an sRGB channel, clamped to the ones that exist
+fn to_srgb(linear: f64) -> u8 {
+    let c = linear.clamp(0.0, 1.0);
+    let c = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
@@ -0,0 +1,328 @@
+// Volumes: the simulation grown in three dimensions instead of two, colors spreading through a
+// block of cells to any of the 26 around each (or fewer, following the neighborhood setting).
+// A volume can't be shown as one image, so it's saved slice by slice: as a numbered PNG per layer
+// of depth, or as an animation sweeping through it from front to back.
+//
+// Volumes have no border, every cell is simulated. Growth stops at the faces of the block unless
+// the boundary wraps, in which case it continues on the opposite face.
+
+use std::{
+    fs,
+    path::Path,
+    process,
+    time::{Duration, Instant},
+};
+
+use image::{
+    imageops::{self, FilterType},
+    ImageBuffer, ImageResult, Rgb, RgbImage,
+};
+use ndarray::Array3;
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+use crate::{
+    cli::VolumeArgs,
+    config::{Boundary, SimulationConfig},
+    estimate::{confirm_export_size, image_bytes},
+    grid::{heated, pick_child, random_cell, RgbColor},
+    i18n::t,
+    palettes::{ColorPalette, PALETTE_JITTER},
+    rules::Rule,
+    timelapse::save_animation,
+    verbosity::status,
+};
+
+/// How many cells deep volumes are by default
+pub const DEPTH_DEFAULT: u32 = 32;
+
+/// A block of cells spreading their colors in three dimensions
+#[derive(Debug, Clone)]
+pub struct Volume {
+    // Indexed [z, y, x], front to back, top to bottom, left to right
+    alive_states: Array3<bool>,
+    color_states: Array3<RgbColor>,
+    /// Number of generations simulated so far
+    pub generation: u64,
+    colorshift: u8,
+    spread_chance: f64,
+    temperature: f64,
+    // Volumes have no border, so excluding the edges is the same as clamping at them
+    boundary: Boundary,
+    // Where the cells a cell can spread to are from it, as [layers back, rows down, columns right]
+    neighbor_offsets: Vec<[isize; 3]>,
+    palette: ColorPalette,
+    rule: Rule,
+}
+
+impl Volume {
+    /// Creates an empty (entirely dead) volume `depth` cells deep, with the size and settings of
+    /// `config`
+    pub fn new(config: &SimulationConfig, depth: usize) -> Self {
+        let shape = (depth, config.height, config.width);
+        Self {
+            alive_states: Array3::from_elem(shape, false),
+            color_states: Array3::from_elem(shape, config.theme.background()),
+            generation: 0,
+            colorshift: config.colorshift,
+            spread_chance: config.spread_chance,
+            temperature: config.temperature,
+            boundary: config.boundary,
+            neighbor_offsets: config.neighborhood.offsets(config.spread_radius),
+            palette: config.current_palette(),
+            rule: Rule::default(),
+        }
+    }
+
+    /// How many cells deep, high and wide the volume is
+    pub fn dimensions(&self) -> (usize, usize, usize) {
+        self.alive_states.dim()
+    }
+
+    /// Places a cell with a random color (from the palette, if it has colors) anywhere in the
+    /// volume
+    pub fn spawn_orphan_at_random_position(&mut self, rng: &mut StdRng) {
+        let (depth, height, width) = self.dimensions();
+        let cell = random_cell([depth, height, width], 0, rng);
+        let color = self
+            .palette
+            .orphan_color(heated(PALETTE_JITTER, self.temperature), rng);
+        self.alive_states[cell] = true;
+        self.color_states[cell] = color;
+    }
+
+    // The cells a cell at [z, y, x] can spread to. Cells near the faces of a wrapping volume
+    // reach around to the opposite face, other volumes' faces cut their neighborhoods short.
+    fn neighbors(&self, cell: [usize; 3]) -> impl Iterator<Item = [usize; 3]> + '_ {
+        let (depth, height, width) = self.dimensions();
+        self.neighbor_offsets
+            .iter()
+            .filter_map(move |&offset| self.boundary.neighbor(cell, offset, [depth, height, width]))
+    }
+
+    /// Advances the simulation by one generation, letting every live cell try to spread to one
+    /// of its dead neighbors. Returns false once every cell is alive, meaning it's finished.
+    pub fn step(&mut self, rng: &mut StdRng) -> bool {
+        let mut seen_dead_cell = false;
+        let (depth, height, width) = self.dimensions();
+        for z in 0..depth {
+            for y in 0..height {
+                for x in 0..width {
+                    if !self.alive_states[[z, y, x]] {
+                        seen_dead_cell = true;
+                        continue;
+                    }
+                    let dead_neighbors = self
+                        .neighbors([z, y, x])
+                        .filter(|cell| !self.alive_states[*cell]);
+                    let spread_chance = self.rule.spread_chance(self.spread_chance);
+                    if let Some(child) = pick_child(dead_neighbors, spread_chance, rng) {
+                        let colorshift = heated(self.colorshift, self.temperature);
+                        let parent = self.color_states[[z, y, x]];
+                        self.color_states[child] = self.rule.child_color(parent, colorshift, rng);
+                        self.alive_states[child] = true;
+                    }
+                }
+            }
+        }
+        self.generation += 1;
+        seen_dead_cell
+    }
+
+    /// The layer `z` cells back from the front as an image, a pixel per cell
+    pub fn slice(&self, z: usize) -> RgbImage {
+        let (_, height, width) = self.dimensions();
+        ImageBuffer::from_fn(width as u32, height as u32, |x, y| {
+            Rgb(self.color_states[(z, y as usize, x as usize)].as_slice())
+        })
+    }
+
+    /// Every layer from front to back as an image, each cell scaled up to `scale`x`scale` pixels
+    pub fn slices(&self, scale: u32) -> Vec<RgbImage> {
+        let (depth, height, width) = self.dimensions();
+        let size = (width as u32 * scale, height as u32 * scale);
+        (0..depth)
+            .map(|z| match scale {
+                1 => self.slice(z),
+                _ => imageops::resize(&self.slice(z), size.0, size.1, FilterType::Nearest),
+            })
+            .collect()
+    }
+}
+
+/// Grows a volume from the settings and saves it as the arguments ask
+pub fn run(args: &VolumeArgs) {
+    let mut config = match &args.config {
+        Some(path) => {
+            SimulationConfig::from_file(path, args.profile.as_deref()).unwrap_or_else(|e| {
+                eprintln!("{}", t!("run.invalid_settings", error = e));
+                process::exit(1);
+            })
+        }
+        None => SimulationConfig::default(),
+    };
+    if let Some(width) = args.width {
+        config.width = width;
+    }
+    if let Some(height) = args.height {
+        config.height = height;
+    }
+    config.resolve_auto();
+    if let Err(e) = config.validate() {
+        eprintln!("{}", t!("run.invalid_settings", error = e));
+        process::exit(1);
+    }
+    // Nothing would ever spread, so the volume would never fill up
+    if config.spread_chance <= 0.0 {
+        eprintln!("{}", t!("volume.never_fills"));
+        process::exit(1);
+    }
+
+    let bytes = image_bytes(&config, args.scale).saturating_mul(args.depth.into());
+    let what = t!("volume.size", count = args.depth);
+    if !confirm_export_size(&what, bytes, args.max_export_size, !args.yes) {
+        return;
+    }
+
+    let seed = args
+        .seed
+        .or(config.seed)
+        .unwrap_or_else(|| thread_rng().gen());
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut volume = Volume::new(&config, args.depth as usize);
+    for _ in 0..config.starting_live_cells {
+        volume.spawn_orphan_at_random_position(&mut rng);
+    }
+    status!(
+        "{}",
+        t!(
+            "volume.start",
+            width = config.width,
+            height = config.height,
+            depth = args.depth,
+            seed = seed
+        )
+    );
+    let timer = Instant::now();
+    while volume.step(&mut rng) {}
+    let elapsed = format!("{:?}", timer.elapsed());
+    status!(
+        "{}",
+        t!(
+            "volume.finished",
+            generations = volume.generation,
+            elapsed = elapsed
+        )
+    );
+
+    let slices = volume.slices(args.scale);
+    if let Some(dir) = &args.slices {
+        match save_slices(&slices, dir) {
+            Ok(()) => status!(
+                "{}",
+                t!(
+                    "volume.slices_saved",
+                    count = slices.len(),
+                    path = dir.display()
+                )
+            ),
+            Err(e) => eprintln!("{}", t!("volume.save_failed", error = e)),
+        }
+    }
+    if let Some(path) = &args.sweep {
+        let frame_time = Duration::from_secs_f64(1.0 / args.sweep_fps);
+        match save_animation(&slices, path, frame_time) {
+            Ok(()) => status!("{}", t!("volume.sweep_saved", path = path.display())),
+            Err(e) => eprintln!("{}", t!("volume.save_failed", error = e)),
+        }
+    }
+}
+
+// Saves each of `slices` in `dir` as slice_0001.png, slice_0002.png, ..., from front to back,
+// creating the directory if it's missing
+fn save_slices(slices: &[RgbImage], dir: &Path) -> ImageResult<()> {
+    fs::create_dir_all(dir)?;
+    for (z, slice) in (1..).zip(slices) {
+        slice.save(dir.join(format!("slice_{z:04}.png")))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use clap::ValueEnum;
+    use image::{codecs::gif::GifDecoder, AnimationDecoder};
+    use ndarray::s;
+
+    use super::*;
+    use crate::testing::temp_path;
+
+    fn config(boundary: Boundary) -> SimulationConfig {
+        SimulationConfig {
+            width: 6,
+            height: 5,
+            spread_chance: 1.0,
+            boundary,
+            ..SimulationConfig::default()
+        }
+    }
+
+    #[test]
+    fn seeded_volumes_fill_up() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut volume = Volume::new(&config(Boundary::Exclude), 4);
+        for _ in 0..3 {
+            volume.spawn_orphan_at_random_position(&mut rng);
+        }
+        while volume.step(&mut rng) {}
+        assert!(volume.alive_states.iter().all(|&alive| alive));
+        assert!(volume.generation > 1);
+    }
+
+    #[test]
+    fn only_wrapping_volumes_grow_through_their_faces() {
+        for boundary in Boundary::value_variants() {
+            // The back layer is alive, and is stepped last, so its children have no time to spread
+            // further in the generation: only wrapping around reaches the front layer
+            let mut volume = Volume::new(&config(*boundary), 4);
+            volume.alive_states.slice_mut(s![3, .., ..]).fill(true);
+            volume.step(&mut StdRng::seed_from_u64(7));
+            let front = volume
+                .alive_states
+                .slice(s![0, .., ..])
+                .iter()
+                .any(|&alive| alive);
+            assert_eq!(front, *boundary == Boundary::Wrap, "{boundary}");
+        }
+    }
+
+    #[test]
+    fn slices_and_sweeps_hold_every_layer() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut volume = Volume::new(&config(Boundary::Clamp), 4);
+        volume.spawn_orphan_at_random_position(&mut rng);
+        while volume.step(&mut rng) {}
+        let slices = volume.slices(3);
+        assert_eq!(slices.len(), 4);
+
+        let dir = temp_path("volume_slices");
+        save_slices(&slices, &dir).unwrap();
+        for z in 1..=4 {
+            let slice = image::open(dir.join(format!("slice_{z:04}.png"))).unwrap();
+            assert_eq!((slice.width(), slice.height()), (18, 15), "slice {z}");
+        }
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 4);
+        fs::remove_dir_all(dir).unwrap();
+
+        let path = temp_path("volume_sweep.gif");
+        save_animation(&slices, &path, Duration::from_millis(100)).unwrap();
+        let decoder = GifDecoder::new(File::open(&path).unwrap()).unwrap();
+        let frames = decoder.into_frames().collect_frames().unwrap();
+        fs::remove_file(path).unwrap();
+        assert_eq!(frames.len(), 4);
+        assert!(frames
+            .iter()
+            .all(|frame| frame.buffer().dimensions() == (18, 15)));
+    }
+}
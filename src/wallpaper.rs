@@ -0,0 +1,120 @@
+// Setting saved images as the desktop background, through whatever the desktop itself offers:
+// AppleScript on macOS, the Win32 API (by way of PowerShell) on Windows, and on Linux the settings
+// of GNOME-like desktops, KDE Plasma's or XFCE's tools, or feh for plain window managers.
+// Nothing has to be installed for it on macOS, Windows, GNOME or KDE.
+//
+// This deliberately doesn't use the `wallpaper` crate: it isn't available to our offline builds,
+// and what it does for each desktop comes down to the one command here, which doesn't justify
+// its platform dependencies.
+
+use std::{
+    env,
+    io::ErrorKind,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+// The environment variable the image's path is handed to scripts in, so it doesn't need quoting
+const PATH_VARIABLE: &str = "SPREADING_COLORS_WALLPAPER_PATH";
+
+/// Sets the image at `path` as the desktop background, or says why it couldn't be
+pub fn set(path: &Path) -> Result<(), String> {
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("{} can't be found: {e}", path.display()))?;
+    let path = path.to_str().ok_or("the image's path isn't valid UTF-8")?;
+    if cfg!(target_os = "macos") {
+        run(Command::new("osascript").args([
+            "-e",
+            "on run argv",
+            "-e",
+            "tell application \"System Events\" to tell every desktop to set picture to item 1 of argv",
+            "-e",
+            "end run",
+            path,
+        ]))
+    } else if cfg!(windows) {
+        // SPI_SETDESKWALLPAPER (20), saved to the user's profile and announced to other programs (3)
+        let script = format!(
+            "Add-Type -TypeDefinition 'using System.Runtime.InteropServices; public class Desktop {{ \
+             [DllImport(\"user32.dll\", CharSet = CharSet.Unicode)] public static extern bool \
+             SystemParametersInfo(int action, int param, string file, int flags); }}'; \
+             if (-not [Desktop]::SystemParametersInfo(20, 0, $env:{PATH_VARIABLE}, 3)) {{ exit 1 }}"
+        );
+        run(Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+            .env(PATH_VARIABLE, path))
+    } else {
+        set_on_linux(path)
+    }
+}
+
+// Sets the background of whichever desktop is running, by the name it gives itself
+fn set_on_linux(path: &str) -> Result<(), String> {
+    let desktop = env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if desktop.contains("kde") {
+        return run(Command::new("plasma-apply-wallpaperimage").arg(path));
+    }
+    if desktop.contains("xfce") {
+        // Every monitor and workspace has its own setting, all named after `last-image`
+        let properties = Command::new("xfconf-query")
+            .args(["-c", "xfce4-desktop", "-l"])
+            .output()
+            .map_err(|e| missing("xfconf-query", e.kind()))?;
+        let properties = String::from_utf8_lossy(&properties.stdout);
+        for property in properties.lines().filter(|p| p.ends_with("/last-image")) {
+            run(Command::new("xfconf-query").args([
+                "-c",
+                "xfce4-desktop",
+                "-p",
+                property,
+                "-s",
+                path,
+            ]))?;
+        }
+        return Ok(());
+    }
+    if ["gnome", "unity", "cinnamon", "budgie", "pantheon"]
+        .iter()
+        .any(|name| desktop.contains(name))
+    {
+        let uri = format!("file://{path}");
+        let schema = if desktop.contains("cinnamon") {
+            "org.cinnamon.desktop.background"
+        } else {
+            "org.gnome.desktop.background"
+        };
+        run(Command::new("gsettings").args(["set", schema, "picture-uri", &uri]))?;
+        // Newer GNOME versions keep a separate background for dark mode, older ones don't have it
+        let _ = run(Command::new("gsettings").args(["set", schema, "picture-uri-dark", &uri]));
+        return Ok(());
+    }
+    run(Command::new("feh").args(["--bg-fill", path]))
+}
+
+// Runs `command`, failing if it can't be run or doesn't succeed
+fn run(command: &mut Command) -> Result<(), String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| missing(&program, e.kind()))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        let error = String::from_utf8_lossy(&output.stderr);
+        Err(format!("{program} failed: {}", error.trim()))
+    }
+}
+
+// Why `program` couldn't be run
+fn missing(program: &str, kind: ErrorKind) -> String {
+    match kind {
+        ErrorKind::NotFound => {
+            format!("{program} wasn't found, it's needed to set the wallpaper here")
+        }
+        kind => format!("{program} couldn't be run: {kind}"),
+    }
+}
@@ -0,0 +1,199 @@
+// A headless render worker. Several workers (possibly on different machines sharing a network
+// drive) can take jobs from the same directory: a job is claimed by atomically moving it into
+// `<jobs>/claimed`, so only one worker ever renders it.
+//
+// Jobs directory layout:
+//   <jobs>/*.json, <jobs>/*.toml   pending jobs, each one a simulation config
+//   <jobs>/claimed/                jobs being rendered right now
+//   <jobs>/done/                   jobs that rendered successfully
+//   <jobs>/failed/                 jobs that couldn't be rendered, next to a `.error.txt` file
+// Each finished job `name` produces `<results>/name.png` and the manifest
+// `<results>/name.png.manifest.json`.
+
+use std::{
+    collections::HashSet,
+    env, fs, io,
+    path::{Path, PathBuf},
+    process, thread,
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+
+use crate::{
+    cli::WorkerArgs,
+    config::SimulationConfig,
+    export::{write_image, ImageOptions},
+    i18n::t,
+    manifest::{manifest_path, RunManifest},
+    simulation::render,
+    verbosity::status,
+};
+
+pub fn run(args: &WorkerArgs) {
+    let worker_id = worker_id();
+    status!(
+        "{}",
+        t!("worker.start", id = worker_id, dir = args.jobs.display())
+    );
+
+    // Jobs this worker couldn't claim for a reason other than losing a race, left alone from then on
+    let mut skipped = HashSet::new();
+
+    loop {
+        let jobs = match pending_jobs(&args.jobs) {
+            Ok(jobs) => jobs
+                .into_iter()
+                .filter(|job| !skipped.contains(job))
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                eprintln!(
+                    "{}",
+                    t!(
+                        "worker.unreadable_jobs",
+                        dir = args.jobs.display(),
+                        error = e
+                    )
+                );
+                process::exit(1);
+            }
+        };
+
+        if jobs.is_empty() {
+            if !args.watch {
+                status!("{}", t!("worker.no_jobs"));
+                return;
+            }
+            thread::sleep(Duration::from_secs(args.poll_interval));
+            continue;
+        }
+
+        for job in jobs {
+            match claim(&args.jobs, &job) {
+                Ok(claimed) => process_job(&claimed, &args.jobs, &args.results, &worker_id),
+                // Another worker claimed this job since the directory was listed
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    eprintln!(
+                        "{}",
+                        t!("worker.claim_failed", path = job.display(), error = e)
+                    );
+                    skipped.insert(job);
+                }
+            }
+        }
+    }
+}
+
+// Identifies this worker in manifests, e.g. "render-box-3-12345"
+fn worker_id() -> String {
+    let host = env::var("HOSTNAME")
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .unwrap_or("worker".to_string());
+    format!("{host}-{}", process::id())
+}
+
+// Lists the job files waiting in `dir`, oldest name first
+fn pending_jobs(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut jobs: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "json" || ext == "toml")
+        })
+        .collect();
+    jobs.sort();
+    Ok(jobs)
+}
+
+// Moves a job into `claimed/`. Renaming is atomic, so exactly one worker succeeds.
+fn claim(jobs_dir: &Path, job: &Path) -> io::Result<PathBuf> {
+    let claimed_dir = jobs_dir.join("claimed");
+    fs::create_dir_all(&claimed_dir)?;
+    let claimed = claimed_dir.join(job.file_name().unwrap());
+    fs::rename(job, &claimed)?;
+    Ok(claimed)
+}
+
+// Moves a claimed job into `done/` or `failed/`
+fn finish(jobs_dir: &Path, claimed: &Path, outcome: &str) -> io::Result<PathBuf> {
+    let dir = jobs_dir.join(outcome);
+    fs::create_dir_all(&dir)?;
+    let finished = dir.join(claimed.file_name().unwrap());
+    fs::rename(claimed, &finished)?;
+    Ok(finished)
+}
+
+fn process_job(claimed: &Path, jobs_dir: &Path, results_dir: &Path, worker_id: &str) {
+    let name = claimed.file_stem().unwrap().to_string_lossy().to_string();
+    status!("{}", t!("worker.rendering", name = name));
+
+    match render_job(claimed, &name, results_dir, worker_id) {
+        Ok(image) => {
+            status!(
+                "{}",
+                t!("worker.finished", name = name, path = image.display())
+            );
+            if let Err(e) = finish(jobs_dir, claimed, "done") {
+                eprintln!(
+                    "{}",
+                    t!("worker.move_failed", name = name, dir = "done", error = e)
+                );
+            }
+        }
+        Err(message) => {
+            eprintln!("{}", t!("worker.failed", name = name, error = message));
+            match finish(jobs_dir, claimed, "failed") {
+                Ok(failed) => {
+                    let _ = fs::write(failed.with_extension("error.txt"), message + "\n");
+                }
+                Err(e) => eprintln!(
+                    "{}",
+                    t!("worker.move_failed", name = name, dir = "failed", error = e)
+                ),
+            }
+        }
+    }
+}
+
+// Renders one job, returning the path of the finished image
+fn render_job(
+    job: &Path,
+    name: &str,
+    results_dir: &Path,
+    worker_id: &str,
+) -> Result<PathBuf, String> {
+    let mut config = SimulationConfig::from_file(job, None).map_err(|e| e.to_string())?;
+    // Record the seed, theme and palette that are actually used, so the manifest can reproduce the image
+    let seed = *config.seed.get_or_insert_with(|| thread_rng().gen());
+    config.resolve_auto();
+
+    let timer = Instant::now();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let grid = render(&config, &mut rng, false);
+    let runtime = timer.elapsed();
+
+    let image = results_dir.join(format!("{name}.png"));
+    write_image(&grid, &image, &ImageOptions::default())
+        .map_err(|e| format!("couldn't save the image: {e}"))?;
+
+    RunManifest {
+        crate_version: env!("CARGO_PKG_VERSION"),
+        image: &image,
+        config: &config,
+        generations: grid.generation,
+        runtime_secs: runtime.as_secs_f64(),
+        job: Some(name),
+        worker: Some(worker_id),
+        randomized: &[],
+    }
+    .write(&manifest_path(&image))
+    .map_err(|e| format!("couldn't write the manifest: {e}"))?;
+
+    Ok(image)
+}